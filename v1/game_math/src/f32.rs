@@ -28,6 +28,10 @@ impl Number for f32{
         f32_cos(*self)
     }
 
+    fn atan2(&self, other: Self) -> Self {
+        f32_atan2(*self, other)
+    }
+
     fn min(&self, other: Self) -> Self{
         if *self < other {
             *self
@@ -43,6 +47,10 @@ impl Number for f32{
             other
         }
     }
+
+    fn floor_to_int(&self) -> i32 {
+        f32_floor(*self) as i32
+    }
 }
 
 // These functions are to 'overload' the other ones and to prevent infinite recursion calls.
@@ -53,11 +61,21 @@ fn f32_abs(f: f32) -> f32{
 }
 
 
+fn f32_floor(f: f32) -> f32{
+    f.floor()
+}
+
+
 fn f32_cos(f: f32) -> f32{
     f.cos()
 }
 
 
+fn f32_atan2(y: f32, x: f32) -> f32{
+    y.atan2(x)
+}
+
+
 fn f32_sin(f: f32) -> f32{
     f.sin()
 }
@@ -1,36 +1,198 @@
 use crate::Number;
 
-type Fix = i32;
-
 implement_types!(Fix);
 
+/// The number of bits used for the fractional part of a `Fix`.
+pub const FRACTIONAL_BITS: u32 = 16;
+
+/// The number of entries in a quarter wave of the trig lookup tables.
+const LUT_SIZE: usize = 1024;
+
+/// Bits of precision used when generating the lookup tables.
+const LUT_PRECISION: u32 = 30;
+
+/// PI / 2 with `LUT_PRECISION` fractional bits.
+const LUT_HALF_PI: i64 = 1_686_629_713;
+
+/// sin(theta) for theta in 0..=PI/2. Generated at compile time so every platform uses the same values.
+const SINE_LUT: [i32; LUT_SIZE + 1] = generate_sine_lut();
+
+/// atan(t) for t in 0..=1. Generated at compile time so every platform uses the same values.
+const ATAN_LUT: [i32; LUT_SIZE + 1] = generate_atan_lut();
+
+/// A deterministic Q16.16 fixed point number. All operations are done with integer math so results are bit-exact across platforms.
+/// Operators saturate at the min and max values. Wrapping variants are available where overflow is expected.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Fix(i32);
+
+impl Fix {
+    pub const ZERO: Self = Self(0);
+    pub const ONE: Self = Self(1 << FRACTIONAL_BITS);
+    pub const MIN: Self = Self(i32::MIN);
+    pub const MAX: Self = Self(i32::MAX);
+    /// The smallest representable positive value.
+    pub const EPSILON: Self = Self(1);
+    pub const PI: Self = Self(205_887);
+    pub const HALF_PI: Self = Self(102_944);
+    pub const TWO_PI: Self = Self(411_775);
+
+    /// Creates a Fix from its raw bits.
+    pub const fn from_raw(raw: i32) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw bits of the Fix.
+    pub const fn raw(&self) -> i32 {
+        self.0
+    }
+
+    /// Creates a Fix from an integer, saturating if it is out of range.
+    pub const fn from_int(int: i32) -> Self {
+        Self(saturate((int as i64) << FRACTIONAL_BITS))
+    }
+
+    /// Creates a Fix from a ratio of two integers. Useful for constants, as it avoids floats.
+    pub fn from_ratio(numerator: i32, denominator: i32) -> Self {
+        Self::from_int(numerator) / Self::from_int(denominator)
+    }
+
+    /// Creates a Fix from an f32. Only intended for tools and debugging, as floats are not deterministic across platforms.
+    pub fn from_f32(f: f32) -> Self {
+        Self(saturate((f * Self::ONE.0 as f32) as i64))
+    }
+
+    /// Converts to an f32. Only intended for rendering and debugging.
+    pub fn to_f32(&self) -> f32 {
+        self.0 as f32 / Self::ONE.0 as f32
+    }
+
+    /// Returns the integer part, rounded towards negative infinity.
+    pub const fn floor_to_int(&self) -> i32 {
+        self.0 >> FRACTIONAL_BITS
+    }
+
+    pub fn saturating_add(self, other: Self) -> Self {
+        Self(self.0.saturating_add(other.0))
+    }
+
+    pub fn saturating_sub(self, other: Self) -> Self {
+        Self(self.0.saturating_sub(other.0))
+    }
+
+    pub fn saturating_mul(self, other: Self) -> Self {
+        Self(saturate(mul_raw(self.0, other.0)))
+    }
+
+    /// Divides the two numbers. Division by zero saturates towards the sign of the numerator.
+    pub fn saturating_div(self, other: Self) -> Self {
+        match div_raw(self.0, other.0) {
+            Some(raw) => Self(saturate(raw)),
+            None => Self::div_by_zero(self),
+        }
+    }
+
+    pub fn wrapping_add(self, other: Self) -> Self {
+        Self(self.0.wrapping_add(other.0))
+    }
+
+    pub fn wrapping_sub(self, other: Self) -> Self {
+        Self(self.0.wrapping_sub(other.0))
+    }
+
+    pub fn wrapping_mul(self, other: Self) -> Self {
+        Self(mul_raw(self.0, other.0) as i32)
+    }
+
+    /// Divides the two numbers. Division by zero saturates towards the sign of the numerator.
+    pub fn wrapping_div(self, other: Self) -> Self {
+        match div_raw(self.0, other.0) {
+            Some(raw) => Self(raw as i32),
+            None => Self::div_by_zero(self),
+        }
+    }
+
+    /// Returns the four quadrant arctangent of y / x, in the range -PI..=PI.
+    pub fn atan2(y: Self, x: Self) -> Self {
+        if x.0 == 0 && y.0 == 0 {
+            return Self::ZERO;
+        }
+
+        let abs_x = (x.0 as i64).abs();
+        let abs_y = (y.0 as i64).abs();
+
+        // Keep the ratio within 0..=1 so it may index into the table.
+        let angle = {
+            if abs_y <= abs_x {
+                atan_lut((abs_y << FRACTIONAL_BITS) / abs_x)
+            } else {
+                Self::HALF_PI.0 - atan_lut((abs_x << FRACTIONAL_BITS) / abs_y)
+            }
+        };
+
+        let angle = if x.0 < 0 { Self::PI.0 - angle } else { angle };
+        let angle = if y.0 < 0 { -angle } else { angle };
+
+        Self(angle)
+    }
+
+    fn div_by_zero(numerator: Self) -> Self {
+        if numerator.0 > 0 {
+            Self::MAX
+        } else if numerator.0 < 0 {
+            Self::MIN
+        } else {
+            Self::ZERO
+        }
+    }
+
+    /// Converts the angle to a position along the full lookup table, with `FRACTIONAL_BITS` fractional bits.
+    fn lut_position(&self) -> i64 {
+        let theta = self.0.rem_euclid(Self::TWO_PI.0) as i64;
+        let full_wave = (4 * LUT_SIZE as i64) << FRACTIONAL_BITS;
+
+        ((theta << FRACTIONAL_BITS) * 4 * LUT_SIZE as i64 / Self::TWO_PI.0 as i64) % full_wave
+    }
+}
+
 impl Number for Fix {
     fn i32(real_number: i32) -> Self {
-        real_number as Self
+        Self::from_int(real_number)
     }
 
     fn abs(&self) -> Self {
-        if *self < 0 {
-            -(*self)
-        } else {
-            *self
-        }
+        Self(self.0.saturating_abs())
     }
 
+    /// A bit-exact square root, rounded down. Negative numbers return 0.
     fn sqrt(&self) -> Self {
-        unimplemented!("Need to implement fixed point sqrt")
+        if self.0 <= 0 {
+            return Self::ZERO;
+        }
+
+        let root = isqrt((self.0 as u64) << FRACTIONAL_BITS);
+        Self(root as i32)
     }
 
     fn sqrd(&self) -> Self {
-        self * self
+        *self * *self
     }
 
     fn sin(&self) -> Self {
-        unimplemented!("Need to implement fixed point sin")
+        Self(sine_from_lut_position(self.lut_position()))
     }
 
     fn cos(&self) -> Self {
-        unimplemented!("Need to implement fixed point cos")
+        // Cosine is sine shifted by a quarter wave.
+        let quarter_wave = (LUT_SIZE as i64) << FRACTIONAL_BITS;
+        let full_wave = 4 * quarter_wave;
+
+        Self(sine_from_lut_position(
+            (self.lut_position() + quarter_wave) % full_wave,
+        ))
+    }
+
+    fn atan2(&self, other: Self) -> Self {
+        Fix::atan2(*self, other)
     }
 
     fn min(&self, other: Self) -> Self {
@@ -48,4 +210,367 @@ impl Number for Fix {
             *self
         }
     }
+
+    fn floor_to_int(&self) -> i32 {
+        Fix::floor_to_int(self)
+    }
+}
+
+impl From<i32> for Fix {
+    fn from(int: i32) -> Self {
+        Self::from_int(int)
+    }
+}
+
+impl std::ops::Add for Fix {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        self.saturating_add(other)
+    }
+}
+
+impl std::ops::AddAssign for Fix {
+    fn add_assign(&mut self, other: Self) {
+        *self = *self + other;
+    }
+}
+
+impl std::ops::Sub for Fix {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        self.saturating_sub(other)
+    }
+}
+
+impl std::ops::SubAssign for Fix {
+    fn sub_assign(&mut self, other: Self) {
+        *self = *self - other;
+    }
+}
+
+impl std::ops::Mul for Fix {
+    type Output = Self;
+
+    fn mul(self, other: Self) -> Self {
+        self.saturating_mul(other)
+    }
+}
+
+impl std::ops::MulAssign for Fix {
+    fn mul_assign(&mut self, other: Self) {
+        *self = *self * other;
+    }
+}
+
+impl std::ops::Div for Fix {
+    type Output = Self;
+
+    fn div(self, other: Self) -> Self {
+        self.saturating_div(other)
+    }
+}
+
+impl std::ops::DivAssign for Fix {
+    fn div_assign(&mut self, other: Self) {
+        *self = *self / other;
+    }
+}
+
+impl std::ops::Neg for Fix {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self(self.0.saturating_neg())
+    }
+}
+
+/// Clamps a wide value to the range of a Fix.
+const fn saturate(raw: i64) -> i32 {
+    if raw > i32::MAX as i64 {
+        i32::MAX
+    } else if raw < i32::MIN as i64 {
+        i32::MIN
+    } else {
+        raw as i32
+    }
+}
+
+fn mul_raw(a: i32, b: i32) -> i64 {
+    (a as i64 * b as i64) >> FRACTIONAL_BITS
+}
+
+fn div_raw(a: i32, b: i32) -> Option<i64> {
+    if b == 0 {
+        return None;
+    }
+
+    Some(((a as i64) << FRACTIONAL_BITS) / b as i64)
+}
+
+/// Integer square root, rounded down.
+const fn isqrt(n: u64) -> u64 {
+    let mut remainder = n;
+    let mut root = 0;
+    let mut bit = 1 << 62;
+
+    while bit > n {
+        bit >>= 2;
+    }
+
+    while bit != 0 {
+        if remainder >= root + bit {
+            remainder -= root + bit;
+            root = (root >> 1) + bit;
+        } else {
+            root >>= 1;
+        }
+        bit >>= 2;
+    }
+
+    root
+}
+
+/// Linearly interpolates a table entry. The position has `FRACTIONAL_BITS` fractional bits.
+fn interpolate_lut(lut: &[i32; LUT_SIZE + 1], position: i64) -> i32 {
+    let index = (position >> FRACTIONAL_BITS) as usize;
+    let fraction = position & ((1 << FRACTIONAL_BITS) - 1);
+
+    if index >= LUT_SIZE {
+        return lut[LUT_SIZE];
+    }
+
+    let a = lut[index] as i64;
+    let b = lut[index + 1] as i64;
+
+    (a + (((b - a) * fraction) >> FRACTIONAL_BITS)) as i32
+}
+
+/// Looks up the sine for a position along the full wave. Only a quarter wave is stored, so the rest is mirrored.
+fn sine_from_lut_position(position: i64) -> i32 {
+    let quarter_wave = (LUT_SIZE as i64) << FRACTIONAL_BITS;
+    let quadrant = position / quarter_wave;
+    let position = position % quarter_wave;
+
+    match quadrant {
+        0 => interpolate_lut(&SINE_LUT, position),
+        1 => interpolate_lut(&SINE_LUT, quarter_wave - position),
+        2 => -interpolate_lut(&SINE_LUT, position),
+        _ => -interpolate_lut(&SINE_LUT, quarter_wave - position),
+    }
+}
+
+/// Looks up atan(t) for t in 0..=1 with `FRACTIONAL_BITS` fractional bits.
+fn atan_lut(t: i64) -> i32 {
+    interpolate_lut(&ATAN_LUT, t * LUT_SIZE as i64)
+}
+
+/// Multiplies two numbers with `LUT_PRECISION` fractional bits.
+const fn lut_mul(a: i64, b: i64) -> i64 {
+    (a * b) >> LUT_PRECISION
+}
+
+/// Converts a number with `LUT_PRECISION` fractional bits to a rounded Fix.
+const fn lut_to_raw(n: i64) -> i32 {
+    let shift = LUT_PRECISION - FRACTIONAL_BITS;
+    ((n + (1 << (shift - 1))) >> shift) as i32
+}
+
+/// Taylor series for sine. Accurate for 0..=PI/2.
+const fn lut_sine(theta: i64) -> i64 {
+    let theta_sqrd = lut_mul(theta, theta);
+    let mut term = theta;
+    let mut sum = theta;
+    let mut k = 1;
+
+    while k < 10 {
+        term = -lut_mul(term, theta_sqrd) / ((2 * k) * (2 * k + 1));
+        sum += term;
+        k += 1;
+    }
+
+    sum
+}
+
+/// Taylor series for arctangent. Halves the angle twice first so the series converges quickly for 0..=1.
+const fn lut_atan(t: i64) -> i64 {
+    let one = 1 << LUT_PRECISION;
+
+    // atan(t) = 2 * atan(t / (1 + sqrt(1 + t^2)))
+    let mut t = t;
+    let mut halvings = 0;
+    while halvings < 2 {
+        let root = isqrt(((one + lut_mul(t, t)) as u64) << LUT_PRECISION) as i64;
+        t = (t << LUT_PRECISION) / (one + root);
+        halvings += 1;
+    }
+
+    let t_sqrd = lut_mul(t, t);
+    let mut power = t;
+    let mut sum = t;
+    let mut k = 1;
+
+    while k < 12 {
+        power = -lut_mul(power, t_sqrd);
+        sum += power / (2 * k + 1);
+        k += 1;
+    }
+
+    sum << halvings
+}
+
+const fn generate_sine_lut() -> [i32; LUT_SIZE + 1] {
+    let mut lut = [0; LUT_SIZE + 1];
+    let mut i = 0;
+
+    while i <= LUT_SIZE {
+        let theta = LUT_HALF_PI * i as i64 / LUT_SIZE as i64;
+        lut[i] = lut_to_raw(lut_sine(theta));
+        i += 1;
+    }
+
+    lut
+}
+
+const fn generate_atan_lut() -> [i32; LUT_SIZE + 1] {
+    let mut lut = [0; LUT_SIZE + 1];
+    let mut i = 0;
+
+    while i <= LUT_SIZE {
+        let t = (1 << LUT_PRECISION) * i as i64 / LUT_SIZE as i64;
+        lut[i] = lut_to_raw(lut_atan(t));
+        i += 1;
+    }
+
+    lut
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The max error allowed when comparing to f32 trig functions.
+    const TRIG_TOLERANCE: f32 = 0.0005;
+
+    fn assert_close(expected: f32, actual: Fix, tolerance: f32) {
+        let difference = (expected - actual.to_f32()).abs();
+        assert!(
+            difference <= tolerance,
+            "expected {}, got {} ({:?})",
+            expected,
+            actual.to_f32(),
+            actual
+        );
+    }
+
+    #[test]
+    fn fix_arithmetic() {
+        let a = Fix::from_ratio(3, 2);
+        let b = Fix::from_int(2);
+
+        assert_eq!(Fix::from_ratio(7, 2), a + b);
+        assert_eq!(Fix::from_ratio(-1, 2), a - b);
+        assert_eq!(Fix::from_int(3), a * b);
+        assert_eq!(Fix::from_ratio(3, 4), a / b);
+        assert_eq!(Fix::from_ratio(-3, 2), -a);
+        assert_eq!(Fix::from_ratio(3, 2), (-a).abs());
+        assert_eq!(-2, Fix::from_ratio(-3, 2).floor_to_int());
+    }
+
+    #[test]
+    fn fix_saturates() {
+        assert_eq!(Fix::MAX, Fix::MAX + Fix::ONE);
+        assert_eq!(Fix::MIN, Fix::MIN - Fix::ONE);
+        assert_eq!(Fix::MAX, Fix::from_int(30_000) * Fix::from_int(30_000));
+        assert_eq!(Fix::MIN, Fix::from_int(-30_000) * Fix::from_int(30_000));
+        assert_eq!(Fix::MAX, Fix::from_int(30_000) / Fix::from_ratio(1, 100));
+        assert_eq!(Fix::MAX, -Fix::MIN);
+        assert_eq!(Fix::MAX, Fix::from_int(i32::MAX));
+    }
+
+    #[test]
+    fn fix_wraps() {
+        assert_eq!(Fix::MIN, Fix::MAX.wrapping_add(Fix::EPSILON));
+        assert_eq!(Fix::MAX, Fix::MIN.wrapping_sub(Fix::EPSILON));
+        assert_eq!(
+            Fix::from_raw((30_000i64 * 30_000 * 65_536) as i32),
+            Fix::from_int(30_000).wrapping_mul(Fix::from_int(30_000))
+        );
+    }
+
+    #[test]
+    fn fix_div_by_zero_saturates() {
+        assert_eq!(Fix::MAX, Fix::ONE / Fix::ZERO);
+        assert_eq!(Fix::MIN, -Fix::ONE / Fix::ZERO);
+        assert_eq!(Fix::ZERO, Fix::ZERO / Fix::ZERO);
+    }
+
+    #[test]
+    fn fix_sqrt_is_bit_exact() {
+        assert_eq!(Fix::from_int(2), Fix::from_int(4).sqrt());
+        assert_eq!(Fix::from_int(12), Fix::from_int(144).sqrt());
+        assert_eq!(Fix::from_ratio(1, 2), Fix::from_ratio(1, 4).sqrt());
+        assert_eq!(Fix::ZERO, Fix::from_int(-4).sqrt());
+
+        // floor(sqrt(2) * 2^16)
+        assert_eq!(Fix::from_raw(92_681), Fix::from_int(2).sqrt());
+        // floor(sqrt(i32::MAX * 2^16))
+        assert_eq!(Fix::from_raw(11_863_283), Fix::MAX.sqrt());
+    }
+
+    #[test]
+    fn fix_sine_lut_matches_known_values() {
+        assert_eq!(0, SINE_LUT[0]);
+        assert_eq!(Fix::ONE.raw(), SINE_LUT[LUT_SIZE]);
+        // round(sin(PI / 4) * 2^16)
+        assert_eq!(46_341, SINE_LUT[LUT_SIZE / 2]);
+
+        assert_eq!(0, ATAN_LUT[0]);
+        // round(atan(1) * 2^16) = round(PI / 4 * 2^16)
+        assert_eq!(51_472, ATAN_LUT[LUT_SIZE]);
+    }
+
+    #[test]
+    fn fix_sin_cos() {
+        let mut theta = -10.;
+        while theta < 10. {
+            let fix = Fix::from_f32(theta);
+            let f = fix.to_f32();
+
+            assert_close(f.sin(), fix.sin(), TRIG_TOLERANCE);
+            assert_close(f.cos(), fix.cos(), TRIG_TOLERANCE);
+
+            theta += 0.01;
+        }
+
+        assert_eq!(Fix::ZERO, Fix::ZERO.sin());
+        assert_eq!(Fix::ONE, Fix::ZERO.cos());
+        assert_eq!(Fix::ONE, Fix::HALF_PI.sin());
+    }
+
+    #[test]
+    fn fix_atan2() {
+        let mut theta: f32 = -3.;
+        while theta < 3. {
+            let y = Fix::from_f32(theta.sin() * 5.);
+            let x = Fix::from_f32(theta.cos() * 5.);
+
+            assert_close(theta, Fix::atan2(y, x), TRIG_TOLERANCE * 2.);
+
+            theta += 0.01;
+        }
+
+        assert_eq!(Fix::ZERO, Fix::atan2(Fix::ZERO, Fix::ZERO));
+        assert_eq!(Fix::ZERO, Fix::atan2(Fix::ZERO, Fix::ONE));
+        assert_eq!(Fix::HALF_PI, Fix::atan2(Fix::ONE, Fix::ZERO));
+        assert_eq!(-Fix::HALF_PI, Fix::atan2(-Fix::ONE, Fix::ZERO));
+        assert_eq!(Fix::PI, Fix::atan2(Fix::ZERO, -Fix::ONE));
+    }
+
+    #[test]
+    fn fix_vectors_use_fixed_math() {
+        let v = Vec3::new(Fix::from_int(3), Fix::from_int(4), Fix::ZERO);
+        assert_eq!(Fix::from_int(5), v.len());
+    }
 }
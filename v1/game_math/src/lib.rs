@@ -5,9 +5,9 @@ pub mod f32;
 pub mod fix;
 pub mod mat4;
 pub mod quaternion;
-pub mod vec2;
-pub mod vec3;
-pub mod vec4;
+pub mod random;
+pub mod shapes;
+pub mod vectors;
 
 /// A list of all features a number must provide.
 pub trait Number:
@@ -61,10 +61,14 @@ pub trait Number:
     fn sin(&self) -> Self;
     /// Returns the cosine of a number.
     fn cos(&self) -> Self;
+    /// Returns the four quadrant arctangent of self (y) and other (x).
+    fn atan2(&self, other: Self) -> Self;
     /// Returns the min of a number.
     fn min(&self, other: Self) -> Self;
     /// Returns the max of a number.
     fn max(&self, other: Self) -> Self;
+    /// Returns the number rounded towards negative infinity as an i32, saturating if it is out of range.
+    fn floor_to_int(&self) -> i32;
 }
 
 /// Converts a data structure to a raw type. Useful for uploading to the GPU.
@@ -5,9 +5,16 @@ macro_rules! implement_types {
         pub type Num = $n;
         pub use crate::RawConverter;
         pub type Mat4 = crate::mat4::Mat4<Num>;
-        pub type Vec2 = crate::vec2::Vec2<Num>;
-        pub type Vec3 = crate::vec3::Vec3<Num>;
-        pub type Vec4 = crate::vec4::Vec4<Num>;
+        pub type Vec2 = crate::vectors::Vec2<Num>;
+        pub type Vec3 = crate::vectors::Vec3<Num>;
+        pub type Vec4 = crate::vectors::Vec4<Num>;
         pub type Quaternion = crate::quaternion::Quaternion<Num>;
+        pub type Aabb = crate::shapes::Aabb<Num>;
+        pub type Capsule = crate::shapes::Capsule<Num>;
+        pub type Plane = crate::shapes::Plane<Num>;
+        pub type Ray = crate::shapes::Ray<Num>;
+        pub type Segment = crate::shapes::Segment<Num>;
+        pub type Sphere = crate::shapes::Sphere<Num>;
+        pub type Triangle = crate::shapes::Triangle<Num>;
     };
 }
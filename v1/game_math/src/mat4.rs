@@ -1,5 +1,10 @@
-use super::{vec3::Vec3, vec4::Vec4, Number, RawConverter};
+use super::{
+    vectors::{Vec3, Vec4},
+    Number, RawConverter,
+};
 
+/// A 4x4 matrix. Stored column major, so `m[i]` is column `i` and the raw layout matches what the GPU expects.
+/// Vectors are treated as columns, so `a * b` applies `b` first.
 #[derive(Copy, Clone, PartialEq, Debug)]
 pub struct Mat4<N>([Vec4<N>; 4])
 where
@@ -14,7 +19,7 @@ where
         Self::identity()
     }
 
-    /// Creates a new Mat4.
+    /// Creates a new Mat4 from its columns.
     pub fn new(m0: Vec4<N>, m1: Vec4<N>, m2: Vec4<N>, m3: Vec4<N>) -> Self {
         Self([m0, m1, m2, m3])
     }
@@ -36,16 +41,229 @@ where
         Self::i32(1)
     }
 
+    /// Creates a translation matrix.
+    pub fn from_translation(v: Vec3<N>) -> Self {
+        let mut m = Self::identity().to_raw();
+        m[3] = [v.x, v.y, v.z, N::i32(1)];
+
+        Self::from_raw(m)
+    }
+
+    /// Creates a scale matrix.
+    pub fn from_scale(scale: Vec3<N>) -> Self {
+        let mut m = Self::identity().to_raw();
+        m[0][0] = scale.x;
+        m[1][1] = scale.y;
+        m[2][2] = scale.z;
+
+        Self::from_raw(m)
+    }
+
+    /// Returns the matrix with a translation applied before it.
     pub fn translate(mat: Self, v: Vec3<N>) -> Self {
-        unimplemented!();
+        mat * Self::from_translation(v)
     }
 
+    /// Returns the matrix with a scale applied before it.
     pub fn scale(&self, scale: Vec3<N>) -> Self {
-        unimplemented!();
+        *self * Self::from_scale(scale)
+    }
+
+    /// Swaps the rows and columns of the matrix.
+    pub fn transpose(&self) -> Self {
+        let m = self.to_raw();
+        let mut t = m;
+
+        for (col, column) in m.iter().enumerate() {
+            for (row, value) in column.iter().enumerate() {
+                t[row][col] = *value;
+            }
+        }
+
+        Self::from_raw(t)
+    }
+
+    /// The determinant of the matrix.
+    pub fn determinant(&self) -> N {
+        let m = self.to_raw();
+        Self::determinant_from_cofactors(&m, &Self::cofactors(&m))
+    }
+
+    /// The inverse of the matrix. Returns None if the matrix is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let m = self.to_raw();
+        let cofactors = Self::cofactors(&m);
+        let determinant = Self::determinant_from_cofactors(&m, &cofactors);
+        let zero = N::i32(0);
+
+        if determinant == zero {
+            return None;
+        }
+
+        let mut inverse = [[zero; 4]; 4];
+        for (i, value) in cofactors.iter().enumerate() {
+            inverse[i / 4][i % 4] = *value / determinant;
+        }
+
+        Some(Self::from_raw(inverse))
+    }
+
+    /// Creates a right handed view matrix looking from the eye to the target.
+    pub fn look_at(eye: Vec3<N>, target: Vec3<N>, up: Vec3<N>) -> Self {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+
+        let forward = (target - eye).unit_vector();
+        let side = forward.cross(up).unit_vector();
+        let up = side.cross(forward);
+
+        Self::from_raw([
+            [side.x, up.x, -forward.x, zero],
+            [side.y, up.y, -forward.y, zero],
+            [side.z, up.z, -forward.z, zero],
+            [-side.dot(eye), -up.dot(eye), forward.dot(eye), one],
+        ])
+    }
+
+    /// Creates a right handed perspective projection. The field of view is in radians. Depth is mapped to -1..1.
+    pub fn perspective(fovy: N, aspect: N, near: N, far: N) -> Self {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+        let two = N::i32(2);
+
+        let half_fovy = fovy / two;
+        let f = half_fovy.cos() / half_fovy.sin();
+        let depth = near - far;
+
+        Self::from_raw([
+            [f / aspect, zero, zero, zero],
+            [zero, f, zero, zero],
+            [zero, zero, (far + near) / depth, -one],
+            [zero, zero, two * far * near / depth, zero],
+        ])
+    }
+
+    /// Creates a right handed orthographic projection. Depth is mapped to -1..1.
+    pub fn orthographic(left: N, right: N, bottom: N, top: N, near: N, far: N) -> Self {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+        let two = N::i32(2);
+
+        let width = right - left;
+        let height = top - bottom;
+        let depth = far - near;
+
+        Self::from_raw([
+            [two / width, zero, zero, zero],
+            [zero, two / height, zero, zero],
+            [zero, zero, -two / depth, zero],
+            [
+                -(right + left) / width,
+                -(top + bottom) / height,
+                -(far + near) / depth,
+                one,
+            ],
+        ])
+    }
+
+    /// Transforms a point, applying translation. Divides by w if the matrix is a projection.
+    pub fn transform_point(&self, point: Vec3<N>) -> Vec3<N> {
+        let one = N::i32(1);
+        let [x, y, z, w] = (*self * Vec4::new(point.x, point.y, point.z, one)).to_raw();
+
+        if w == one || w == N::i32(0) {
+            Vec3::new(x, y, z)
+        } else {
+            Vec3::new(x / w, y / w, z / w)
+        }
+    }
+
+    /// Transforms a direction, ignoring translation.
+    pub fn transform_direction(&self, direction: Vec3<N>) -> Vec3<N> {
+        (*self * Vec4::new(direction.x, direction.y, direction.z, N::i32(0))).vec3()
+    }
+
+    /// Calculates the cofactors of the matrix, laid out as the transposed adjugate so dividing by the determinant gives the inverse.
+    /// Derived from the MESA implementation of gluInvertMatrix.
+    fn cofactors(m: &[[N; 4]; 4]) -> [N; 16] {
+        let zero = N::i32(0);
+        let m = [
+            m[0][0], m[0][1], m[0][2], m[0][3], m[1][0], m[1][1], m[1][2], m[1][3], m[2][0], m[2][1],
+            m[2][2], m[2][3], m[3][0], m[3][1], m[3][2], m[3][3],
+        ];
+        let mut inv = [zero; 16];
+
+        inv[0] = m[5] * m[10] * m[15] - m[5] * m[11] * m[14] - m[9] * m[6] * m[15]
+            + m[9] * m[7] * m[14]
+            + m[13] * m[6] * m[11]
+            - m[13] * m[7] * m[10];
+        inv[4] = -m[4] * m[10] * m[15] + m[4] * m[11] * m[14] + m[8] * m[6] * m[15]
+            - m[8] * m[7] * m[14]
+            - m[12] * m[6] * m[11]
+            + m[12] * m[7] * m[10];
+        inv[8] = m[4] * m[9] * m[15] - m[4] * m[11] * m[13] - m[8] * m[5] * m[15]
+            + m[8] * m[7] * m[13]
+            + m[12] * m[5] * m[11]
+            - m[12] * m[7] * m[9];
+        inv[12] = -m[4] * m[9] * m[14] + m[4] * m[10] * m[13] + m[8] * m[5] * m[14]
+            - m[8] * m[6] * m[13]
+            - m[12] * m[5] * m[10]
+            + m[12] * m[6] * m[9];
+        inv[1] = -m[1] * m[10] * m[15] + m[1] * m[11] * m[14] + m[9] * m[2] * m[15]
+            - m[9] * m[3] * m[14]
+            - m[13] * m[2] * m[11]
+            + m[13] * m[3] * m[10];
+        inv[5] = m[0] * m[10] * m[15] - m[0] * m[11] * m[14] - m[8] * m[2] * m[15]
+            + m[8] * m[3] * m[14]
+            + m[12] * m[2] * m[11]
+            - m[12] * m[3] * m[10];
+        inv[9] = -m[0] * m[9] * m[15] + m[0] * m[11] * m[13] + m[8] * m[1] * m[15]
+            - m[8] * m[3] * m[13]
+            - m[12] * m[1] * m[11]
+            + m[12] * m[3] * m[9];
+        inv[13] = m[0] * m[9] * m[14] - m[0] * m[10] * m[13] - m[8] * m[1] * m[14]
+            + m[8] * m[2] * m[13]
+            + m[12] * m[1] * m[10]
+            - m[12] * m[2] * m[9];
+        inv[2] = m[1] * m[6] * m[15] - m[1] * m[7] * m[14] - m[5] * m[2] * m[15]
+            + m[5] * m[3] * m[14]
+            + m[13] * m[2] * m[7]
+            - m[13] * m[3] * m[6];
+        inv[6] = -m[0] * m[6] * m[15] + m[0] * m[7] * m[14] + m[4] * m[2] * m[15]
+            - m[4] * m[3] * m[14]
+            - m[12] * m[2] * m[7]
+            + m[12] * m[3] * m[6];
+        inv[10] = m[0] * m[5] * m[15] - m[0] * m[7] * m[13] - m[4] * m[1] * m[15]
+            + m[4] * m[3] * m[13]
+            + m[12] * m[1] * m[7]
+            - m[12] * m[3] * m[5];
+        inv[14] = -m[0] * m[5] * m[14] + m[0] * m[6] * m[13] + m[4] * m[1] * m[14]
+            - m[4] * m[2] * m[13]
+            - m[12] * m[1] * m[6]
+            + m[12] * m[2] * m[5];
+        inv[3] = -m[1] * m[6] * m[11] + m[1] * m[7] * m[10] + m[5] * m[2] * m[11]
+            - m[5] * m[3] * m[10]
+            - m[9] * m[2] * m[7]
+            + m[9] * m[3] * m[6];
+        inv[7] = m[0] * m[6] * m[11] - m[0] * m[7] * m[10] - m[4] * m[2] * m[11]
+            + m[4] * m[3] * m[10]
+            + m[8] * m[2] * m[7]
+            - m[8] * m[3] * m[6];
+        inv[11] = -m[0] * m[5] * m[11] + m[0] * m[7] * m[9] + m[4] * m[1] * m[11]
+            - m[4] * m[3] * m[9]
+            - m[8] * m[1] * m[7]
+            + m[8] * m[3] * m[5];
+        inv[15] = m[0] * m[5] * m[10] - m[0] * m[6] * m[9] - m[4] * m[1] * m[10]
+            + m[4] * m[2] * m[9]
+            + m[8] * m[1] * m[6]
+            - m[8] * m[2] * m[5];
+
+        inv
     }
 
-    pub fn inverse(&self) -> Self {
-        unimplemented!();
+    /// Expands the determinant along the first column.
+    fn determinant_from_cofactors(m: &[[N; 4]; 4], cofactors: &[N; 16]) -> N {
+        m[0][0] * cofactors[0] + m[0][1] * cofactors[4] + m[0][2] * cofactors[8] + m[0][3] * cofactors[12]
     }
 }
 
@@ -91,6 +309,171 @@ where
 {
     type Output = Self;
     fn mul(self, rhs: Self) -> Self {
-        unimplemented!();
+        Self([self * rhs[0], self * rhs[1], self * rhs[2], self * rhs[3]])
+    }
+}
+
+impl<N> std::ops::Mul<Vec4<N>> for Mat4<N>
+where
+    N: Number,
+{
+    type Output = Vec4<N>;
+    fn mul(self, rhs: Vec4<N>) -> Vec4<N> {
+        let m = self.to_raw();
+        let v = rhs.to_raw();
+        let mut result = [N::i32(0); 4];
+
+        for (row, value) in result.iter_mut().enumerate() {
+            *value = m[0][row] * v[0] + m[1][row] * v[1] + m[2][row] * v[2] + m[3][row] * v[3];
+        }
+
+        Vec4::from_raw(result)
+    }
+}
+
+impl<N> std::ops::Mul<Vec3<N>> for Mat4<N>
+where
+    N: Number,
+{
+    type Output = Vec3<N>;
+    /// Transforms the Vec3 as a point.
+    fn mul(self, rhs: Vec3<N>) -> Vec3<N> {
+        self.transform_point(rhs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::Fix;
+
+    type M = Mat4<f32>;
+    type V3 = Vec3<f32>;
+
+    fn assert_mat_eq(expected: M, actual: M) {
+        let expected = expected.to_raw();
+        let actual = actual.to_raw();
+
+        for col in 0..4 {
+            for row in 0..4 {
+                let difference = (expected[col][row] - actual[col][row]).abs();
+                assert!(difference < 0.0001, "expected {:?}, got {:?}", expected, actual);
+            }
+        }
+    }
+
+    fn assert_vec3_eq(expected: V3, actual: V3) {
+        assert!((expected - actual).len() < 0.0001, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    fn test_matrix() -> M {
+        M::from_raw([
+            [2., 0., 1., 0.],
+            [1., 3., 0., 0.],
+            [0., 1., 4., 0.],
+            [5., -2., 7., 1.],
+        ])
+    }
+
+    #[test]
+    fn mat4_mul_returns_expected() {
+        let a = M::from_raw([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 10., 11., 12.],
+            [13., 14., 15., 16.],
+        ]);
+        let b = M::from_translation(V3::new(1., 2., 3.));
+
+        // Column 3 of a * b is a * (1, 2, 3, 1)
+        let expected = M::from_raw([
+            [1., 2., 3., 4.],
+            [5., 6., 7., 8.],
+            [9., 10., 11., 12.],
+            [51., 58., 65., 72.],
+        ]);
+
+        assert_eq!(expected, a * b);
+        assert_eq!(a, a * M::identity());
+        assert_eq!(a, M::identity() * a);
+    }
+
+    #[test]
+    fn mat4_transpose_returns_expected() {
+        let m = test_matrix();
+        let t = m.transpose().to_raw();
+
+        assert_eq!(5., t[0][3]);
+        assert_eq!(1., t[2][0]);
+        assert_eq!(m, m.transpose().transpose());
+    }
+
+    #[test]
+    fn mat4_inverse_returns_expected() {
+        let m = test_matrix();
+        let inverse = m.inverse().unwrap();
+
+        assert_mat_eq(M::identity(), m * inverse);
+        assert_mat_eq(M::identity(), inverse * m);
+        assert_eq!(25., m.determinant());
+
+        let translation = M::from_translation(V3::new(1., -2., 3.));
+        assert_eq!(Some(M::from_translation(V3::new(-1., 2., -3.))), translation.inverse());
+    }
+
+    #[test]
+    fn mat4_inverse_singular_returns_none() {
+        assert_eq!(None, M::i32(0).inverse());
+        assert_eq!(None, M::from_scale(V3::new(1., 0., 1.)).inverse());
+    }
+
+    #[test]
+    fn mat4_translate_scale_transforms_point() {
+        let m = M::translate(M::identity(), V3::new(1., 2., 3.)).scale(V3::new(2., 2., 2.));
+        let point = V3::new(1., 1., 1.);
+
+        // Scale is applied first, then the translation.
+        assert_eq!(V3::new(3., 4., 5.), m.transform_point(point));
+        assert_eq!(V3::new(3., 4., 5.), m * point);
+        assert_eq!(V3::new(2., 2., 2.), m.transform_direction(point));
+    }
+
+    #[test]
+    fn mat4_look_at_returns_expected() {
+        let eye = V3::new(0., 0., 5.);
+        let view = M::look_at(eye, V3::default(), V3::new(0., 1., 0.));
+
+        assert_vec3_eq(V3::default(), view * eye);
+        // The target ends up down the negative z axis.
+        assert_vec3_eq(V3::new(0., 0., -5.), view * V3::default());
+        assert_vec3_eq(V3::new(1., 0., -5.), view * V3::new(1., 0., 0.));
+    }
+
+    #[test]
+    fn mat4_perspective_maps_depth() {
+        let projection = M::perspective(std::f32::consts::FRAC_PI_2, 2., 1., 10.);
+
+        assert_vec3_eq(V3::new(0., 0., -1.), projection * V3::new(0., 0., -1.));
+        assert_vec3_eq(V3::new(0., 0., 1.), projection * V3::new(0., 0., -10.));
+        // With a 90 degree fov, y = -z is on the top edge.
+        assert_vec3_eq(V3::new(0.5, 1., -1.), projection * V3::new(1., 1., -1.));
+    }
+
+    #[test]
+    fn mat4_orthographic_maps_bounds() {
+        let projection = M::orthographic(-2., 2., -1., 1., 0., 10.);
+
+        assert_vec3_eq(V3::new(-1., -1., -1.), projection * V3::new(-2., -1., 0.));
+        assert_vec3_eq(V3::new(1., 1., 1.), projection * V3::new(2., 1., -10.));
+    }
+
+    #[test]
+    fn mat4_fixed_point_inverse_returns_expected() {
+        let v = |x, y, z| Vec3::new(Fix::from_int(x), Fix::from_int(y), Fix::from_int(z));
+        let m = Mat4::translate(Mat4::identity(), v(4, -2, 1)).scale(v(2, 2, 2));
+
+        let inverse = m.inverse().unwrap();
+        assert_eq!(Mat4::identity(), m * inverse);
+        assert_eq!(v(6, 0, 3), m * v(1, 1, 1));
     }
 }
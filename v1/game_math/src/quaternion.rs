@@ -1,6 +1,6 @@
-use crate::{mat4::Mat4, vec4::Vec4};
+use crate::mat4::Mat4;
 
-use super::vec3::Vec3;
+use super::vectors::Vec3;
 use super::*;
 
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -37,7 +37,8 @@ where
         Self::new(R::i32(0), R::i32(0), R::i32(0), R::i32(1))
     }
 
-    fn from_axis_angle(axis: Vec3<R>, angle: R) -> Self {
+    /// Creates a rotation of `angle` radians around the axis. The axis should be normalized.
+    pub fn from_axis_angle(axis: Vec3<R>, angle: R) -> Self {
         let angle = angle / R::i32(2);
 
         let w = angle.cos();
@@ -58,6 +59,155 @@ where
         Self::from_axis_angle((0, 0, 1).into(), angle)
     }
 
+    /// Creates a rotation from euler angles in radians. Applied in the order x (roll), y (pitch), then z (yaw).
+    pub fn from_euler(angles: Vec3<R>) -> Self {
+        Self::from_z_rotation(angles.z) * Self::from_y_rotation(angles.y) * Self::from_x_rotation(angles.x)
+    }
+
+    /// Converts the rotation to euler angles in radians. The inverse of `from_euler`, with pitch limited to -PI/2..=PI/2.
+    pub fn to_euler(&self) -> Vec3<R> {
+        let one = R::i32(1);
+        let two = R::i32(2);
+
+        let (w, x, y, z) = (self.w, self.x, self.y, self.z);
+
+        let roll = (two * (w * x + y * z)).atan2(one - two * (x.sqrd() + y.sqrd()));
+
+        // Clamp to avoid drifting outside of asin's domain at the poles.
+        let sin_pitch = (two * (w * y - z * x)).max(-one).min(one);
+        let pitch = sin_pitch.atan2((one - sin_pitch.sqrd()).sqrt());
+
+        let yaw = (two * (w * z + x * y)).atan2(one - two * (y.sqrd() + z.sqrd()));
+
+        Vec3::new(roll, pitch, yaw)
+    }
+
+    /// Creates a rotation that points +z along `forward`, with +y as close to `up` as possible.
+    pub fn look_rotation(forward: Vec3<R>, up: Vec3<R>) -> Self {
+        let z_axis = forward.unit_vector();
+        let x_axis = up.cross(z_axis).unit_vector();
+        let y_axis = z_axis.cross(x_axis);
+
+        Self::from_basis(x_axis, y_axis, z_axis)
+    }
+
+    /// Creates a rotation from an orthonormal basis, using the largest diagonal to stay numerically stable.
+    fn from_basis(x_axis: Vec3<R>, y_axis: Vec3<R>, z_axis: Vec3<R>) -> Self {
+        let zero = R::i32(0);
+        let one = R::i32(1);
+        let two = R::i32(2);
+        let four = R::i32(4);
+
+        let (m00, m01, m02) = (x_axis.x, y_axis.x, z_axis.x);
+        let (m10, m11, m12) = (x_axis.y, y_axis.y, z_axis.y);
+        let (m20, m21, m22) = (x_axis.z, y_axis.z, z_axis.z);
+
+        let trace = m00 + m11 + m22;
+
+        let q = {
+            if trace > zero {
+                let s = (trace + one).sqrt() * two;
+                Self::new((m21 - m12) / s, (m02 - m20) / s, (m10 - m01) / s, s / four)
+            } else if m00 > m11 && m00 > m22 {
+                let s = (one + m00 - m11 - m22).sqrt() * two;
+                Self::new(s / four, (m01 + m10) / s, (m02 + m20) / s, (m21 - m12) / s)
+            } else if m11 > m22 {
+                let s = (one + m11 - m00 - m22).sqrt() * two;
+                Self::new((m01 + m10) / s, s / four, (m12 + m21) / s, (m02 - m20) / s)
+            } else {
+                let s = (one + m22 - m00 - m11).sqrt() * two;
+                Self::new((m02 + m20) / s, (m12 + m21) / s, s / four, (m10 - m01) / s)
+            }
+        };
+
+        q.normalize()
+    }
+
+    /// The conjugate of the Quaternion. For unit Quaternions this is the inverse rotation.
+    pub fn conjugate(&self) -> Self {
+        Self::new(-self.x, -self.y, -self.z, self.w)
+    }
+
+    /// The inverse of the Quaternion. Returns None if it has no length.
+    pub fn inverse(&self) -> Option<Self> {
+        let mag = self.magnitude();
+        if mag == R::i32(0) {
+            return None;
+        }
+
+        let c = self.conjugate();
+        Some(Self::new(c.x / mag, c.y / mag, c.z / mag, c.w / mag))
+    }
+
+    /// The dot product of two Quaternions.
+    pub fn dot(&self, other: Self) -> R {
+        self.w * other.w + self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    /// Normalized linear interpolation. Cheaper than `slerp` but doesn't rotate at a constant speed.
+    pub fn nlerp(&self, other: Self, t: R) -> Self {
+        let other = self.shortest_path(other);
+        let s = R::i32(1) - t;
+
+        Self::new(
+            self.x * s + other.x * t,
+            self.y * s + other.y * t,
+            self.z * s + other.z * t,
+            self.w * s + other.w * t,
+        )
+        .normalize()
+    }
+
+    /// Spherical linear interpolation. Rotates along the shortest path at a constant speed.
+    pub fn slerp(&self, other: Self, t: R) -> Self {
+        let one = R::i32(1);
+        let other = self.shortest_path(other);
+        let cos_theta = self.dot(other).min(one);
+
+        let sin_theta = (one - cos_theta.sqrd()).sqrt();
+        let theta = sin_theta.atan2(cos_theta);
+
+        // Nearly parallel, so the sin ratio will blow up. They're close enough to lerp instead.
+        let nearly_parallel = R::i32(1) / R::i32(1000);
+        if sin_theta <= nearly_parallel {
+            return self.nlerp(other, t);
+        }
+
+        let a = ((one - t) * theta).sin() / sin_theta;
+        let b = (t * theta).sin() / sin_theta;
+
+        Self::new(
+            self.x * a + other.x * b,
+            self.y * a + other.y * b,
+            self.z * a + other.z * b,
+            self.w * a + other.w * b,
+        )
+    }
+
+    /// Returns whether the Quaternion has drifted from unit length by more than the tolerance.
+    pub fn should_normalize(&self, tolerance: R) -> bool {
+        (self.magnitude() - R::i32(1)).abs() > tolerance
+    }
+
+    /// Normalizes the Quaternion only if it has drifted from unit length by more than the tolerance.
+    /// Useful after repeated multiplication, where rounding errors build up.
+    pub fn renormalize(&self, tolerance: R) -> Self {
+        if self.should_normalize(tolerance) {
+            self.normalize()
+        } else {
+            *self
+        }
+    }
+
+    /// Negates the other Quaternion if needed, so interpolation takes the shortest path.
+    fn shortest_path(&self, other: Self) -> Self {
+        if self.dot(other) < R::i32(0) {
+            Self::new(-other.x, -other.y, -other.z, -other.w)
+        } else {
+            other
+        }
+    }
+
     /// Normalizes the Quaternion, setting its length to 1.
     pub fn normalize(&self) -> Self {
         let mag = self.magnitude();
@@ -76,17 +226,8 @@ where
 
     /// Converts the Quaternion to a Mat4.
     pub fn to_mat4(&self) -> Mat4<R>{
-        let m = self.to_matrix();
-        let mut i = 0;
-        let m0 = Vec4::new(m[i][0], m[i][1], m[i][2], m[i][3]);
-        i += 1;
-        let m1 = Vec4::new(m[i][0], m[i][1], m[i][2], m[i][3]);
-        i += 1;
-        let m2 = Vec4::new(m[i][0], m[i][1], m[i][2], m[i][3]);
-        i += 1;
-        let m3 = Vec4::new(m[i][0], m[i][1], m[i][2], m[i][3]);
-        
-        Mat4::new(m0, m1, m2, m3)
+        // The matrix is built row major, while Mat4 is column major.
+        Mat4::from_raw(self.to_matrix()).transpose()
     }
 
     fn to_matrix(&self) -> [[R; 4]; 4] {
@@ -126,13 +267,13 @@ where
         let m2 = [
             two_xy + two_wz,
             one - two_x2 - two_z2,
-            two_yz + two_wx,
+            two_yz - two_wx,
             zero,
         ];
 
         let m3 = [
             two_xz - two_wy,
-            two_yz - two_wx,
+            two_yz + two_wx,
             one - two_x2 - two_y2,
             zero,
         ];
@@ -166,29 +307,28 @@ where
         let y = self.w * other.y - self.x * other.z + self.y * other.w + self.z * other.x;
         let z = self.w * other.z + self.x * other.y - self.y * other.x + self.z * other.w;
 
-        // Check if we need to renormalize it?
+        // Rounding errors accumulate, so callers should `renormalize` after repeated multiplication.
         Self::new(x, y, z, w)
     }
 
     fn magnitude(&self) -> R {
         self.w.sqrd() + self.x.sqrd() + self.y.sqrd() + self.z.sqrd()
     }
+}
 
-    /*
-    fn should_normalize(&self) -> bool {
-        unimplemented!();
-        /*
-        let tolerance = R::decimal_resolution_value() * R::from_i32(10); // Tolerance is how much rounding errors we can tolerate
-        let norm = self.magnitude();
+impl<R> RawConverter for Quaternion<R>
+where
+    R: Number,
+{
+    type RawType = [R; 4];
 
-        if R::i32(1) - tolerance < norm && R::i32(1) + tolerance > norm {
-            return false;
-        }
+    fn to_raw(&self) -> Self::RawType {
+        [self.x, self.y, self.z, self.w]
+    }
 
-        true
-        */
+    fn from_raw(raw: Self::RawType) -> Self {
+        Self::new(raw[0], raw[1], raw[2], raw[3])
     }
-    */
 }
 
 impl<R> std::ops::Mul for Quaternion<R>
@@ -221,9 +361,138 @@ mod tests {
 
     type Q = Quaternion<f32>;
 
+    fn assert_q_eq(expected: Q, actual: Q) {
+        // q and -q are the same rotation
+        let difference = 1.0 - expected.dot(actual).abs();
+        assert!(difference < 0.0001, "expected {:?}, got {:?}", expected, actual);
+    }
+
+    fn assert_vec3_eq(expected: Vec3<f32>, actual: Vec3<f32>) {
+        assert!((expected - actual).len() < 0.0001, "expected {:?}, got {:?}", expected, actual);
+    }
+
     #[test]
     fn Quaternion_to_matrix() {
-        assert_eq!(true, false);
+        let q = Q::from_z_rotation(std::f32::consts::FRAC_PI_2);
+        let m = q.to_matrix();
+
+        // Row major rotation of 90 degrees around z
+        let expected = [
+            [0., -1., 0., 0.],
+            [1., 0., 0., 0.],
+            [0., 0., 1., 0.],
+            [0., 0., 0., 1.],
+        ];
+
+        for row in 0..4 {
+            for col in 0..4 {
+                assert!((expected[row][col] - m[row][col]).abs() < 0.0001);
+            }
+        }
+    }
+
+    #[test]
+    fn Quaternion_conjugate_inverse() {
+        let q = Q::from_axis_angle(Vec3::new(0., 0.6, 0.8), 1.3);
+
+        assert_q_eq(Q::identity(), q * q.conjugate());
+        assert_q_eq(q.conjugate(), q.inverse().unwrap());
+
+        let scaled = Q::new(0., 0., 0., 2.);
+        assert_eq!(Some(Q::new(0., 0., 0., 0.5)), scaled.inverse());
+        assert_eq!(None, Q::new(0., 0., 0., 0.).inverse());
+    }
+
+    #[test]
+    fn Quaternion_slerp_nlerp() {
+        let a = Q::identity();
+        let b = Q::from_z_rotation(std::f32::consts::FRAC_PI_2);
+
+        assert_q_eq(a, a.slerp(b, 0.));
+        assert_q_eq(b, a.slerp(b, 1.));
+        assert_q_eq(Q::from_z_rotation(std::f32::consts::FRAC_PI_4), a.slerp(b, 0.5));
+        assert_q_eq(Q::from_z_rotation(std::f32::consts::FRAC_PI_8), a.slerp(b, 0.25));
+
+        // Nlerp matches at the midpoint, but not elsewhere.
+        assert_q_eq(a.slerp(b, 0.5), a.nlerp(b, 0.5));
+        assert_q_eq(b, a.nlerp(b, 1.));
+
+        // Takes the shortest path when the other side of the hypersphere is given.
+        let negated_b = Q::new(-b.x, -b.y, -b.z, -b.w);
+        assert_q_eq(a.slerp(b, 0.5), a.slerp(negated_b, 0.5));
+
+        // Nearly identical rotations fall back to nlerp.
+        let c = Q::from_z_rotation(0.0001);
+        assert_q_eq(Q::from_z_rotation(0.00005), a.slerp(c, 0.5));
+    }
+
+    #[test]
+    fn Quaternion_euler_round_trips() {
+        let angles = Vec3::new(0.3, -0.7, 1.9);
+        let q = Q::from_euler(angles);
+
+        assert_vec3_eq(angles, q.to_euler());
+
+        let expected = Q::from_z_rotation(angles.z) * Q::from_y_rotation(angles.y) * Q::from_x_rotation(angles.x);
+        assert_q_eq(expected, q);
+
+        // Applied x first
+        let v = Vec3::new(0., 1., 0.);
+        let expected = Q::from_z_rotation(angles.z)
+            .rotate_vec3(Q::from_y_rotation(angles.y).rotate_vec3(Q::from_x_rotation(angles.x).rotate_vec3(v)));
+        assert_vec3_eq(expected, q.rotate_vec3(v));
+    }
+
+    #[test]
+    fn Quaternion_look_rotation() {
+        let up = Vec3::new(0., 1., 0.);
+
+        assert_q_eq(Q::identity(), Q::look_rotation(Vec3::new(0., 0., 1.), up));
+
+        for forward in [Vec3::new(1., 0., 0.), Vec3::new(0., 0., -1.), Vec3::new(-1., 2., 0.5)].iter() {
+            let q = Q::look_rotation(*forward, up);
+
+            assert_vec3_eq(forward.unit_vector(), q.rotate_vec3(Vec3::new(0., 0., 1.)));
+            // Right stays level
+            assert!(q.rotate_vec3(Vec3::new(1., 0., 0.)).y.abs() < 0.0001);
+        }
+    }
+
+    #[test]
+    fn Quaternion_renormalize() {
+        let q = Q::from_x_rotation(1.);
+        let drifted = Q::new(q.x * 1.01, q.y * 1.01, q.z * 1.01, q.w * 1.01);
+
+        assert_eq!(false, q.should_normalize(0.001));
+        assert_eq!(true, drifted.should_normalize(0.001));
+        assert_eq!(false, drifted.should_normalize(0.1));
+
+        assert_eq!(drifted, drifted.renormalize(0.1));
+        assert_eq!(false, drifted.renormalize(0.001).should_normalize(0.001));
+    }
+
+    #[test]
+    fn Quaternion_fixed_point_slerp() {
+        use crate::fix::Fix;
+
+        let a = Quaternion::<Fix>::identity();
+        let b = Quaternion::from_z_rotation(Fix::HALF_PI);
+        let expected = Quaternion::from_z_rotation(Fix::HALF_PI / Fix::from_int(2));
+
+        let actual = a.slerp(b, Fix::from_ratio(1, 2));
+        let difference = Fix::ONE - expected.dot(actual);
+        assert!(difference < Fix::from_ratio(1, 1000), "{:?}", difference);
+    }
+
+    #[test]
+    fn Quaternion_to_mat4_matches_rotate_vec3() {
+        let q = Q::from_x_rotation(0.5) * Q::from_y_rotation(1.25);
+        let v: Vec3<f32> = (1, -2, 3).into();
+
+        let expected = q.rotate_vec3(v);
+        let actual = q.to_mat4() * v;
+
+        assert!((expected - actual).len() < 0.0001);
     }
 
     #[test]
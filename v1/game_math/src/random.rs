@@ -0,0 +1,276 @@
+use super::{
+    vectors::{Vec2, Vec3},
+    Number,
+};
+
+const MULTIPLIER: u64 = 6_364_136_223_846_793_005;
+
+/// The number of random bits used when generating a Number in 0..1. Kept small so it fits in a `Fix` without overflowing.
+const UNIT_BITS: u32 = 15;
+
+/// A deterministic PCG32 random number generator. Only integer math is used, so the same seed gives bit-identical results on every platform.
+/// The state is plain data, so it can be stored in rollback game states and compared.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Random {
+    state: u64,
+    increment: u64,
+}
+
+impl Random {
+    /// Creates a new generator from the seed.
+    pub fn new(seed: u64) -> Self {
+        Self::with_stream(seed, 0)
+    }
+
+    /// Creates a new generator from the seed and stream. Generators with the same seed but different streams produce different sequences.
+    pub fn with_stream(seed: u64, stream: u64) -> Self {
+        let mut random = Self {
+            state: 0,
+            increment: (stream << 1) | 1,
+        };
+
+        random.step();
+        random.state = random.state.wrapping_add(seed);
+        random.step();
+
+        random
+    }
+
+    fn step(&mut self) {
+        self.state = self
+            .state
+            .wrapping_mul(MULTIPLIER)
+            .wrapping_add(self.increment);
+    }
+
+    /// Returns the next random u32.
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.step();
+
+        let xor_shifted = (((old >> 18) ^ old) >> 27) as u32;
+        let rotation = (old >> 59) as u32;
+
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// Returns a random u32 in 0..bound, without modulo bias. Returns 0 if the bound is 0.
+    pub fn next_bounded(&mut self, bound: u32) -> u32 {
+        if bound == 0 {
+            return 0;
+        }
+
+        let threshold = bound.wrapping_neg() % bound;
+        loop {
+            let r = self.next_u32();
+            if r >= threshold {
+                return r % bound;
+            }
+        }
+    }
+
+    /// Returns a random i32 in min..max. Returns min if the range is empty.
+    pub fn range_i32(&mut self, min: i32, max: i32) -> i32 {
+        if max <= min {
+            return min;
+        }
+
+        let span = (max as i64 - min as i64) as u32;
+        (min as i64 + self.next_bounded(span) as i64) as i32
+    }
+
+    /// Returns a random bool.
+    pub fn bool(&mut self) -> bool {
+        self.next_u32() >> 31 == 1
+    }
+
+    /// Returns true with the given probability, where 0 never happens and 1 always happens.
+    pub fn chance<N>(&mut self, probability: N) -> bool
+    where
+        N: Number,
+    {
+        self.unit::<N>() < probability
+    }
+
+    /// Returns a random Number in 0..1.
+    pub fn unit<N>(&mut self) -> N
+    where
+        N: Number,
+    {
+        let bits = (self.next_u32() >> (32 - UNIT_BITS)) as i32;
+
+        // Split the divisor so it stays in range for fixed point numbers.
+        N::i32(bits) / N::i32(1 << (UNIT_BITS - 8)) / N::i32(1 << 8)
+    }
+
+    /// Returns a random Number in min..max.
+    pub fn range<N>(&mut self, min: N, max: N) -> N
+    where
+        N: Number,
+    {
+        min + (max - min) * self.unit()
+    }
+
+    /// Returns a random Vec2 with a length of 1.
+    pub fn unit_vec2<N>(&mut self) -> Vec2<N>
+    where
+        N: Number,
+    {
+        // Rejection sampling avoids sin and cos, which are not deterministic for floats.
+        loop {
+            let v = Vec2::new(self.signed_unit(), self.signed_unit());
+            let len_squared = v.len_squared();
+
+            if len_squared > N::i32(0) && len_squared <= N::i32(1) {
+                return v.normalize();
+            }
+        }
+    }
+
+    /// Returns a random Vec3 with a length of 1.
+    pub fn unit_vec3<N>(&mut self) -> Vec3<N>
+    where
+        N: Number,
+    {
+        loop {
+            let v = Vec3::new(self.signed_unit(), self.signed_unit(), self.signed_unit());
+            let len_squared = v.len_squared();
+
+            if len_squared > N::i32(0) && len_squared <= N::i32(1) {
+                return v.normalize();
+            }
+        }
+    }
+
+    /// Returns a random Number in -1..1.
+    fn signed_unit<N>(&mut self) -> N
+    where
+        N: Number,
+    {
+        self.range(N::i32(-1), N::i32(1))
+    }
+
+    /// Shuffles the slice in place using Fisher-Yates.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.next_bounded(i as u32 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Returns a random item from the slice, or None if it is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+
+        items.get(self.next_bounded(items.len() as u32) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::Fix;
+
+    #[test]
+    fn random_matches_reference_pcg32() {
+        // Reference values from the PCG32 demo, seeded with 42 on stream 54.
+        let mut random = Random::with_stream(42, 54);
+        let expected = [
+            0xa15c02b7, 0x7b47f409, 0xba1d3330, 0x83d2f293, 0xbfa4784b, 0xcbed606e,
+        ];
+
+        for e in expected.iter() {
+            assert_eq!(*e, random.next_u32());
+        }
+    }
+
+    #[test]
+    fn random_same_seed_same_sequence() {
+        let mut a = Random::new(1234);
+        let mut b = Random::new(1234);
+        let c = Random::new(4321);
+
+        assert_eq!(a, b);
+        assert_eq!(false, a == c);
+
+        for _ in 0..100 {
+            assert_eq!(a.next_u32(), b.next_u32());
+        }
+
+        // Copies continue the same sequence, as a rollback restore would.
+        let mut restored = a;
+        assert_eq!(a.range_i32(0, 1000), restored.range_i32(0, 1000));
+        assert_eq!(a, restored);
+    }
+
+    #[test]
+    fn random_ranges_stay_in_bounds() {
+        let mut random = Random::new(7);
+
+        for _ in 0..1000 {
+            let i = random.range_i32(-5, 5);
+            assert_eq!(true, (-5..5).contains(&i));
+
+            let f = random.range(-2.5f32, 4.);
+            assert_eq!(true, (-2.5..4.).contains(&f));
+
+            let fix = random.range(Fix::from_int(-3), Fix::from_int(100));
+            assert_eq!(true, (Fix::from_int(-3)..Fix::from_int(100)).contains(&fix));
+
+            let unit = random.unit::<Fix>();
+            assert_eq!(true, (Fix::ZERO..Fix::ONE).contains(&unit));
+        }
+
+        assert_eq!(3, random.range_i32(3, 3));
+        assert_eq!(i32::MIN, Random::new(0).range_i32(i32::MIN, i32::MIN + 1));
+    }
+
+    #[test]
+    fn random_unit_matches_between_number_types() {
+        let mut a = Random::new(99);
+        let mut b = a;
+
+        for _ in 0..100 {
+            let f: f32 = a.unit();
+            let fix: Fix = b.unit();
+            assert_eq!(f, fix.to_f32());
+        }
+    }
+
+    #[test]
+    fn random_unit_vectors_have_length_one() {
+        let mut random = Random::new(11);
+
+        for _ in 0..100 {
+            let v = random.unit_vec3::<f32>();
+            assert_eq!(true, (v.len() - 1.).abs() < 0.0001);
+
+            let v = random.unit_vec2::<f32>();
+            assert_eq!(true, (v.len() - 1.).abs() < 0.0001);
+
+            let v = random.unit_vec3::<Fix>();
+            assert_eq!(true, (v.len() - Fix::ONE).abs() < Fix::from_ratio(1, 100));
+        }
+    }
+
+    #[test]
+    fn random_shuffle_is_deterministic_permutation() {
+        let mut items: Vec<i32> = (0..20).collect();
+        let mut other = items.clone();
+
+        Random::new(5).shuffle(&mut items);
+        Random::new(5).shuffle(&mut other);
+
+        assert_eq!(other, items);
+        assert_eq!(false, items == (0..20).collect::<Vec<i32>>());
+
+        let mut sorted = items.clone();
+        sorted.sort();
+        assert_eq!((0..20).collect::<Vec<i32>>(), sorted);
+
+        assert_eq!(None, Random::new(0).choose::<i32>(&[]));
+        assert_eq!(true, items.contains(Random::new(0).choose(&items).unwrap()));
+    }
+}
@@ -0,0 +1,297 @@
+use super::*;
+use crate::{mat4::Mat4, RawConverter};
+
+/// An axis aligned bounding box.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Aabb<N>
+where
+    N: Number,
+{
+    pub min: Vec3<N>,
+    pub max: Vec3<N>,
+}
+
+impl<N> Aabb<N>
+where
+    N: Number,
+{
+    /// Creates a new Aabb.
+    pub fn new(min: Vec3<N>, max: Vec3<N>) -> Self {
+        Self { min, max }
+    }
+
+    /// Creates a new Aabb from its center and half extents.
+    pub fn from_center_extents(center: Vec3<N>, extents: Vec3<N>) -> Self {
+        Self {
+            min: center - extents,
+            max: center + extents,
+        }
+    }
+
+    /// The center of the box.
+    pub fn center(&self) -> Vec3<N> {
+        (self.min + self.max) / N::i32(2)
+    }
+
+    /// The half extents of the box.
+    pub fn extents(&self) -> Vec3<N> {
+        (self.max - self.min) / N::i32(2)
+    }
+
+    /// Returns the box moved by the given offset.
+    pub fn offset(&self, offset: Vec3<N>) -> Self {
+        Self {
+            min: self.min + offset,
+            max: self.max + offset,
+        }
+    }
+
+    /// Whether the point is inside the box.
+    pub fn contains_point(&self, point: Vec3<N>) -> bool {
+        point.x >= self.min.x
+            && point.x <= self.max.x
+            && point.y >= self.min.y
+            && point.y <= self.max.y
+            && point.z >= self.min.z
+            && point.z <= self.max.z
+    }
+
+    /// Whether the two boxes overlap.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+            && self.min.z <= other.max.z
+            && self.max.z >= other.min.z
+    }
+
+    /// Collides the two boxes using the separating axis theorem. The normal is along the axis of least penetration, pointing towards the other box.
+    /// Ported from v0's `aabb_vs_aabb`.
+    pub fn collide(&self, other: &Self) -> Option<Manifold<N>> {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+
+        let delta = (other.center() - self.center()).to_raw();
+        let combined_extents = (self.extents() + other.extents()).to_raw();
+
+        let mut manifold: Option<Manifold<N>> = None;
+
+        for axis in 0..3 {
+            let overlap = combined_extents[axis] - delta[axis].abs();
+            if overlap <= zero {
+                return None;
+            }
+
+            let is_least = match manifold {
+                Some(m) => overlap < m.penetration,
+                None => true,
+            };
+
+            if is_least {
+                let mut normal = [zero; 3];
+                normal[axis] = if delta[axis] < zero { -one } else { one };
+
+                manifold = Some(Manifold {
+                    penetration: overlap,
+                    normal: Vec3::from_raw(normal),
+                });
+            }
+        }
+
+        manifold
+    }
+
+    /// The closest point in the box to the given point.
+    pub fn closest_point(&self, point: Vec3<N>) -> Vec3<N> {
+        point.max(self.min).min(self.max)
+    }
+
+    /// Returns a box containing both boxes.
+    pub fn merge(&self, other: &Self) -> Self {
+        Self {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Returns the box containing this box after it has been transformed.
+    /// Derived from Graphics Gems, 'Transforming Axis-Aligned Bounding Boxes'.
+    pub fn transform(&self, transform: &Mat4<N>) -> Self {
+        let m = transform.to_raw();
+        let min = self.min.to_raw();
+        let max = self.max.to_raw();
+
+        // Start with the translation
+        let mut new_min = [m[3][0], m[3][1], m[3][2]];
+        let mut new_max = new_min;
+
+        for row in 0..3 {
+            for col in 0..3 {
+                let a = m[col][row] * min[col];
+                let b = m[col][row] * max[col];
+
+                new_min[row] += a.min(b);
+                new_max[row] += a.max(b);
+            }
+        }
+
+        Self {
+            min: Vec3::from_raw(new_min),
+            max: Vec3::from_raw(new_max),
+        }
+    }
+
+    /// Casts a ray against the box using the slab method. Rays starting inside the box hit immediately.
+    pub fn raycast(&self, ray: &Ray<N>) -> Option<RaycastHit<N>> {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+
+        let origin = ray.origin.to_raw();
+        let direction = ray.direction.to_raw();
+        let min = self.min.to_raw();
+        let max = self.max.to_raw();
+
+        let mut t_min = zero;
+        let mut t_max = ray.max_distance;
+        let mut normal: Option<Vec3<N>> = None;
+
+        for axis in 0..3 {
+            if direction[axis] == zero {
+                // Parallel to the slab, so the origin must be inside it.
+                if origin[axis] < min[axis] || origin[axis] > max[axis] {
+                    return None;
+                }
+            } else {
+                let t0 = (min[axis] - origin[axis]) / direction[axis];
+                let t1 = (max[axis] - origin[axis]) / direction[axis];
+
+                let (near, far) = if t0 < t1 { (t0, t1) } else { (t1, t0) };
+
+                if near > t_min {
+                    t_min = near;
+
+                    let mut n = [zero; 3];
+                    n[axis] = if direction[axis] < zero { one } else { -one };
+                    normal = Some(Vec3::from_raw(n));
+                }
+
+                t_max = t_max.min(far);
+
+                if t_min > t_max {
+                    return None;
+                }
+            }
+        }
+
+        Some(RaycastHit {
+            distance: t_min,
+            // No entering face means the ray started inside.
+            normal: normal.unwrap_or(-ray.direction),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type A = Aabb<f32>;
+
+    fn unit_box() -> A {
+        A::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.))
+    }
+
+    #[test]
+    fn aabb_overlaps_returns_expected() {
+        let a = unit_box();
+
+        assert_eq!(true, a.overlaps(&unit_box().offset(Vec3::new(2., 0., 0.))));
+        assert_eq!(false, a.overlaps(&unit_box().offset(Vec3::new(2.1, 0., 0.))));
+        assert_eq!(true, a.contains_point(Vec3::new(1., -1., 0.)));
+        assert_eq!(false, a.contains_point(Vec3::new(1., -1.5, 0.)));
+    }
+
+    #[test]
+    fn aabb_collide_returns_least_penetration() {
+        let a = unit_box();
+        let b = unit_box().offset(Vec3::new(0.5, -1.5, 0.));
+
+        let expected = Manifold {
+            penetration: 0.5,
+            normal: Vec3::new(0., -1., 0.),
+        };
+        assert_eq!(Some(expected), a.collide(&b));
+        assert_eq!(None, a.collide(&unit_box().offset(Vec3::new(0., 0., 2.))));
+    }
+
+    #[test]
+    fn aabb_closest_point_merge() {
+        let a = unit_box();
+
+        assert_eq!(Vec3::new(1., 0.5, -1.), a.closest_point(Vec3::new(4., 0.5, -3.)));
+        assert_eq!(Vec3::new(0.5, 0.5, 0.5), a.closest_point(Vec3::new(0.5, 0.5, 0.5)));
+
+        let b = A::new(Vec3::new(0., 2., -4.), Vec3::new(3., 3., -3.));
+        assert_eq!(A::new(Vec3::new(-1., -1., -4.), Vec3::new(3., 3., 1.)), a.merge(&b));
+        assert_eq!(Vec3::new(1., 1., -1.5), a.merge(&b).center());
+        assert_eq!(Vec3::new(2., 2., 2.5), a.merge(&b).extents());
+    }
+
+    #[test]
+    fn aabb_transform_returns_expected() {
+        let a = A::new(Vec3::new(0., 0., 0.), Vec3::new(2., 1., 1.));
+        let transform = Mat4::translate(Mat4::identity(), Vec3::new(10., 0., 0.))
+            * crate::quaternion::Quaternion::from_z_rotation(std::f32::consts::FRAC_PI_2).to_mat4();
+
+        let actual = a.transform(&transform);
+        let expected = A::new(Vec3::new(9., 0., 0.), Vec3::new(10., 2., 1.));
+
+        assert!((expected.min - actual.min).len() < 0.0001, "{:?}", actual);
+        assert!((expected.max - actual.max).len() < 0.0001, "{:?}", actual);
+    }
+
+    #[test]
+    fn aabb_raycast_returns_expected() {
+        let a = unit_box().offset(Vec3::new(5., 0., 0.));
+
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 100.);
+        let expected = RaycastHit {
+            distance: 4.,
+            normal: Vec3::new(-1., 0., 0.),
+        };
+        assert_eq!(Some(expected), a.raycast(&ray));
+
+        let ray = Ray::new(Vec3::new(5., 10., 0.), Vec3::new(0., -1., 0.), 100.);
+        let expected = RaycastHit {
+            distance: 9.,
+            normal: Vec3::new(0., 1., 0.),
+        };
+        assert_eq!(Some(expected), a.raycast(&ray));
+
+        // Misses
+        let ray = Ray::new(Vec3::new(0., 2., 0.), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(None, a.raycast(&ray));
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 3.);
+        assert_eq!(None, a.raycast(&ray));
+
+        // Inside
+        let ray = Ray::new(Vec3::new(5., 0., 0.), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(Some(0.), a.raycast(&ray).map(|hit| hit.distance));
+    }
+
+    #[test]
+    fn aabb_fixed_point_raycast() {
+        use crate::fix::Fix;
+
+        let v = |x, y, z| Vec3::new(Fix::from_int(x), Fix::from_int(y), Fix::from_int(z));
+        let a = Aabb::new(v(4, -1, -1), v(6, 1, 1));
+        let ray = Ray::new(v(0, 0, 0), v(1, 0, 0), Fix::from_int(100));
+
+        let expected = RaycastHit {
+            distance: Fix::from_int(4),
+            normal: v(-1, 0, 0),
+        };
+        assert_eq!(Some(expected), a.raycast(&ray));
+    }
+}
@@ -0,0 +1,207 @@
+use super::*;
+
+/// A capsule, made up of all points within the radius of a segment.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Capsule<N>
+where
+    N: Number,
+{
+    pub segment: Segment<N>,
+    pub radius: N,
+}
+
+impl<N> Capsule<N>
+where
+    N: Number,
+{
+    /// Creates a new Capsule.
+    pub fn new(start: Vec3<N>, end: Vec3<N>, radius: N) -> Self {
+        Self {
+            segment: Segment::new(start, end),
+            radius,
+        }
+    }
+
+    /// The bounding box of the capsule.
+    pub fn aabb(&self) -> Aabb<N> {
+        let start = Sphere::new(self.segment.start, self.radius).aabb();
+        let end = Sphere::new(self.segment.end, self.radius).aabb();
+
+        start.merge(&end)
+    }
+
+    /// Whether the point is inside the capsule.
+    pub fn contains_point(&self, point: Vec3<N>) -> bool {
+        self.segment.distance_squared(point) <= self.radius.sqrd()
+    }
+
+    /// The closest point in the capsule to the given point. Points inside the capsule are returned as is.
+    pub fn closest_point(&self, point: Vec3<N>) -> Vec3<N> {
+        Sphere::new(self.segment.closest_point(point), self.radius).closest_point(point)
+    }
+
+    /// Whether the capsule overlaps the sphere.
+    pub fn overlaps_sphere(&self, sphere: &Sphere<N>) -> bool {
+        self.segment.distance_squared(sphere.center) <= (self.radius + sphere.radius).sqrd()
+    }
+
+    /// Collides the capsule and sphere, with the normal pointing towards the sphere.
+    pub fn collide_sphere(&self, sphere: &Sphere<N>) -> Option<Manifold<N>> {
+        let nearest = self.segment.closest_point(sphere.center);
+        sphere_vs_sphere(nearest, self.radius, sphere.center, sphere.radius)
+    }
+
+    /// Whether the two capsules overlap.
+    pub fn overlaps_capsule(&self, other: &Self) -> bool {
+        let (a, b) = self.segment.closest_points(&other.segment);
+        (b - a).len_squared() <= (self.radius + other.radius).sqrd()
+    }
+
+    /// Collides the two capsules, with the normal pointing towards the other capsule.
+    pub fn collide_capsule(&self, other: &Self) -> Option<Manifold<N>> {
+        let (a, b) = self.segment.closest_points(&other.segment);
+        sphere_vs_sphere(a, self.radius, b, other.radius)
+    }
+
+    /// Casts a ray against the capsule. Rays starting inside the capsule hit immediately.
+    /// The body is tested as an infinite cylinder clipped to the segment, then the end caps as spheres.
+    pub fn raycast(&self, ray: &Ray<N>) -> Option<RaycastHit<N>> {
+        let zero = N::i32(0);
+
+        if self.contains_point(ray.origin) {
+            return Some(RaycastHit {
+                distance: zero,
+                normal: -ray.direction,
+            });
+        }
+
+        let ba = self.segment.end - self.segment.start;
+        let oa = ray.origin - self.segment.start;
+
+        let baba = ba.len_squared();
+        let bard = ba.dot(ray.direction);
+        let baoa = ba.dot(oa);
+        let rdoa = ray.direction.dot(oa);
+        let oaoa = oa.len_squared();
+
+        let a = baba - bard.sqrd();
+        let b = baba * rdoa - baoa * bard;
+        let c = baba * oaoa - baoa.sqrd() - self.radius.sqrd() * baba;
+        let h = b.sqrd() - a * c;
+
+        let mut closest: Option<RaycastHit<N>> = None;
+
+        // Parallel rays can only hit the caps.
+        if a != zero && h >= zero {
+            let distance = (-b - h.sqrt()) / a;
+            let along_axis = baoa + distance * bard;
+
+            if along_axis > zero && along_axis < baba && ray.in_range(distance) {
+                let point = ray.point_at(distance);
+                let normal = (point - self.segment.closest_point(point)).unit_vector();
+
+                closest = Some(RaycastHit { distance, normal });
+            }
+        }
+
+        for end in [self.segment.start, self.segment.end].iter() {
+            if let Some(hit) = Sphere::new(*end, self.radius).raycast(ray) {
+                let is_closer = match closest {
+                    Some(c) => hit.distance < c.distance,
+                    None => true,
+                };
+
+                if is_closer {
+                    closest = Some(hit);
+                }
+            }
+        }
+
+        closest
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type C = Capsule<f32>;
+
+    fn capsule() -> C {
+        C::new(Vec3::new(0., -2., 0.), Vec3::new(0., 2., 0.), 1.)
+    }
+
+    #[test]
+    fn capsule_overlaps_returns_expected() {
+        let a = capsule();
+
+        assert_eq!(true, a.contains_point(Vec3::new(0.5, 2.5, 0.)));
+        assert_eq!(false, a.contains_point(Vec3::new(0.9, 2.9, 0.)));
+
+        assert_eq!(true, a.overlaps_sphere(&Sphere::new(Vec3::new(2., 1., 0.), 1.)));
+        assert_eq!(false, a.overlaps_sphere(&Sphere::new(Vec3::new(0., 4.1, 0.), 1.)));
+
+        let b = C::new(Vec3::new(-5., 0., 1.5), Vec3::new(5., 0., 1.5), 0.5);
+        assert_eq!(true, a.overlaps_capsule(&b));
+        let b = C::new(Vec3::new(-5., 0., 1.6), Vec3::new(5., 0., 1.6), 0.5);
+        assert_eq!(false, a.overlaps_capsule(&b));
+    }
+
+    #[test]
+    fn capsule_collide_returns_manifold() {
+        let a = capsule();
+
+        let sphere = Sphere::new(Vec3::new(1.5, 1., 0.), 1.);
+        let expected = Manifold {
+            penetration: 0.5,
+            normal: Vec3::new(1., 0., 0.),
+        };
+        assert_eq!(Some(expected), a.collide_sphere(&sphere));
+
+        let b = C::new(Vec3::new(-5., 0., 1.), Vec3::new(5., 0., 1.), 0.5);
+        let expected = Manifold {
+            penetration: 0.5,
+            normal: Vec3::new(0., 0., 1.),
+        };
+        assert_eq!(Some(expected), a.collide_capsule(&b));
+    }
+
+    #[test]
+    fn capsule_closest_point_returns_expected() {
+        let a = capsule();
+
+        assert_eq!(Vec3::new(1., 1., 0.), a.closest_point(Vec3::new(5., 1., 0.)));
+        assert_eq!(Vec3::new(0., 3., 0.), a.closest_point(Vec3::new(0., 8., 0.)));
+        assert_eq!(
+            Aabb::new(Vec3::new(-1., -3., -1.), Vec3::new(1., 3., 1.)),
+            a.aabb()
+        );
+    }
+
+    #[test]
+    fn capsule_raycast_returns_expected() {
+        let a = capsule();
+
+        // Body
+        let ray = Ray::new(Vec3::new(-5., 1., 0.), Vec3::new(1., 0., 0.), 100.);
+        let expected = RaycastHit {
+            distance: 4.,
+            normal: Vec3::new(-1., 0., 0.),
+        };
+        assert_eq!(Some(expected), a.raycast(&ray));
+
+        // Cap, parallel to the axis
+        let ray = Ray::new(Vec3::new(0., 10., 0.), Vec3::new(0., -1., 0.), 100.);
+        let expected = RaycastHit {
+            distance: 7.,
+            normal: Vec3::new(0., 1., 0.),
+        };
+        assert_eq!(Some(expected), a.raycast(&ray));
+
+        // Misses
+        let ray = Ray::new(Vec3::new(-5., 3.5, 0.), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(None, a.raycast(&ray));
+        let ray = Ray::new(Vec3::new(-5., 0., 0.), Vec3::new(1., 0., 0.), 3.);
+        assert_eq!(None, a.raycast(&ray));
+    }
+}
@@ -0,0 +1,82 @@
+use super::{vectors::Vec3, Number};
+
+pub mod aabb;
+pub mod capsule;
+pub mod plane;
+pub mod ray;
+pub mod segment;
+pub mod sphere;
+pub mod triangle;
+
+pub use aabb::Aabb;
+pub use capsule::Capsule;
+pub use plane::Plane;
+pub use ray::Ray;
+pub use segment::Segment;
+pub use sphere::Sphere;
+pub use triangle::Triangle;
+
+/// Where a ray hit a shape.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RaycastHit<N>
+where
+    N: Number,
+{
+    /// The distance along the ray.
+    pub distance: N,
+    /// The surface normal at the hit.
+    pub normal: Vec3<N>,
+}
+
+/// How two overlapping shapes collide.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Manifold<N>
+where
+    N: Number,
+{
+    /// How far the shapes overlap.
+    pub penetration: N,
+    /// The direction from the first shape to the second. Moving the second shape along it by the penetration separates them.
+    pub normal: Vec3<N>,
+}
+
+/// Collides two spheres. Ported from v0's `circle_vs_circle`.
+pub(crate) fn sphere_vs_sphere<N>(
+    a_center: Vec3<N>,
+    a_radius: N,
+    b_center: Vec3<N>,
+    b_radius: N,
+) -> Option<Manifold<N>>
+where
+    N: Number,
+{
+    let normal = b_center - a_center;
+    let radius = a_radius + b_radius;
+
+    if normal.len_squared() > radius.sqrd() {
+        return None;
+    }
+
+    let distance = normal.len();
+
+    if distance == N::i32(0) {
+        // They're on the same position, so pick an arbitrary direction.
+        return Some(Manifold {
+            penetration: radius,
+            normal: (0, 1, 0).into(),
+        });
+    }
+
+    Some(Manifold {
+        penetration: radius - distance,
+        normal: normal / distance,
+    })
+}
+
+/// Clamps a number to the given range.
+pub(crate) fn clamp<N>(n: N, min: N, max: N) -> N
+where
+    N: Number,
+{
+    n.max(min).min(max)
+}
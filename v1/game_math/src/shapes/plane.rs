@@ -0,0 +1,101 @@
+use super::*;
+
+/// An infinite plane, made up of all points where `normal.dot(point) == distance`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Plane<N>
+where
+    N: Number,
+{
+    /// The normal of the plane. Should be normalized.
+    pub normal: Vec3<N>,
+    /// The distance of the plane from the origin along the normal.
+    pub distance: N,
+}
+
+impl<N> Plane<N>
+where
+    N: Number,
+{
+    /// Creates a new Plane.
+    pub fn new(normal: Vec3<N>, distance: N) -> Self {
+        Self { normal, distance }
+    }
+
+    /// Creates a plane passing through the point.
+    pub fn from_point_normal(point: Vec3<N>, normal: Vec3<N>) -> Self {
+        let normal = normal.unit_vector();
+
+        Self {
+            normal,
+            distance: normal.dot(point),
+        }
+    }
+
+    /// The distance from the plane to the point. Negative if the point is behind the plane.
+    pub fn signed_distance(&self, point: Vec3<N>) -> N {
+        self.normal.dot(point) - self.distance
+    }
+
+    /// The closest point on the plane to the given point.
+    pub fn closest_point(&self, point: Vec3<N>) -> Vec3<N> {
+        point - self.normal * self.signed_distance(point)
+    }
+
+    /// Casts a ray against the front of the plane.
+    pub fn raycast(&self, ray: &Ray<N>) -> Option<RaycastHit<N>> {
+        let denom = self.normal.dot(ray.direction);
+
+        // Parallel or hitting the back
+        if denom >= N::i32(0) {
+            return None;
+        }
+
+        let distance = -self.signed_distance(ray.origin) / denom;
+
+        if ray.in_range(distance) {
+            Some(RaycastHit {
+                distance,
+                normal: self.normal,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type P = Plane<f32>;
+
+    #[test]
+    fn plane_signed_distance_returns_expected() {
+        let plane = P::from_point_normal(Vec3::new(0., 2., 0.), Vec3::new(0., 3., 0.));
+
+        assert_eq!(Vec3::new(0., 1., 0.), plane.normal);
+        assert_eq!(3., plane.signed_distance(Vec3::new(7., 5., 1.)));
+        assert_eq!(-2., plane.signed_distance(Vec3::new(7., 0., 1.)));
+        assert_eq!(Vec3::new(7., 2., 1.), plane.closest_point(Vec3::new(7., 0., 1.)));
+    }
+
+    #[test]
+    fn plane_raycast_returns_expected() {
+        let plane = P::new(Vec3::new(0., 1., 0.), 0.);
+
+        let ray = Ray::new(Vec3::new(1., 4., 0.), Vec3::new(0., -1., 0.), 10.);
+        let expected = RaycastHit {
+            distance: 4.,
+            normal: Vec3::new(0., 1., 0.),
+        };
+        assert_eq!(Some(expected), plane.raycast(&ray));
+
+        // From behind
+        let ray = Ray::new(Vec3::new(1., -4., 0.), Vec3::new(0., 1., 0.), 10.);
+        assert_eq!(None, plane.raycast(&ray));
+
+        // Too short
+        let ray = Ray::new(Vec3::new(1., 4., 0.), Vec3::new(0., -1., 0.), 3.);
+        assert_eq!(None, plane.raycast(&ray));
+    }
+}
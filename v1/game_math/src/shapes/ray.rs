@@ -0,0 +1,52 @@
+use super::*;
+
+/// A ray used for queries such as picking or hitscan weapons.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ray<N>
+where
+    N: Number,
+{
+    pub origin: Vec3<N>,
+    /// The direction of the ray. Distances are returned as multiples of this, so it should be normalized.
+    pub direction: Vec3<N>,
+    pub max_distance: N,
+}
+
+impl<N> Ray<N>
+where
+    N: Number,
+{
+    /// Creates a new Ray.
+    pub fn new(origin: Vec3<N>, direction: Vec3<N>, max_distance: N) -> Self {
+        Self {
+            origin,
+            direction,
+            max_distance,
+        }
+    }
+
+    /// Returns the point the given distance along the ray.
+    pub fn point_at(&self, distance: N) -> Vec3<N> {
+        self.origin + self.direction * distance
+    }
+
+    /// Whether the distance is within the range of the ray.
+    pub(crate) fn in_range(&self, distance: N) -> bool {
+        distance >= N::i32(0) && distance <= self.max_distance
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ray_point_at_returns_expected() {
+        let ray = Ray::<f32>::new(Vec3::new(1., 2., 3.), Vec3::new(0., 0., -1.), 10.);
+
+        assert_eq!(Vec3::new(1., 2., -1.), ray.point_at(4.));
+        assert_eq!(true, ray.in_range(10.));
+        assert_eq!(false, ray.in_range(10.5));
+        assert_eq!(false, ray.in_range(-0.5));
+    }
+}
@@ -0,0 +1,133 @@
+use super::*;
+
+/// A line segment between two points.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment<N>
+where
+    N: Number,
+{
+    pub start: Vec3<N>,
+    pub end: Vec3<N>,
+}
+
+impl<N> Segment<N>
+where
+    N: Number,
+{
+    /// Creates a new Segment.
+    pub fn new(start: Vec3<N>, end: Vec3<N>) -> Self {
+        Self { start, end }
+    }
+
+    /// The length of the segment.
+    pub fn len(&self) -> N {
+        (self.end - self.start).len()
+    }
+
+    /// The closest point on the segment to the given point.
+    pub fn closest_point(&self, point: Vec3<N>) -> Vec3<N> {
+        let zero = N::i32(0);
+        let ab = self.end - self.start;
+        let len_squared = ab.len_squared();
+
+        if len_squared == zero {
+            return self.start;
+        }
+
+        let t = clamp((point - self.start).dot(ab) / len_squared, zero, N::i32(1));
+        self.start + ab * t
+    }
+
+    /// The closest points between two segments, with the first point on this segment.
+    /// Derived from Real-Time Collision Detection, 5.1.9.
+    pub fn closest_points(&self, other: &Self) -> (Vec3<N>, Vec3<N>) {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+
+        let d1 = self.end - self.start;
+        let d2 = other.end - other.start;
+        let r = self.start - other.start;
+
+        let a = d1.len_squared();
+        let e = d2.len_squared();
+        let f = d2.dot(r);
+
+        // Both segments are points
+        if a == zero && e == zero {
+            return (self.start, other.start);
+        }
+
+        let (s, t) = {
+            if a == zero {
+                (zero, clamp(f / e, zero, one))
+            } else {
+                let c = d1.dot(r);
+
+                if e == zero {
+                    (clamp(-c / a, zero, one), zero)
+                } else {
+                    let b = d1.dot(d2);
+                    let denom = a * e - b * b;
+
+                    // Parallel segments can use any s, so pick the start.
+                    let s = if denom != zero { clamp((b * f - c * e) / denom, zero, one) } else { zero };
+
+                    let t = (b * s + f) / e;
+
+                    if t < zero {
+                        (clamp(-c / a, zero, one), zero)
+                    } else if t > one {
+                        (clamp((b - c) / a, zero, one), one)
+                    } else {
+                        (s, t)
+                    }
+                }
+            }
+        };
+
+        (self.start + d1 * s, other.start + d2 * t)
+    }
+
+    /// The squared distance from the segment to the point.
+    pub fn distance_squared(&self, point: Vec3<N>) -> N {
+        (point - self.closest_point(point)).len_squared()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type S = Segment<f32>;
+
+    #[test]
+    fn segment_closest_point_clamps_to_ends() {
+        let segment = S::new(Vec3::new(0., 0., 0.), Vec3::new(10., 0., 0.));
+
+        assert_eq!(Vec3::new(4., 0., 0.), segment.closest_point(Vec3::new(4., 3., 0.)));
+        assert_eq!(Vec3::new(0., 0., 0.), segment.closest_point(Vec3::new(-4., 3., 0.)));
+        assert_eq!(Vec3::new(10., 0., 0.), segment.closest_point(Vec3::new(14., 3., 0.)));
+        assert_eq!(9., segment.distance_squared(Vec3::new(4., 3., 0.)));
+
+        let point = S::new(Vec3::new(1., 1., 1.), Vec3::new(1., 1., 1.));
+        assert_eq!(Vec3::new(1., 1., 1.), point.closest_point(Vec3::new(4., 3., 0.)));
+    }
+
+    #[test]
+    fn segment_closest_points_returns_expected() {
+        let a = S::new(Vec3::new(-5., 0., 0.), Vec3::new(5., 0., 0.));
+
+        // Crossing above
+        let b = S::new(Vec3::new(2., 3., -5.), Vec3::new(2., 3., 5.));
+        assert_eq!((Vec3::new(2., 0., 0.), Vec3::new(2., 3., 0.)), a.closest_points(&b));
+
+        // Past the end
+        let b = S::new(Vec3::new(8., 1., 0.), Vec3::new(8., 4., 0.));
+        assert_eq!((Vec3::new(5., 0., 0.), Vec3::new(8., 1., 0.)), a.closest_points(&b));
+
+        // Parallel
+        let b = S::new(Vec3::new(-5., 2., 0.), Vec3::new(5., 2., 0.));
+        let (p1, p2) = a.closest_points(&b);
+        assert_eq!(2., (p2 - p1).len());
+    }
+}
@@ -0,0 +1,158 @@
+use super::*;
+
+/// A sphere.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Sphere<N>
+where
+    N: Number,
+{
+    pub center: Vec3<N>,
+    pub radius: N,
+}
+
+impl<N> Sphere<N>
+where
+    N: Number,
+{
+    /// Creates a new Sphere.
+    pub fn new(center: Vec3<N>, radius: N) -> Self {
+        Self { center, radius }
+    }
+
+    /// The bounding box of the sphere.
+    pub fn aabb(&self) -> Aabb<N> {
+        let extents = Vec3::new(self.radius, self.radius, self.radius);
+        Aabb::new(self.center - extents, self.center + extents)
+    }
+
+    /// Whether the point is inside the sphere.
+    pub fn contains_point(&self, point: Vec3<N>) -> bool {
+        (point - self.center).len_squared() <= self.radius.sqrd()
+    }
+
+    /// The closest point in the sphere to the given point. Points inside the sphere are returned as is.
+    pub fn closest_point(&self, point: Vec3<N>) -> Vec3<N> {
+        if self.contains_point(point) {
+            return point;
+        }
+
+        self.center + (point - self.center).unit_vector() * self.radius
+    }
+
+    /// Whether the two spheres overlap.
+    pub fn overlaps_sphere(&self, other: &Self) -> bool {
+        (other.center - self.center).len_squared() <= (self.radius + other.radius).sqrd()
+    }
+
+    /// Collides the two spheres, with the normal pointing towards the other sphere.
+    pub fn collide_sphere(&self, other: &Self) -> Option<Manifold<N>> {
+        sphere_vs_sphere(self.center, self.radius, other.center, other.radius)
+    }
+
+    /// Whether the sphere overlaps the box.
+    pub fn overlaps_aabb(&self, aabb: &Aabb<N>) -> bool {
+        self.contains_point(aabb.closest_point(self.center))
+    }
+
+    /// Casts a ray against the sphere. Rays starting inside the sphere hit immediately.
+    /// Derived from Real-Time Collision Detection, 5.3.2.
+    pub fn raycast(&self, ray: &Ray<N>) -> Option<RaycastHit<N>> {
+        let zero = N::i32(0);
+
+        let m = ray.origin - self.center;
+        let b = m.dot(ray.direction);
+        let c = m.len_squared() - self.radius.sqrd();
+
+        // Outside and pointing away
+        if c > zero && b > zero {
+            return None;
+        }
+
+        let discriminant = b.sqrd() - c;
+        if discriminant < zero {
+            return None;
+        }
+
+        let distance = -b - discriminant.sqrt();
+
+        if distance < zero {
+            return Some(RaycastHit {
+                distance: zero,
+                normal: -ray.direction,
+            });
+        }
+
+        if distance > ray.max_distance {
+            return None;
+        }
+
+        Some(RaycastHit {
+            distance,
+            normal: (ray.point_at(distance) - self.center) / self.radius,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type S = Sphere<f32>;
+
+    #[test]
+    fn sphere_overlaps_returns_expected() {
+        let a = S::new(Vec3::new(0., 0., 0.), 2.);
+
+        assert_eq!(true, a.overlaps_sphere(&S::new(Vec3::new(3., 0., 0.), 1.)));
+        assert_eq!(false, a.overlaps_sphere(&S::new(Vec3::new(3.1, 0., 0.), 1.)));
+
+        let aabb = Aabb::new(Vec3::new(1., 1., -1.), Vec3::new(3., 3., 1.));
+        assert_eq!(true, a.overlaps_aabb(&aabb));
+        assert_eq!(false, S::new(Vec3::new(0., 0., 0.), 1.).overlaps_aabb(&aabb));
+    }
+
+    #[test]
+    fn sphere_collide_returns_manifold() {
+        let a = S::new(Vec3::new(0., 0., 0.), 2.);
+        let b = S::new(Vec3::new(0., 2.5, 0.), 1.);
+
+        let expected = Manifold {
+            penetration: 0.5,
+            normal: Vec3::new(0., 1., 0.),
+        };
+        assert_eq!(Some(expected), a.collide_sphere(&b));
+        assert_eq!(None, a.collide_sphere(&S::new(Vec3::new(0., 4., 0.), 1.)));
+    }
+
+    #[test]
+    fn sphere_closest_point_returns_expected() {
+        let sphere = S::new(Vec3::new(1., 0., 0.), 2.);
+
+        assert_eq!(Vec3::new(1., 2., 0.), sphere.closest_point(Vec3::new(1., 5., 0.)));
+        assert_eq!(Vec3::new(1., 1., 0.), sphere.closest_point(Vec3::new(1., 1., 0.)));
+    }
+
+    #[test]
+    fn sphere_raycast_returns_expected() {
+        let sphere = S::new(Vec3::new(10., 0., 0.), 2.);
+
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 100.);
+        let expected = RaycastHit {
+            distance: 8.,
+            normal: Vec3::new(-1., 0., 0.),
+        };
+        assert_eq!(Some(expected), sphere.raycast(&ray));
+
+        // Misses
+        let ray = Ray::new(Vec3::new(0., 3., 0.), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(None, sphere.raycast(&ray));
+        let ray = Ray::new(Vec3::default(), Vec3::new(-1., 0., 0.), 100.);
+        assert_eq!(None, sphere.raycast(&ray));
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 7.);
+        assert_eq!(None, sphere.raycast(&ray));
+
+        // Inside
+        let ray = Ray::new(Vec3::new(10., 0., 0.), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(Some(0.), sphere.raycast(&ray).map(|hit| hit.distance));
+    }
+}
@@ -0,0 +1,217 @@
+use super::*;
+
+/// A triangle. Counter clockwise winding is the front face.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Triangle<N>
+where
+    N: Number,
+{
+    pub a: Vec3<N>,
+    pub b: Vec3<N>,
+    pub c: Vec3<N>,
+}
+
+impl<N> Triangle<N>
+where
+    N: Number,
+{
+    /// Creates a new Triangle.
+    pub fn new(a: Vec3<N>, b: Vec3<N>, c: Vec3<N>) -> Self {
+        Self { a, b, c }
+    }
+
+    /// The normal of the front face.
+    pub fn normal(&self) -> Vec3<N> {
+        (self.b - self.a).cross(self.c - self.a).unit_vector()
+    }
+
+    /// The plane the triangle lies on.
+    pub fn plane(&self) -> Plane<N> {
+        Plane::from_point_normal(self.a, self.normal())
+    }
+
+    /// The bounding box of the triangle.
+    pub fn aabb(&self) -> Aabb<N> {
+        Aabb::new(
+            self.a.min(self.b).min(self.c),
+            self.a.max(self.b).max(self.c),
+        )
+    }
+
+    /// The closest point on the triangle to the given point.
+    /// Derived from Real-Time Collision Detection, 5.1.5.
+    pub fn closest_point(&self, point: Vec3<N>) -> Vec3<N> {
+        let zero = N::i32(0);
+        let (a, b, c) = (self.a, self.b, self.c);
+
+        let ab = b - a;
+        let ac = c - a;
+
+        // Vertex region A
+        let ap = point - a;
+        let d1 = ab.dot(ap);
+        let d2 = ac.dot(ap);
+        if d1 <= zero && d2 <= zero {
+            return a;
+        }
+
+        // Vertex region B
+        let bp = point - b;
+        let d3 = ab.dot(bp);
+        let d4 = ac.dot(bp);
+        if d3 >= zero && d4 <= d3 {
+            return b;
+        }
+
+        // Edge region AB
+        let vc = d1 * d4 - d3 * d2;
+        if vc <= zero && d1 >= zero && d3 <= zero {
+            let v = d1 / (d1 - d3);
+            return a + ab * v;
+        }
+
+        // Vertex region C
+        let cp = point - c;
+        let d5 = ab.dot(cp);
+        let d6 = ac.dot(cp);
+        if d6 >= zero && d5 <= d6 {
+            return c;
+        }
+
+        // Edge region AC
+        let vb = d5 * d2 - d1 * d6;
+        if vb <= zero && d2 >= zero && d6 <= zero {
+            let w = d2 / (d2 - d6);
+            return a + ac * w;
+        }
+
+        // Edge region BC
+        let va = d3 * d6 - d5 * d4;
+        if va <= zero && (d4 - d3) >= zero && (d5 - d6) >= zero {
+            let w = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+            return b + (c - b) * w;
+        }
+
+        // Inside the face
+        let denom = va + vb + vc;
+        let v = vb / denom;
+        let w = vc / denom;
+
+        a + ab * v + ac * w
+    }
+
+    /// Whether the triangle overlaps the sphere.
+    pub fn overlaps_sphere(&self, sphere: &Sphere<N>) -> bool {
+        sphere.contains_point(self.closest_point(sphere.center))
+    }
+
+    /// Casts a ray against both sides of the triangle. The normal faces the ray.
+    /// Derived from the Moller-Trumbore algorithm.
+    pub fn raycast(&self, ray: &Ray<N>) -> Option<RaycastHit<N>> {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+
+        let ab = self.b - self.a;
+        let ac = self.c - self.a;
+
+        let p = ray.direction.cross(ac);
+        let determinant = ab.dot(p);
+
+        // Parallel to the triangle
+        if determinant == zero {
+            return None;
+        }
+
+        let t = ray.origin - self.a;
+        let u = t.dot(p) / determinant;
+        if u < zero || u > one {
+            return None;
+        }
+
+        let q = t.cross(ab);
+        let v = ray.direction.dot(q) / determinant;
+        if v < zero || u + v > one {
+            return None;
+        }
+
+        let distance = ac.dot(q) / determinant;
+        if !ray.in_range(distance) {
+            return None;
+        }
+
+        let normal = self.normal();
+        let normal = if normal.dot(ray.direction) > zero { -normal } else { normal };
+
+        Some(RaycastHit { distance, normal })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type T = Triangle<f32>;
+
+    fn triangle() -> T {
+        T::new(Vec3::new(0., 0., 0.), Vec3::new(4., 0., 0.), Vec3::new(0., 4., 0.))
+    }
+
+    #[test]
+    fn triangle_normal_returns_expected() {
+        assert_eq!(Vec3::new(0., 0., 1.), triangle().normal());
+        assert_eq!(
+            Aabb::new(Vec3::new(0., 0., 0.), Vec3::new(4., 4., 0.)),
+            triangle().aabb()
+        );
+    }
+
+    #[test]
+    fn triangle_closest_point_returns_expected() {
+        let t = triangle();
+
+        // Face
+        assert_eq!(Vec3::new(1., 1., 0.), t.closest_point(Vec3::new(1., 1., 5.)));
+        // Vertices
+        assert_eq!(t.a, t.closest_point(Vec3::new(-1., -1., 0.)));
+        assert_eq!(t.b, t.closest_point(Vec3::new(6., -1., 0.)));
+        assert_eq!(t.c, t.closest_point(Vec3::new(-1., 6., 0.)));
+        // Edges
+        assert_eq!(Vec3::new(2., 0., 0.), t.closest_point(Vec3::new(2., -3., 0.)));
+        assert_eq!(Vec3::new(0., 2., 0.), t.closest_point(Vec3::new(-3., 2., 0.)));
+        assert_eq!(Vec3::new(2., 2., 0.), t.closest_point(Vec3::new(3., 3., 0.)));
+    }
+
+    #[test]
+    fn triangle_overlaps_sphere_returns_expected() {
+        let t = triangle();
+
+        assert_eq!(true, t.overlaps_sphere(&Sphere::new(Vec3::new(1., 1., 1.), 1.)));
+        assert_eq!(false, t.overlaps_sphere(&Sphere::new(Vec3::new(1., 1., 1.1), 1.)));
+    }
+
+    #[test]
+    fn triangle_raycast_returns_expected() {
+        let t = triangle();
+
+        let ray = Ray::new(Vec3::new(1., 1., 5.), Vec3::new(0., 0., -1.), 100.);
+        let expected = RaycastHit {
+            distance: 5.,
+            normal: Vec3::new(0., 0., 1.),
+        };
+        assert_eq!(Some(expected), t.raycast(&ray));
+
+        // Back face
+        let ray = Ray::new(Vec3::new(1., 1., -5.), Vec3::new(0., 0., 1.), 100.);
+        let expected = RaycastHit {
+            distance: 5.,
+            normal: Vec3::new(0., 0., -1.),
+        };
+        assert_eq!(Some(expected), t.raycast(&ray));
+
+        // Misses
+        let ray = Ray::new(Vec3::new(3., 3., 5.), Vec3::new(0., 0., -1.), 100.);
+        assert_eq!(None, t.raycast(&ray));
+        let ray = Ray::new(Vec3::new(1., 1., 5.), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(None, t.raycast(&ray));
+    }
+}
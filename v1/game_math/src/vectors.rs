@@ -1,65 +1,492 @@
-#[derive(Copy, Clone, PartialEq, Debug)]
-pub struct Vec3<N> {
-    pub x: N,
-    pub y: N,
-    pub z: N,
+use super::{Number, RawConverter};
+
+/// Implements the shared API for a vector type with the given components.
+macro_rules! implement_vector {
+    ($vec:ident, $size:literal, $($field:ident),+) => {
+        #[derive(Copy, Clone, PartialEq, Debug)]
+        pub struct $vec<N>
+        where
+            N: Number,
+        {
+            $(pub $field: N,)+
+        }
+
+        impl<N> $vec<N>
+        where
+            N: Number,
+        {
+            /// Vector set to 0.
+            pub fn default() -> Self {
+                Self::i32(0)
+            }
+
+            pub fn new($($field: N),+) -> Self {
+                Self { $($field),+ }
+            }
+
+            /// Vector with all components set to the given value.
+            pub fn i32(i: i32) -> Self {
+                Self::splat(N::i32(i))
+            }
+
+            /// Vector with all components set to the given value.
+            pub fn splat(n: N) -> Self {
+                Self { $($field: n),+ }
+            }
+
+            /// Dot product of two vectors.
+            pub fn dot(&self, other: Self) -> N {
+                let mut sum = N::i32(0);
+                $(sum += self.$field * other.$field;)+
+                sum
+            }
+
+            /// The length squared of the vector.
+            pub fn len_squared(&self) -> N {
+                self.dot(*self)
+            }
+
+            /// The length of the vector.
+            pub fn len(&self) -> N {
+                self.len_squared().sqrt()
+            }
+
+            /// The distance between two points.
+            pub fn distance(&self, other: Self) -> N {
+                (other - *self).len()
+            }
+
+            /// The distance squared between two points.
+            pub fn distance_squared(&self, other: Self) -> N {
+                (other - *self).len_squared()
+            }
+
+            /// A vector normalized to length = 1. Zero length vectors return NaN or saturate.
+            pub fn unit_vector(&self) -> Self {
+                *self / self.len()
+            }
+
+            /// A vector normalized to length = 1. Zero length vectors are returned as is.
+            pub fn normalize(&self) -> Self {
+                let len = self.len();
+                if len == N::i32(0) {
+                    return *self;
+                }
+
+                *self / len
+            }
+
+            /// Linearly interpolates between two vectors. A t of 0 returns self, a t of 1 returns other.
+            pub fn lerp(&self, other: Self, t: N) -> Self {
+                *self + (other - *self) * t
+            }
+
+            /// Componentwise absolute value.
+            pub fn abs(&self) -> Self {
+                Self { $($field: self.$field.abs()),+ }
+            }
+
+            /// Componentwise clamp between min and max.
+            pub fn clamp(&self, min: Self, max: Self) -> Self {
+                self.max(min).min(max)
+            }
+
+            /// Componentwise min.
+            pub fn min(&self, other: Self) -> Self {
+                Self { $($field: self.$field.min(other.$field)),+ }
+            }
+
+            /// Componentwise max.
+            pub fn max(&self, other: Self) -> Self {
+                Self { $($field: self.$field.max(other.$field)),+ }
+            }
+        }
+
+        impl<N> RawConverter for $vec<N>
+        where
+            N: Number,
+        {
+            type RawType = [N; $size];
+
+            fn to_raw(&self) -> Self::RawType {
+                [$(self.$field),+]
+            }
+
+            fn from_raw(raw: Self::RawType) -> Self {
+                let [$($field),+] = raw;
+                Self { $($field),+ }
+            }
+        }
+
+        impl<N> std::ops::Neg for $vec<N>
+        where
+            N: Number,
+        {
+            type Output = Self;
+
+            fn neg(self) -> Self {
+                Self { $($field: -self.$field),+ }
+            }
+        }
+
+        implement_vector_op!($vec, Add, add, AddAssign, add_assign, +, +=, $($field),+);
+        implement_vector_op!($vec, Sub, sub, SubAssign, sub_assign, -, -=, $($field),+);
+        implement_vector_op!($vec, Mul, mul, MulAssign, mul_assign, *, *=, $($field),+);
+        implement_vector_op!($vec, Div, div, DivAssign, div_assign, /, /=, $($field),+);
+    };
 }
 
-impl<N> Vec3<N> {
-    pub fn default() -> Self {
-        unimplemented!();
-    }
+/// Implements the componentwise and scalar versions of an operator for a vector.
+macro_rules! implement_vector_op {
+    ($vec:ident, $op:ident, $fn:ident, $op_assign:ident, $fn_assign:ident, $sym:tt, $sym_assign:tt, $($field:ident),+) => {
+        impl<N> std::ops::$op for $vec<N>
+        where
+            N: Number,
+        {
+            type Output = Self;
+
+            fn $fn(self, rhs: Self) -> Self {
+                Self { $($field: self.$field $sym rhs.$field),+ }
+            }
+        }
+
+        impl<N> std::ops::$op<N> for $vec<N>
+        where
+            N: Number,
+        {
+            type Output = Self;
+
+            fn $fn(self, rhs: N) -> Self {
+                Self { $($field: self.$field $sym rhs),+ }
+            }
+        }
+
+        impl<N> std::ops::$op_assign for $vec<N>
+        where
+            N: Number,
+        {
+            fn $fn_assign(&mut self, rhs: Self) {
+                $(self.$field $sym_assign rhs.$field;)+
+            }
+        }
 
-    pub fn min(&self, other: Self) -> Self {
-        unimplemented!("Componentwise min.");
+        impl<N> std::ops::$op_assign<N> for $vec<N>
+        where
+            N: Number,
+        {
+            fn $fn_assign(&mut self, rhs: N) {
+                $(self.$field $sym_assign rhs;)+
+            }
+        }
+    };
+}
+
+/// Implements swizzles that return a new vector built from the given components.
+macro_rules! implement_swizzles {
+    ($vec:ident, $($swizzle:ident => $out:ident($($field:ident),+)),+) => {
+        impl<N> $vec<N>
+        where
+            N: Number,
+        {
+            $(
+                pub fn $swizzle(&self) -> $out<N> {
+                    $out::new($(self.$field),+)
+                }
+            )+
+        }
+    };
+}
+
+implement_vector!(Vec2, 2, x, y);
+implement_vector!(Vec3, 3, x, y, z);
+implement_vector!(Vec4, 4, x, y, z, w);
+
+implement_swizzles!(Vec2,
+    yx => Vec2(y, x)
+);
+
+implement_swizzles!(Vec3,
+    xy => Vec2(x, y),
+    xz => Vec2(x, z),
+    yz => Vec2(y, z),
+    yx => Vec2(y, x),
+    zx => Vec2(z, x),
+    zy => Vec2(z, y),
+    xzy => Vec3(x, z, y),
+    yxz => Vec3(y, x, z),
+    yzx => Vec3(y, z, x),
+    zxy => Vec3(z, x, y),
+    zyx => Vec3(z, y, x)
+);
+
+implement_swizzles!(Vec4,
+    xy => Vec2(x, y),
+    xz => Vec2(x, z),
+    yz => Vec2(y, z),
+    xyz => Vec3(x, y, z),
+    vec3 => Vec3(x, y, z),
+    wzyx => Vec4(w, z, y, x)
+);
+
+impl<N> Vec2<N>
+where
+    N: Number,
+{
+    /// The vector rotated 90 degrees counter clockwise.
+    pub fn perpendicular(&self) -> Self {
+        Self::new(-self.y, self.x)
     }
 
-    pub fn max(&self, other: Self) -> Self {
-        unimplemented!("Componentwise max");
+    /// Extends the vector with a z component.
+    pub fn extend(&self, z: N) -> Vec3<N> {
+        Vec3::new(self.x, self.y, z)
     }
 }
 
-impl<R> std::ops::Mul for Vec3<R> {
-    type Output = Self;
-    fn mul(self, rhs: Self) -> Self {
-        unimplemented!();
-        /*
+impl<N> Vec3<N>
+where
+    N: Number,
+{
+    /// Cross product of two Vec3s.
+    pub fn cross(&self, other: Self) -> Self {
         Self {
-            x: self.x * rhs.x,
-            y: self.y * rhs.y,
-            z: self.z * rhs.z,
+            x: (self.y * other.z - self.z * other.y),
+            y: (self.z * other.x - self.x * other.z),
+            z: (self.x * other.y - self.y * other.x),
         }
-        */
+    }
+
+    /// Extends the vector with a w component.
+    pub fn extend(&self, w: N) -> Vec4<N> {
+        Vec4::new(self.x, self.y, self.z, w)
     }
 }
 
-impl<R> std::ops::Mul<R> for Vec3<R> {
-    type Output = Vec3<R>;
-    fn mul(self, rhs: R) -> Vec3<R> {
-        unimplemented!();
-        /*
-        Self {
-            x: self.x * rhs,
-            y: self.y * rhs,
-            z: self.z * rhs,
-        }
-        */
+impl<N> From<(i32, i32)> for Vec2<N>
+where
+    N: Number,
+{
+    fn from(i: (i32, i32)) -> Self {
+        Self::new(N::i32(i.0), N::i32(i.1))
+    }
+}
+
+impl<N> From<(i32, i32, i32)> for Vec3<N>
+where
+    N: Number,
+{
+    fn from(i: (i32, i32, i32)) -> Self {
+        Self::new(N::i32(i.0), N::i32(i.1), N::i32(i.2))
     }
 }
 
-impl<R> std::ops::MulAssign<R> for Vec3<R> {
-    fn mul_assign(&mut self, rhs: R) {
-        unimplemented!();
-        /*
-        self.x *= rhs;
-        self.y *= rhs;
-        self.z *= rhs;
-        */
+impl<N> From<(i32, i32, i32, i32)> for Vec4<N>
+where
+    N: Number,
+{
+    fn from(i: (i32, i32, i32, i32)) -> Self {
+        Self::new(N::i32(i.0), N::i32(i.1), N::i32(i.2), N::i32(i.3))
     }
 }
 
-impl<R> std::ops::AddAssign for Vec3<R> {
-    fn add_assign(&mut self, rhs: Self) {
-        unimplemented!();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fix::Fix;
+
+    /// Runs each generic test against both f32 and fixed point numbers.
+    macro_rules! shared_tests {
+        ($($test:ident),+) => {
+            mod f32_tests {
+                $(
+                    #[test]
+                    fn $test() {
+                        super::$test::<f32>();
+                    }
+                )+
+            }
+
+            mod fix_tests {
+                use crate::fix::Fix;
+                $(
+                    #[test]
+                    fn $test() {
+                        super::$test::<Fix>();
+                    }
+                )+
+            }
+        };
+    }
+
+    shared_tests!(
+        vectors_default_returns_zero,
+        vectors_ops_return_expected,
+        vectors_assign_ops_return_expected,
+        vectors_dot_len_distance_return_expected,
+        vectors_normalize_returns_expected,
+        vectors_lerp_returns_expected,
+        vectors_abs_clamp_min_max_return_expected,
+        vectors_swizzles_return_expected,
+        vectors_raw_roundtrips
+    );
+
+    fn half<N: Number>() -> N {
+        N::i32(1) / N::i32(2)
+    }
+
+    fn vectors_default_returns_zero<N: Number>() {
+        let zero = N::i32(0);
+
+        assert_eq!(Vec2::new(zero, zero), Vec2::default());
+        assert_eq!(Vec3::new(zero, zero, zero), Vec3::default());
+        assert_eq!(Vec4::new(zero, zero, zero, zero), Vec4::default());
+        assert_eq!(Vec3::<N>::from((3, 3, 3)), Vec3::i32(3));
+    }
+
+    fn vectors_ops_return_expected<N: Number>() {
+        let a = Vec3::<N>::from((2, -4, 6));
+        let b = Vec3::<N>::from((1, 2, -3));
+
+        assert_eq!(Vec3::from((-2, 4, -6)), -a);
+        assert_eq!(Vec3::from((3, -2, 3)), a + b);
+        assert_eq!(Vec3::from((1, -6, 9)), a - b);
+        assert_eq!(Vec3::from((2, -8, -18)), a * b);
+        assert_eq!(Vec3::from((2, -2, -2)), a / b);
+
+        assert_eq!(Vec3::from((4, -2, 8)), a + N::i32(2));
+        assert_eq!(Vec3::from((0, -6, 4)), a - N::i32(2));
+        assert_eq!(Vec3::from((4, -8, 12)), a * N::i32(2));
+        assert_eq!(Vec3::from((1, -2, 3)), a / N::i32(2));
+
+        let a = Vec2::<N>::from((2, -4));
+        assert_eq!(Vec2::from((3, -2)), a + Vec2::from((1, 2)));
+        let a = Vec4::<N>::from((2, -4, 6, 8));
+        assert_eq!(Vec4::from((1, -2, 3, 4)), a / N::i32(2));
+    }
+
+    fn vectors_assign_ops_return_expected<N: Number>() {
+        let mut a = Vec4::<N>::from((2, -4, 6, 8));
+        let b = Vec4::<N>::from((1, 2, -3, 4));
+
+        a += b;
+        assert_eq!(Vec4::from((3, -2, 3, 12)), a);
+        a -= b;
+        assert_eq!(Vec4::from((2, -4, 6, 8)), a);
+        a *= b;
+        assert_eq!(Vec4::from((2, -8, -18, 32)), a);
+        a /= b;
+        assert_eq!(Vec4::from((2, -4, 6, 8)), a);
+
+        a += N::i32(1);
+        assert_eq!(Vec4::from((3, -3, 7, 9)), a);
+        a -= N::i32(1);
+        assert_eq!(Vec4::from((2, -4, 6, 8)), a);
+        a *= N::i32(3);
+        assert_eq!(Vec4::from((6, -12, 18, 24)), a);
+        a /= N::i32(6);
+        assert_eq!(Vec4::from((1, -2, 3, 4)), a);
+    }
+
+    fn vectors_dot_len_distance_return_expected<N: Number>() {
+        let a = Vec3::<N>::from((2, 3, 6));
+        let b = Vec3::<N>::from((1, -2, 3));
+
+        assert_eq!(N::i32(14), a.dot(b));
+        assert_eq!(N::i32(49), a.len_squared());
+        assert_eq!(N::i32(7), a.len());
+        assert_eq!(Vec3::from((21, 0, -7)), a.cross(b));
+
+        assert_eq!(N::i32(5), Vec2::<N>::from((3, 4)).len());
+        assert_eq!(N::i32(2), Vec4::<N>::from((1, 1, 1, 1)).len());
+
+        let c = Vec3::<N>::from((1, 2, 3));
+        assert_eq!(N::i32(49), c.distance_squared(c + a));
+        assert_eq!(N::i32(7), c.distance(c - a));
+    }
+
+    fn vectors_normalize_returns_expected<N: Number>() {
+        let a = Vec3::<N>::from((0, 3, 4));
+        let expected = Vec3::new(N::i32(0), N::i32(3) / N::i32(5), N::i32(4) / N::i32(5));
+
+        assert_eq!(expected, a.normalize());
+        assert_eq!(expected, a.unit_vector());
+        assert_eq!(Vec2::from((0, -1)), Vec2::<N>::from((0, -8)).normalize());
+        assert_eq!(Vec4::<N>::default(), Vec4::default().normalize());
+    }
+
+    fn vectors_lerp_returns_expected<N: Number>() {
+        let a = Vec3::<N>::from((2, -4, 6));
+        let b = Vec3::<N>::from((4, 0, -6));
+
+        assert_eq!(a, a.lerp(b, N::i32(0)));
+        assert_eq!(b, a.lerp(b, N::i32(1)));
+        assert_eq!(Vec3::from((3, -2, 0)), a.lerp(b, half()));
+        assert_eq!(
+            Vec2::from((6, 4)),
+            Vec2::<N>::from((2, 0)).lerp(Vec2::from((4, 2)), N::i32(2))
+        );
+    }
+
+    fn vectors_abs_clamp_min_max_return_expected<N: Number>() {
+        let a = Vec3::<N>::from((-2, 5, 0));
+        let b = Vec3::<N>::from((1, -3, 0));
+
+        assert_eq!(Vec3::from((2, 5, 0)), a.abs());
+        assert_eq!(Vec3::from((-2, -3, 0)), a.min(b));
+        assert_eq!(Vec3::from((1, 5, 0)), a.max(b));
+        assert_eq!(Vec3::from((-1, 1, 0)), a.clamp(Vec3::i32(-1), Vec3::i32(1)));
+
+        let a = Vec4::<N>::from((-2, 5, 0, 3));
+        assert_eq!(
+            Vec4::from((0, 2, 0, 2)),
+            a.clamp(Vec4::i32(0), Vec4::i32(2))
+        );
+    }
+
+    fn vectors_swizzles_return_expected<N: Number>() {
+        let v2 = Vec2::<N>::from((1, 2));
+        let v3 = Vec3::<N>::from((1, 2, 3));
+        let v4 = Vec4::<N>::from((1, 2, 3, 4));
+
+        assert_eq!(Vec2::from((2, 1)), v2.yx());
+        assert_eq!(Vec2::from((-2, 1)), v2.perpendicular());
+        assert_eq!(v3, v2.extend(N::i32(3)));
+
+        assert_eq!(Vec2::from((1, 2)), v3.xy());
+        assert_eq!(Vec2::from((1, 3)), v3.xz());
+        assert_eq!(Vec2::from((2, 3)), v3.yz());
+        assert_eq!(Vec2::from((3, 2)), v3.zy());
+        assert_eq!(Vec3::from((3, 2, 1)), v3.zyx());
+        assert_eq!(Vec3::from((2, 3, 1)), v3.yzx());
+        assert_eq!(v4, v3.extend(N::i32(4)));
+
+        assert_eq!(v3, v4.xyz());
+        assert_eq!(v3, v4.vec3());
+        assert_eq!(Vec2::from((1, 3)), v4.xz());
+        assert_eq!(Vec4::from((4, 3, 2, 1)), v4.wzyx());
+    }
+
+    fn vectors_raw_roundtrips<N: Number>() {
+        let v3 = Vec3::<N>::from((1, 2, 3));
+        let v4 = Vec4::<N>::from((1, 2, 3, 4));
+
+        assert_eq!([N::i32(1), N::i32(2), N::i32(3)], v3.to_raw());
+        assert_eq!(v3, Vec3::from_raw(v3.to_raw()));
+        assert_eq!(v4, Vec4::from_raw(v4.to_raw()));
+        assert_eq!(
+            Vec2::from((5, 6)),
+            Vec2::<N>::from_raw([N::i32(5), N::i32(6)])
+        );
+    }
+
+    #[test]
+    fn vectors_fixed_point_matches_f32() {
+        let f = Vec3::new(1.5, -2.25, 3.).lerp(Vec3::new(-0.5, 4., 1.), 0.25);
+        let fix = Vec3::new(Fix::from_f32(1.5), Fix::from_f32(-2.25), Fix::from_int(3)).lerp(
+            Vec3::new(Fix::from_f32(-0.5), Fix::from_int(4), Fix::from_int(1)),
+            Fix::from_f32(0.25),
+        );
+
+        assert_eq!(f, Vec3::new(fix.x.to_f32(), fix.y.to_f32(), fix.z.to_f32()));
     }
 }
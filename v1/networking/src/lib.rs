@@ -3,5 +3,6 @@ extern crate lazy_static;
 
 mod encryption;
 mod network;
+pub use network::{Bitstream, Packable};
 
 pub mod rollback;
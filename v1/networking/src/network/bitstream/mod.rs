@@ -25,6 +25,19 @@ impl Bitstream {
         }
     }
 
+    /// Creates a new Bitstream for reading the given bytes.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        let bit_len = bytes.len() * 8;
+
+        Self {
+            buffer: bytes,
+            staging: 0,
+            total_bit_len: bit_len,
+            number_of_staging_bits: 0,
+            max_bits: bit_len,
+        }
+    }
+
     /// Flushes all pending operations and returns the final Vec of bytes.
     pub fn buffer(&mut self) -> Vec<u8> {
         self.flush();
@@ -188,6 +201,20 @@ mod tests {
         assert_eq!(100 * 8, bs.max_bits);
     }
 
+    #[test]
+    fn bitstream_from_bytes_reads_written_values() {
+        let mut bs = Bitstream::new(100);
+        bs.write(true);
+        bs.write(343_u32);
+        bs.write(-2.5_f32);
+
+        let mut bs = Bitstream::from_bytes(bs.buffer());
+
+        assert_eq!(true, bs.read().unwrap());
+        assert_eq!(343, bs.read::<u32>().unwrap());
+        assert_eq!(-2.5, bs.read::<f32>().unwrap());
+    }
+
     #[test]
     fn bitstream_read_byte_in_staging_and_buffer_works_as_expected() {
         let byte_capacity = 2;
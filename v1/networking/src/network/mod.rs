@@ -1,4 +1,5 @@
 mod bitstream;
+pub use bitstream::{Bitstream, Packable};
 mod packet;
 pub mod socket_manager;
 //pub mod stream_manager;
@@ -7,14 +7,17 @@ pub mod prelude {
     };
 }
 
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum RollbackEvent {
-    Disconnected,
+    Disconnected(PlayerId),
+    /// The sync test simulated the frame again and got a different state.
+    Desync(FrameId),
 }
 
 pub trait GameInput: Copy + Clone + Default {}
 
 pub enum RollbackNetcodeModes {
-    /// Initiate a rollback every frame, comparing the saved state to the current one. If they aren't the same will panic.
+    /// Initiate a rollback every frame, comparing the saved state to the current one. If they aren't the same a `RollbackEvent::Desync` is returned.
     SyncTest,
     /// Lag test. Will pick a random value between the min and max latency and apply it.
     LagTest {
@@ -115,10 +118,13 @@ where
 
     /// Tick the game.
     pub fn tick(&mut self) -> Vec<RollbackEvent> {
+        let mut events = vec![];
+        let mut sync_test = false;
+
         for rollback_mode in &self.rollback_modes {
             match rollback_mode {
                 RollbackNetcodeModes::SyncTest => {
-                    unimplemented!("TODO: need to figure out sync test.");
+                    sync_test = true;
                 }
                 RollbackNetcodeModes::LagTest {
                     min_latency,
@@ -154,8 +160,7 @@ where
             // Run it until the last confirmed input frames meet the new confirmed frame. Registering the inputs for each frame then ticking the world.
             let num_rollbacks = self.confirmed_frame..confirmed_input_frame;
             for rollback_frame in num_rollbacks.clone() {
-                self.register_input_for_frame(rollback_frame);
-                self.state.tick();
+                events.extend(self.simulate_frame(rollback_frame, sync_test));
             }
 
             // Update state to the last confirmed frame
@@ -165,20 +170,43 @@ where
             // Catch up until current frame.
             let num_catchups = confirmed_input_frame..self.current_frame;
             for catchup_frame in num_catchups.clone() {
-                self.register_input_for_frame(catchup_frame);
-                self.state.tick();
+                events.extend(self.simulate_frame(catchup_frame, sync_test));
             }
         } else {
             // No rollback, so just continue processing at one execution per tick.
-            self.register_input_for_frame(self.current_frame);
-            self.state.tick();
+            events.extend(self.simulate_frame(self.current_frame, sync_test));
         }
 
         // Increment frame.
         // TODO: how to handle wrapping frames?
         self.current_frame += 1;
 
-        vec![]
+        events
+    }
+
+    /// Register the inputs for the given frame and tick the state.
+    /// When sync testing, the frame is rolled back and simulated again, returning a desync if the results differ.
+    fn simulate_frame(&mut self, frame: FrameId, sync_test: bool) -> Option<RollbackEvent> {
+        let saved_state = match sync_test {
+            true => Some(self.state.clone()),
+            false => None,
+        };
+
+        self.register_input_for_frame(frame);
+        self.state.tick();
+
+        if let Some(saved_state) = saved_state {
+            let simulated_state = std::mem::replace(&mut self.state, saved_state);
+
+            self.register_input_for_frame(frame);
+            self.state.tick();
+
+            if self.state != simulated_state {
+                return Some(RollbackEvent::Desync(frame));
+            }
+        }
+
+        None
     }
 
     /// Register the input for the given frame to the state.
@@ -124,40 +124,11 @@ impl InputHandler {
                                     ElementState::Pressed => input::PressState::Pressed,
                                 };
 
-                                if let Some(keycode) = virtual_keycode {
-                                    match keycode {
-                                        VirtualKeyCode::Left => {
-                                            return Some(input::Input::Key {
-                                                key: input::Key::Left,
-                                                state: input_state,
-                                            });
-                                        }
-                                        VirtualKeyCode::Up => {
-                                            return Some(input::Input::Key {
-                                                key: input::Key::Up,
-                                                state: input_state,
-                                            });
-                                        }
-                                        VirtualKeyCode::Right => {
-                                            return Some(input::Input::Key {
-                                                key: input::Key::Right,
-                                                state: input_state,
-                                            });
-                                        }
-                                        VirtualKeyCode::Down => {
-                                            return Some(input::Input::Key {
-                                                key: input::Key::Down,
-                                                state: input_state,
-                                            });
-                                        }
-                                        VirtualKeyCode::Space => {
-                                            return Some(input::Input::Key {
-                                                key: input::Key::Space,
-                                                state: input_state,
-                                            });
-                                        }
-                                        _ => {}
-                                    }
+                                if let Some(key) = (*virtual_keycode).and_then(key_from_keycode) {
+                                    return Some(input::Input::Key {
+                                        key,
+                                        state: input_state,
+                                    });
                                 }
                             }
                             _ => {}
@@ -275,3 +246,102 @@ pub fn setup(
 
     (event_loop, window, state)
 }
+
+/// Maps a winit key to a portia key, if it's one portia knows about.
+fn key_from_keycode(keycode: VirtualKeyCode) -> Option<input::Key> {
+    let key = match keycode {
+        VirtualKeyCode::Up => input::Key::Up,
+        VirtualKeyCode::Down => input::Key::Down,
+        VirtualKeyCode::Left => input::Key::Left,
+        VirtualKeyCode::Right => input::Key::Right,
+        VirtualKeyCode::Space => input::Key::Space,
+        VirtualKeyCode::A => input::Key::A,
+        VirtualKeyCode::B => input::Key::B,
+        VirtualKeyCode::C => input::Key::C,
+        VirtualKeyCode::D => input::Key::D,
+        VirtualKeyCode::E => input::Key::E,
+        VirtualKeyCode::F => input::Key::F,
+        VirtualKeyCode::G => input::Key::G,
+        VirtualKeyCode::H => input::Key::H,
+        VirtualKeyCode::I => input::Key::I,
+        VirtualKeyCode::J => input::Key::J,
+        VirtualKeyCode::K => input::Key::K,
+        VirtualKeyCode::L => input::Key::L,
+        VirtualKeyCode::M => input::Key::M,
+        VirtualKeyCode::N => input::Key::N,
+        VirtualKeyCode::O => input::Key::O,
+        VirtualKeyCode::P => input::Key::P,
+        VirtualKeyCode::Q => input::Key::Q,
+        VirtualKeyCode::R => input::Key::R,
+        VirtualKeyCode::S => input::Key::S,
+        VirtualKeyCode::T => input::Key::T,
+        VirtualKeyCode::U => input::Key::U,
+        VirtualKeyCode::V => input::Key::V,
+        VirtualKeyCode::W => input::Key::W,
+        VirtualKeyCode::X => input::Key::X,
+        VirtualKeyCode::Y => input::Key::Y,
+        VirtualKeyCode::Z => input::Key::Z,
+        VirtualKeyCode::F1 => input::Key::F1,
+        VirtualKeyCode::F2 => input::Key::F2,
+        VirtualKeyCode::F3 => input::Key::F3,
+        VirtualKeyCode::F4 => input::Key::F4,
+        VirtualKeyCode::F5 => input::Key::F5,
+        VirtualKeyCode::F6 => input::Key::F6,
+        VirtualKeyCode::F7 => input::Key::F7,
+        VirtualKeyCode::F8 => input::Key::F8,
+        VirtualKeyCode::F9 => input::Key::F9,
+        VirtualKeyCode::F10 => input::Key::F10,
+        VirtualKeyCode::F11 => input::Key::F11,
+        VirtualKeyCode::F12 => input::Key::F12,
+        VirtualKeyCode::Escape => input::Key::Escape,
+        VirtualKeyCode::Tab => input::Key::Tab,
+        VirtualKeyCode::Insert => input::Key::Insert,
+        VirtualKeyCode::Delete => input::Key::Delete,
+        VirtualKeyCode::Home => input::Key::Home,
+        VirtualKeyCode::End => input::Key::End,
+        VirtualKeyCode::PageUp => input::Key::PageUp,
+        VirtualKeyCode::PageDown => input::Key::PageDown,
+        VirtualKeyCode::LShift => input::Key::LShift,
+        VirtualKeyCode::RShift => input::Key::RShift,
+        VirtualKeyCode::LControl => input::Key::LControl,
+        VirtualKeyCode::RControl => input::Key::RControl,
+        VirtualKeyCode::LAlt => input::Key::LAlt,
+        VirtualKeyCode::RAlt => input::Key::RAlt,
+        VirtualKeyCode::Comma => input::Key::Comma,
+        VirtualKeyCode::Period => input::Key::Period,
+        VirtualKeyCode::Slash => input::Key::Slash,
+        VirtualKeyCode::Semicolon => input::Key::Semicolon,
+        VirtualKeyCode::Apostrophe => input::Key::Apostrophe,
+        VirtualKeyCode::LBracket => input::Key::LBracket,
+        VirtualKeyCode::RBracket => input::Key::RBracket,
+        VirtualKeyCode::Backslash => input::Key::Backslash,
+        VirtualKeyCode::Minus => input::Key::Minus,
+        VirtualKeyCode::Equals => input::Key::Equals,
+        VirtualKeyCode::Grave => input::Key::Grave,
+        VirtualKeyCode::Numpad0 => input::Key::Numpad0,
+        VirtualKeyCode::Numpad1 => input::Key::Numpad1,
+        VirtualKeyCode::Numpad2 => input::Key::Numpad2,
+        VirtualKeyCode::Numpad3 => input::Key::Numpad3,
+        VirtualKeyCode::Numpad4 => input::Key::Numpad4,
+        VirtualKeyCode::Numpad5 => input::Key::Numpad5,
+        VirtualKeyCode::Numpad6 => input::Key::Numpad6,
+        VirtualKeyCode::Numpad7 => input::Key::Numpad7,
+        VirtualKeyCode::Numpad8 => input::Key::Numpad8,
+        VirtualKeyCode::Numpad9 => input::Key::Numpad9,
+        VirtualKeyCode::Key0 => input::Key::Key0,
+        VirtualKeyCode::Key1 => input::Key::Key1,
+        VirtualKeyCode::Key2 => input::Key::Key2,
+        VirtualKeyCode::Key3 => input::Key::Key3,
+        VirtualKeyCode::Key4 => input::Key::Key4,
+        VirtualKeyCode::Key5 => input::Key::Key5,
+        VirtualKeyCode::Key6 => input::Key::Key6,
+        VirtualKeyCode::Key7 => input::Key::Key7,
+        VirtualKeyCode::Key8 => input::Key::Key8,
+        VirtualKeyCode::Key9 => input::Key::Key9,
+        VirtualKeyCode::Return => input::Key::Enter,
+        VirtualKeyCode::Back => input::Key::Backspace,
+        _ => return None,
+    };
+
+    Some(key)
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Input {
     Tick(u32),
@@ -6,21 +8,107 @@ pub enum Input {
     Mouse(Mouse),
     Window(Window),
     Key { key: Key, state: PressState },
+    Gamepad { id: GamepadId, event: Gamepad },
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum PressState {
     Pressed,
     Released,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Key {
     Up,
     Down,
     Left,
     Right,
     Space,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    Escape,
+    Tab,
+    Enter,
+    Backspace,
+    Insert,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
+    Comma,
+    Period,
+    Slash,
+    Semicolon,
+    Apostrophe,
+    LBracket,
+    RBracket,
+    Backslash,
+    Minus,
+    Equals,
+    Grave,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -43,3 +131,52 @@ pub enum Mouse {
     Released { x: i32, y: i32, button: MouseButton },
     Moved { x: i32, y: i32 },
 }
+
+/// Identifies a connected gamepad.
+pub type GamepadId = u8;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Gamepad {
+    Connected,
+    Disconnected,
+    Button {
+        button: GamepadButton,
+        state: PressState,
+    },
+    /// An axis moved. Sticks range from -1 to 1 with up and right positive, triggers from 0 to 1.
+    Axis {
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+/// Gamepad buttons, named by position so layouts from different vendors match.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    LeftStick,
+    RightStick,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
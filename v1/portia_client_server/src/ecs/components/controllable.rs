@@ -1,5 +1,6 @@
 use crate::ecs::prelude::*;
 use crate::math::*;
+use super::pack_nums;
 
 // TODO: this is the 'input' that controls an actor. Used for all ghostable/creatable objects.
 pub struct Controllable {
@@ -16,4 +17,20 @@ impl Component for Controllable {
             roll: 0.,
         }
     }
+
+    fn max_bit_size(_world_settings: &WorldSettings) -> usize {
+        3 * 32
+    }
+
+    fn pack(&self, stream: &mut Bitstream) -> bool {
+        pack_nums(&[self.yaw, self.pitch, self.roll], stream)
+    }
+
+    fn unpack(stream: &mut Bitstream, _world_settings: &WorldSettings) -> Option<Self> {
+        Some(Self {
+            yaw: stream.read()?,
+            pitch: stream.read()?,
+            roll: stream.read()?,
+        })
+    }
 }
@@ -1,4 +1,5 @@
 use crate::ecs::prelude::*;
+use crate::math::*;
 
 pub type GhostId = u32;
 
@@ -6,15 +7,79 @@ pub type GhostId = u32;
 pub struct Ghostable {
     clients: Vec<ClientId>,
     id: GhostId,
+    /// How important the item is to replicate. Accumulated each tick, so higher priorities are updated more often.
+    pub priority: Num,
 }
 
-impl Component for Ghostable {
-    fn default(world_settings: &WorldSettings) -> Self {
+impl Ghostable {
+    /// Creates a new Ghostable with the given priority.
+    pub fn new(id: GhostId, priority: Num, world_settings: &WorldSettings) -> Self {
         Self {
-            id: 0,
+            id,
+            priority,
             clients: Vec::with_capacity(world_settings.max_clients),
         }
     }
+
+    /// The clients the item is currently in scope for.
+    pub fn clients(&self) -> &Vec<ClientId> {
+        &self.clients
+    }
+
+    /// Returns whether the item is in scope for the given client.
+    pub fn is_scoped_for(&self, client: ClientId) -> bool {
+        self.clients.contains(&client)
+    }
+
+    /// Removes all clients from the item's scope.
+    pub fn clear_clients(&mut self) {
+        self.clients.clear();
+    }
+
+    /// Adds the client to the item's scope.
+    pub fn add_client(&mut self, client: ClientId) {
+        if !self.is_scoped_for(client) {
+            self.clients.push(client);
+        }
+    }
+
+    /// Removes the client from the item's scope.
+    pub fn remove_client(&mut self, client: ClientId) {
+        self.clients.retain(|c| *c != client);
+    }
+}
+
+impl Component for Ghostable {
+    fn default(world_settings: &WorldSettings) -> Self {
+        Self::new(0, 1., world_settings)
+    }
+
+    fn max_bit_size(world_settings: &WorldSettings) -> usize {
+        // id, priority, client count, then each client
+        32 + 32 + 32 + world_settings.max_clients * 32
+    }
+
+    fn pack(&self, stream: &mut Bitstream) -> bool {
+        stream.write(self.id)
+            && stream.write(self.priority)
+            && stream.write(self.clients.len() as u32)
+            && self.clients.iter().all(|client| stream.write(*client))
+    }
+
+    fn unpack(stream: &mut Bitstream, world_settings: &WorldSettings) -> Option<Self> {
+        let mut ghostable = Self::new(stream.read()?, stream.read()?, world_settings);
+
+        let num_clients: u32 = stream.read()?;
+        if num_clients as usize > world_settings.max_clients {
+            return None;
+        }
+
+        for _ in 0..num_clients {
+            ghostable.clients.push(stream.read()?);
+        }
+
+        Some(ghostable)
+    }
 }
 
 /// An item that is 'ghosted' from the server.
@@ -26,4 +91,16 @@ impl Component for Ghost {
     fn default(world_settings: &WorldSettings) -> Self {
         Self { id: 0 }
     }
+
+    fn max_bit_size(_world_settings: &WorldSettings) -> usize {
+        32
+    }
+
+    fn pack(&self, stream: &mut Bitstream) -> bool {
+        stream.write(self.id)
+    }
+
+    fn unpack(stream: &mut Bitstream, _world_settings: &WorldSettings) -> Option<Self> {
+        Some(Self { id: stream.read()? })
+    }
 }
@@ -5,9 +5,45 @@ pub mod spatial;
 pub mod mesh;
 
 use crate::ecs::prelude::*;
+use crate::math::*;
 
-pub trait Component {
+pub trait Component: Sized {
     fn default(world_settings: &WorldSettings) -> Self;
+
+    /// The maximum number of bits the component may take up when packed.
+    fn max_bit_size(world_settings: &WorldSettings) -> usize;
+
+    /// Writes the component to the stream. Returns false if it didn't fit.
+    fn pack(&self, stream: &mut Bitstream) -> bool;
+
+    /// Reads the component from the stream. Returns None if the stream ran out of data.
+    fn unpack(stream: &mut Bitstream, world_settings: &WorldSettings) -> Option<Self>;
+}
+
+/// The number of bits a packed Vec3 takes up.
+pub const VEC3_BIT_SIZE: usize = 3 * 32;
+
+/// The number of bits a packed Quaternion takes up.
+pub const QUATERNION_BIT_SIZE: usize = 4 * 32;
+
+/// Writes all numbers to the stream. Returns false if they didn't fit.
+pub fn pack_nums(nums: &[Num], stream: &mut Bitstream) -> bool {
+    nums.iter().all(|n| stream.write(*n))
+}
+
+/// Reads a Vec3 from the stream.
+pub fn unpack_vec3(stream: &mut Bitstream) -> Option<Vec3> {
+    Some(Vec3::new(stream.read()?, stream.read()?, stream.read()?))
+}
+
+/// Reads a Quaternion from the stream.
+pub fn unpack_quaternion(stream: &mut Bitstream) -> Option<Quaternion> {
+    Some(Quaternion::from_raw([
+        stream.read()?,
+        stream.read()?,
+        stream.read()?,
+        stream.read()?,
+    ]))
 }
 
 /// A generic component store.
@@ -51,4 +87,135 @@ where
             components_len,
         }
     }
+
+    /// Adds a component for the given entity, replacing any existing one. Returns false if the store is full or the entity is out of range.
+    pub fn add(&mut self, entity: EntityId, component: C) -> bool {
+        if entity >= self.entity_id_array.len() {
+            return false;
+        }
+
+        if let Some(index) = self.entity_id_array[entity] {
+            self.components[index] = (entity, component);
+            return true;
+        }
+
+        if self.components_len >= self.components.len() {
+            return false;
+        }
+
+        let index = self.components_len;
+        self.components[index] = (entity, component);
+        self.entity_id_array[entity] = Some(index);
+        self.components_len += 1;
+
+        true
+    }
+
+    /// Removes the component for the given entity. Swaps the last active component into the freed slot to keep the store shrunken.
+    pub fn remove(&mut self, entity: EntityId) {
+        if entity >= self.entity_id_array.len() {
+            return;
+        }
+
+        if let Some(index) = self.entity_id_array[entity].take() {
+            let last = self.components_len - 1;
+            if index != last {
+                self.components.swap(index, last);
+                let moved_entity = self.components[index].0;
+                self.entity_id_array[moved_entity] = Some(index);
+            }
+
+            self.components_len -= 1;
+        }
+    }
+
+    /// Returns a reference to the entity's component.
+    pub fn get(&self, entity: EntityId) -> Option<&C> {
+        match self.entity_id_array.get(entity) {
+            Some(Some(index)) => Some(&self.components[*index].1),
+            _ => None,
+        }
+    }
+
+    /// Returns a mutable reference to the entity's component.
+    pub fn get_mut(&mut self, entity: EntityId) -> Option<&mut C> {
+        match self.entity_id_array.get(entity) {
+            Some(Some(index)) => Some(&mut self.components[*index].1),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of active components.
+    pub fn len(&self) -> usize {
+        self.components_len
+    }
+
+    /// Returns whether there are no active components.
+    pub fn is_empty(&self) -> bool {
+        self.components_len == 0
+    }
+
+    /// Iterates over all active components. Not guaranteed to be in order of entity ids.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, &C)> {
+        self.components[..self.components_len]
+            .iter()
+            .map(|(entity, component)| (*entity, component))
+    }
+
+    /// Mutably iterates over all active components. Not guaranteed to be in order of entity ids.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (EntityId, &mut C)> {
+        self.components[..self.components_len]
+            .iter_mut()
+            .map(|(entity, component)| (*entity, component))
+    }
+
+    /// The maximum number of bits the store may take up when packed.
+    pub fn max_bit_size(&self, world_settings: &WorldSettings) -> usize {
+        let count_bits = 32;
+        let entity_bits = 32;
+
+        count_bits + self.components_len * (entity_bits + C::max_bit_size(world_settings))
+    }
+
+    /// Writes all active components to the stream in order of entity ids. Returns false if they didn't fit.
+    pub fn pack(&self, stream: &mut Bitstream) -> bool {
+        if !stream.write(self.components_len as u32) {
+            return false;
+        }
+
+        for index in self.entity_id_array.iter().filter_map(|index| *index) {
+            let (entity, component) = &self.components[index];
+            if !stream.write(*entity as u32) || !component.pack(stream) {
+                return false;
+            }
+        }
+
+        true
+    }
+
+    /// Reads components from the stream, adding them to the store. Returns false if the stream was invalid.
+    pub fn unpack(&mut self, stream: &mut Bitstream, world_settings: &WorldSettings) -> bool {
+        let count = match stream.read::<u32>() {
+            Some(count) => count,
+            None => return false,
+        };
+
+        for _ in 0..count {
+            let entity = match stream.read::<u32>() {
+                Some(entity) => entity as EntityId,
+                None => return false,
+            };
+
+            let component = match C::unpack(stream, world_settings) {
+                Some(component) => component,
+                None => return false,
+            };
+
+            if !self.add(entity, component) {
+                return false;
+            }
+        }
+
+        true
+    }
 }
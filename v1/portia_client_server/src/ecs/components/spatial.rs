@@ -1,11 +1,7 @@
 use crate::ecs::prelude::*;
 
 use crate::math::*;
-
-pub struct Aabb{
-    min: Vec3,
-    max: Vec3,
-}
+use super::{pack_nums, unpack_quaternion, unpack_vec3, QUATERNION_BIT_SIZE, VEC3_BIT_SIZE};
 
 /// A class for object transformations. Stores the current and previous transforms to allow for interpolation.
 pub struct Transformed{
@@ -14,6 +10,14 @@ pub struct Transformed{
 }
 
 impl Transformed{
+    /// Creates a new Transformed, with the previous transform set to the given one.
+    pub fn new(transform: Transform) -> Self{
+        Self{
+            transform,
+            prev_transform: transform,
+        }
+    }
+
     /// Mutable reference to the current transform.
     pub fn current_mut(&mut self) -> &mut Transform{
         &mut self.transform
@@ -42,6 +46,21 @@ impl Component for Transformed{
             prev_transform: Transform::default()
         }
     }
+
+    fn max_bit_size(_world_settings: &WorldSettings) -> usize {
+        2 * Transform::BIT_SIZE
+    }
+
+    fn pack(&self, stream: &mut Bitstream) -> bool {
+        self.transform.pack(stream) && self.prev_transform.pack(stream)
+    }
+
+    fn unpack(stream: &mut Bitstream, _world_settings: &WorldSettings) -> Option<Self> {
+        Some(Self {
+            transform: Transform::unpack(stream)?,
+            prev_transform: Transform::unpack(stream)?,
+        })
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -52,6 +71,9 @@ pub struct Transform {
 }
 
 impl Transform {
+    /// The number of bits a packed Transform takes up.
+    pub const BIT_SIZE: usize = 2 * VEC3_BIT_SIZE + QUATERNION_BIT_SIZE;
+
      fn default() -> Self {
         Self {
             scale: Vec3::default(),
@@ -59,13 +81,60 @@ impl Transform {
             position: Vec3::default(),
         }
     }
+
+    /// Creates a new transform at the given position.
+    pub fn new(position: Vec3) -> Self {
+        Self {
+            position,
+            ..Self::default()
+        }
+    }
+
+    /// Writes the transform to the stream. Returns false if it didn't fit.
+    pub fn pack(&self, stream: &mut Bitstream) -> bool {
+        pack_nums(&self.scale.to_raw(), stream)
+            && pack_nums(&self.rotation.to_raw(), stream)
+            && pack_nums(&self.position.to_raw(), stream)
+    }
+
+    /// Reads a transform from the stream.
+    pub fn unpack(stream: &mut Bitstream) -> Option<Self> {
+        Some(Self {
+            scale: unpack_vec3(stream)?,
+            rotation: unpack_quaternion(stream)?,
+            position: unpack_vec3(stream)?,
+        })
+    }
 }
 
+/// An object that may be collided with. The bounds are relative to the entity's position.
+pub struct Collidable {
+    pub bounds: Aabb,
+}
 
-pub struct Collidable {}
+impl Collidable{
+    /// Creates a new Collidable with the given local bounds.
+    pub fn new(bounds: Aabb) -> Self{
+        Self{ bounds }
+    }
+}
 
 impl Component for Collidable{
     fn default(world_settings: &WorldSettings) -> Self{
-        Self{}
+        Self{
+            bounds: Aabb::new(Vec3::default(), Vec3::default()),
+        }
+    }
+
+    fn max_bit_size(_world_settings: &WorldSettings) -> usize {
+        2 * VEC3_BIT_SIZE
+    }
+
+    fn pack(&self, stream: &mut Bitstream) -> bool {
+        pack_nums(&self.bounds.min.to_raw(), stream) && pack_nums(&self.bounds.max.to_raw(), stream)
+    }
+
+    fn unpack(stream: &mut Bitstream, _world_settings: &WorldSettings) -> Option<Self> {
+        Some(Self::new(Aabb::new(unpack_vec3(stream)?, unpack_vec3(stream)?)))
     }
-}
\ No newline at end of file
+}
@@ -1,6 +1,9 @@
 use crate::ClientId;
+use networking::Bitstream;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-mod components;
+pub mod components;
+pub mod systems;
 
 use components::{
     ComponentStore,
@@ -19,6 +22,7 @@ pub mod prelude {
     pub use super::components::{
         Component,
     };
+    pub use networking::{Bitstream, Packable};
 }
 
 pub struct WorldSettings {
@@ -26,22 +30,190 @@ pub struct WorldSettings {
     pub max_entities: usize,
 }
 
+/// Identifies a component store in the world. Used by systems to declare what they access.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum StoreId {
+    Ghostables,
+    Ghosts,
+    Controllable,
+    Transforms,
+    Collidables,
+}
+
+/// Generates the accessors for a component store. Stores are locked so that systems may access them from multiple threads.
+macro_rules! store_accessors {
+    ($store:ident, $store_mut:ident, $store_write:ident, $component:ty, $name:literal) => {
+        #[doc = concat!("Returns the ", $name, " store.")]
+        pub fn $store(&self) -> RwLockReadGuard<'_, ComponentStore<$component>> {
+            self.$store.read().unwrap()
+        }
+
+        #[doc = concat!("Returns a mutable reference to the ", $name, " store.")]
+        pub fn $store_mut(&mut self) -> &mut ComponentStore<$component> {
+            self.$store.get_mut().unwrap()
+        }
+
+        #[doc = concat!("Locks the ", $name, " store for writing. Blocks until any readers are finished.")]
+        pub fn $store_write(&self) -> RwLockWriteGuard<'_, ComponentStore<$component>> {
+            self.$store.write().unwrap()
+        }
+    };
+}
+
+/// A world made up of component stores. May be snapshotted to bytes and restored.
 pub struct World {
-    ghostables: ComponentStore<Ghostable>,
-    ghosts: ComponentStore<Ghost>,
-    controllable: ComponentStore<Controllable>,
-    transforms: ComponentStore<Transformed>,
-    collidables: ComponentStore<Collidable>
+    ghostables: RwLock<ComponentStore<Ghostable>>,
+    ghosts: RwLock<ComponentStore<Ghost>>,
+    controllable: RwLock<ComponentStore<Controllable>>,
+    transforms: RwLock<ComponentStore<Transformed>>,
+    collidables: RwLock<ComponentStore<Collidable>>
 }
 
 impl World {
     pub fn new(world_settings: &WorldSettings) -> Self {
         Self {
-            ghostables: ComponentStore::new(world_settings.max_entities, world_settings),
-            ghosts: ComponentStore::new(world_settings.max_entities, world_settings),
-            controllable: ComponentStore::new(world_settings.max_entities, world_settings),
-            transforms: ComponentStore::new(world_settings.max_entities, world_settings),
-            collidables: ComponentStore::new(world_settings.max_entities, world_settings)
+            ghostables: RwLock::new(ComponentStore::new(world_settings.max_entities, world_settings)),
+            ghosts: RwLock::new(ComponentStore::new(world_settings.max_entities, world_settings)),
+            controllable: RwLock::new(ComponentStore::new(world_settings.max_entities, world_settings)),
+            transforms: RwLock::new(ComponentStore::new(world_settings.max_entities, world_settings)),
+            collidables: RwLock::new(ComponentStore::new(world_settings.max_entities, world_settings))
         }
     }
+
+    /// Creates a binary snapshot of every component store.
+    pub fn snapshot(&self, world_settings: &WorldSettings) -> Vec<u8> {
+        let ghostables = self.ghostables();
+        let ghosts = self.ghosts();
+        let controllable = self.controllable();
+        let transforms = self.transforms();
+        let collidables = self.collidables();
+
+        let bits = ghostables.max_bit_size(world_settings)
+            + ghosts.max_bit_size(world_settings)
+            + controllable.max_bit_size(world_settings)
+            + transforms.max_bit_size(world_settings)
+            + collidables.max_bit_size(world_settings);
+
+        let mut stream = Bitstream::new(bits.div_ceil(8));
+
+        let packed = ghostables.pack(&mut stream)
+            && ghosts.pack(&mut stream)
+            && controllable.pack(&mut stream)
+            && transforms.pack(&mut stream)
+            && collidables.pack(&mut stream);
+
+        // The stream is sized to the max bits, so this should never happen.
+        assert!(packed, "World snapshot exceeded its calculated size!");
+
+        stream.buffer()
+    }
+
+    /// Restores a world from a snapshot. Returns None if the snapshot is invalid for the given settings.
+    pub fn from_snapshot(snapshot: Vec<u8>, world_settings: &WorldSettings) -> Option<Self> {
+        let mut world = Self::new(world_settings);
+        let mut stream = Bitstream::from_bytes(snapshot);
+
+        let unpacked = world.ghostables_mut().unpack(&mut stream, world_settings)
+            && world.ghosts_mut().unpack(&mut stream, world_settings)
+            && world.controllable_mut().unpack(&mut stream, world_settings)
+            && world.transforms_mut().unpack(&mut stream, world_settings)
+            && world.collidables_mut().unpack(&mut stream, world_settings);
+
+        if unpacked {
+            Some(world)
+        } else {
+            None
+        }
+    }
+
+    store_accessors!(ghostables, ghostables_mut, ghostables_write, Ghostable, "ghostable");
+    store_accessors!(ghosts, ghosts_mut, ghosts_write, Ghost, "ghost");
+    store_accessors!(controllable, controllable_mut, controllable_write, Controllable, "controllable");
+    store_accessors!(transforms, transforms_mut, transforms_write, Transformed, "transform");
+    store_accessors!(collidables, collidables_mut, collidables_write, Collidable, "collidable");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::spatial::Transform;
+    use crate::math::*;
+
+    const SETTINGS: WorldSettings = WorldSettings {
+        max_clients: 2,
+        max_entities: 8,
+    };
+
+    fn populated_world() -> World {
+        let mut world = World::new(&SETTINGS);
+
+        let mut ghostable = Ghostable::new(7, 2.5, &SETTINGS);
+        ghostable.add_client(1);
+        world.ghostables_mut().add(5, ghostable);
+
+        world.transforms_mut().add(5, Transformed::new(Transform::new(Vec3::new(1., 2., 3.))));
+        world.transforms_mut().add(2, Transformed::new(Transform::new(Vec3::new(-4., 0.5, 9.))));
+        world.collidables_mut().add(
+            2,
+            Collidable::new(Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 2., 1.))),
+        );
+
+        world
+    }
+
+    #[test]
+    fn world_snapshot_restores_all_stores() {
+        let world = populated_world();
+        let snapshot = world.snapshot(&SETTINGS);
+
+        let restored = World::from_snapshot(snapshot.clone(), &SETTINGS).unwrap();
+
+        let ghostables = restored.ghostables();
+        let ghostable = ghostables.get(5).unwrap();
+        assert_eq!(2.5, ghostable.priority);
+        assert_eq!(&vec![1], ghostable.clients());
+
+        assert_eq!(2, restored.transforms().len());
+        assert_eq!(
+            Vec3::new(1., 2., 3.),
+            restored.transforms().get(5).unwrap().current().position
+        );
+        assert_eq!(
+            Vec3::new(-4., 0.5, 9.),
+            restored.transforms().get(2).unwrap().prev().position
+        );
+
+        assert_eq!(
+            Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 2., 1.)),
+            restored.collidables().get(2).unwrap().bounds
+        );
+
+        assert_eq!(snapshot, restored.snapshot(&SETTINGS));
+    }
+
+    #[test]
+    fn world_snapshot_empty_world_round_trips() {
+        let world = World::new(&SETTINGS);
+        let snapshot = world.snapshot(&SETTINGS);
+
+        let restored = World::from_snapshot(snapshot.clone(), &SETTINGS).unwrap();
+        assert_eq!(true, restored.transforms().is_empty());
+        assert_eq!(snapshot, restored.snapshot(&SETTINGS));
+    }
+
+    #[test]
+    fn world_from_snapshot_invalid_returns_none() {
+        let snapshot = populated_world().snapshot(&SETTINGS);
+
+        // Truncated
+        let truncated = snapshot[..snapshot.len() / 2].to_vec();
+        assert_eq!(true, World::from_snapshot(truncated, &SETTINGS).is_none());
+
+        // Entities outside of the world's range
+        let small_settings = WorldSettings {
+            max_clients: 2,
+            max_entities: 3,
+        };
+        assert_eq!(true, World::from_snapshot(snapshot, &small_settings).is_none());
+    }
 }
@@ -0,0 +1,313 @@
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use job_scheduler::JobScheduler;
+
+use super::{StoreId, World};
+
+/// Logic that operates on the world. Each system declares the stores it accesses so the scheduler can run non-conflicting systems in parallel.
+/// A system should only lock the stores it declares.
+pub trait System: Send {
+    /// The stores the system reads from.
+    fn reads(&self) -> Vec<StoreId>;
+
+    /// The stores the system writes to.
+    fn writes(&self) -> Vec<StoreId>;
+
+    /// Executes the system.
+    fn run(&mut self, world: &World);
+}
+
+/// A registered system and its declared access.
+struct ScheduledSystem {
+    system: Arc<Mutex<Box<dyn System>>>,
+    reads: Vec<StoreId>,
+    writes: Vec<StoreId>,
+}
+
+impl ScheduledSystem {
+    /// Whether the two systems may not run at the same time.
+    fn conflicts_with(&self, other: &Self) -> bool {
+        let writes_to = |writes: &Vec<StoreId>, system: &Self| {
+            writes
+                .iter()
+                .any(|store| system.reads.contains(store) || system.writes.contains(store))
+        };
+
+        writes_to(&self.writes, other) || writes_to(&other.writes, self)
+    }
+}
+
+/// Orders systems into stages. Systems in a stage don't conflict and may run in parallel.
+/// Conflicting systems always run in the order they were added.
+pub struct SystemScheduler {
+    systems: Vec<ScheduledSystem>,
+    stages: Vec<Vec<usize>>,
+    deterministic: bool,
+}
+
+impl SystemScheduler {
+    /// Creates a new scheduler. If deterministic, systems run one at a time in the order they were added.
+    pub fn new(deterministic: bool) -> Self {
+        Self {
+            systems: vec![],
+            stages: vec![],
+            deterministic,
+        }
+    }
+
+    /// Whether systems run one at a time in the order they were added.
+    pub fn deterministic(&self) -> bool {
+        self.deterministic
+    }
+
+    /// Sets whether systems run one at a time in the order they were added.
+    pub fn set_deterministic(&mut self, deterministic: bool) {
+        self.deterministic = deterministic;
+    }
+
+    /// Adds a system. It will run after any previously added systems it conflicts with.
+    pub fn add_system(&mut self, system: impl System + 'static) {
+        let scheduled = ScheduledSystem {
+            reads: system.reads(),
+            writes: system.writes(),
+            system: Arc::new(Mutex::new(Box::new(system))),
+        };
+
+        // Place the system in the stage after the last one it conflicts with.
+        let mut stage = 0;
+        for (stage_index, systems) in self.stages.iter().enumerate() {
+            if systems
+                .iter()
+                .any(|index| self.systems[*index].conflicts_with(&scheduled))
+            {
+                stage = stage_index + 1;
+            }
+        }
+
+        if stage == self.stages.len() {
+            self.stages.push(vec![]);
+        }
+
+        self.stages[stage].push(self.systems.len());
+        self.systems.push(scheduled);
+    }
+
+    /// The number of stages systems are split into.
+    pub fn num_stages(&self) -> usize {
+        self.stages.len()
+    }
+
+    /// Runs all systems. Stages run in order, with the systems in each stage spread across the job scheduler.
+    /// Runs sequentially if the scheduler is deterministic.
+    pub fn run(&self, world: &Arc<World>, job_scheduler: &mut JobScheduler) {
+        if self.deterministic {
+            self.run_sequential(world);
+            return;
+        }
+
+        for stage in &self.stages {
+            if stage.len() == 1 {
+                Self::run_system(&self.systems[stage[0]], world);
+                continue;
+            }
+
+            // The job scheduler doesn't track when a job finishes, so each job reports back here.
+            let (sender, receiver) = mpsc::channel();
+
+            for index in stage {
+                let system = self.systems[*index].system.clone();
+                let world = world.clone();
+                let sender = sender.clone();
+
+                job_scheduler.queue(move || {
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                        system.lock().unwrap().run(&world);
+                    }));
+
+                    // Release the world before reporting, so the caller has sole ownership once everything is done.
+                    drop(world);
+                    sender.send(result.is_ok()).unwrap();
+                });
+            }
+
+            let mut remaining = stage.len();
+            while remaining > 0 {
+                job_scheduler.process();
+
+                match receiver.recv_timeout(Duration::from_micros(100)) {
+                    Ok(succeeded) => {
+                        assert!(succeeded, "A system panicked while running!");
+                        remaining -= 1;
+                    }
+                    Err(mpsc::RecvTimeoutError::Timeout) => {}
+                    Err(mpsc::RecvTimeoutError::Disconnected) => {
+                        panic!("Lost connection to a running system!");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Runs all systems on the current thread in the order they were added.
+    pub fn run_sequential(&self, world: &World) {
+        for system in &self.systems {
+            Self::run_system(system, world);
+        }
+    }
+
+    fn run_system(system: &ScheduledSystem, world: &World) {
+        system.system.lock().unwrap().run(world);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::{
+        components::spatial::{Collidable, Transform, Transformed},
+        WorldSettings,
+    };
+    use crate::math::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    const SETTINGS: WorldSettings = WorldSettings {
+        max_clients: 2,
+        max_entities: 4,
+    };
+
+    struct TestSystem {
+        reads: Vec<StoreId>,
+        writes: Vec<StoreId>,
+        run: Box<dyn FnMut(&World) + Send>,
+    }
+
+    impl TestSystem {
+        fn new(reads: Vec<StoreId>, writes: Vec<StoreId>, run: impl FnMut(&World) + Send + 'static) -> Self {
+            Self {
+                reads,
+                writes,
+                run: Box::new(run),
+            }
+        }
+    }
+
+    impl System for TestSystem {
+        fn reads(&self) -> Vec<StoreId> {
+            self.reads.clone()
+        }
+
+        fn writes(&self) -> Vec<StoreId> {
+            self.writes.clone()
+        }
+
+        fn run(&mut self, world: &World) {
+            (self.run)(world);
+        }
+    }
+
+    fn world() -> Arc<World> {
+        let mut world = World::new(&SETTINGS);
+        world
+            .transforms_mut()
+            .add(0, Transformed::new(Transform::new(Vec3::new(0., 0., 0.))));
+        world.collidables_mut().add(
+            0,
+            Collidable::new(Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.))),
+        );
+
+        Arc::new(world)
+    }
+
+    fn conflicting_systems(scheduler: &mut SystemScheduler) {
+        scheduler.add_system(TestSystem::new(vec![], vec![StoreId::Transforms], |world| {
+            world.transforms_write().get_mut(0).unwrap().current_mut().position.x += 1.;
+        }));
+        scheduler.add_system(TestSystem::new(vec![], vec![StoreId::Collidables], |world| {
+            world.collidables_write().get_mut(0).unwrap().bounds.max.y = 5.;
+        }));
+        scheduler.add_system(TestSystem::new(vec![], vec![StoreId::Transforms], |world| {
+            world.transforms_write().get_mut(0).unwrap().current_mut().position.x *= 2.;
+        }));
+        scheduler.add_system(
+            TestSystem::new(vec![StoreId::Collidables], vec![StoreId::Transforms], |world| {
+                let height = world.collidables().get(0).unwrap().bounds.max.y;
+                world.transforms_write().get_mut(0).unwrap().current_mut().position.y = height;
+            }),
+        );
+    }
+
+    #[test]
+    fn system_scheduler_stages_split_on_conflicts() {
+        let mut scheduler = SystemScheduler::new(false);
+
+        scheduler.add_system(TestSystem::new(vec![], vec![StoreId::Transforms], |_| {}));
+        scheduler.add_system(TestSystem::new(vec![StoreId::Collidables], vec![], |_| {}));
+        scheduler.add_system(TestSystem::new(vec![StoreId::Collidables], vec![], |_| {}));
+        assert_eq!(1, scheduler.num_stages());
+
+        scheduler.add_system(TestSystem::new(vec![StoreId::Transforms], vec![], |_| {}));
+        assert_eq!(2, scheduler.num_stages());
+
+        // Only conflicts with the first stage, so it runs alongside the reader.
+        scheduler.add_system(TestSystem::new(vec![], vec![StoreId::Collidables], |_| {}));
+        assert_eq!(vec![vec![0, 1, 2], vec![3, 4]], scheduler.stages);
+    }
+
+    #[test]
+    fn system_scheduler_parallel_matches_deterministic() {
+        let mut job_scheduler = JobScheduler::new(4);
+
+        let parallel_world = world();
+        let mut parallel = SystemScheduler::new(false);
+        conflicting_systems(&mut parallel);
+        parallel.run(&parallel_world, &mut job_scheduler);
+
+        let deterministic_world = world();
+        let mut deterministic = SystemScheduler::new(true);
+        conflicting_systems(&mut deterministic);
+        deterministic.run(&deterministic_world, &mut job_scheduler);
+
+        // Conflicting writes happen in the order the systems were added.
+        let expected = Vec3::new(2., 5., 0.);
+        assert_eq!(expected, parallel_world.transforms().get(0).unwrap().current().position);
+        assert_eq!(expected, deterministic_world.transforms().get(0).unwrap().current().position);
+
+        // All jobs have released the world.
+        assert_eq!(1, Arc::strong_count(&parallel_world));
+    }
+
+    #[test]
+    fn system_scheduler_runs_non_conflicting_systems_in_parallel() {
+        let mut job_scheduler = JobScheduler::new(2);
+        let mut scheduler = SystemScheduler::new(false);
+
+        let started = Arc::new(AtomicUsize::new(0));
+        let overlapped = Arc::new(AtomicUsize::new(0));
+
+        for store in [StoreId::Transforms, StoreId::Collidables].iter() {
+            let started = started.clone();
+            let overlapped = overlapped.clone();
+
+            scheduler.add_system(TestSystem::new(vec![], vec![*store], move |_| {
+                started.fetch_add(1, Ordering::SeqCst);
+
+                // Wait for the other system to start. Bail out rather than hang if they're run one at a time.
+                let timeout = Instant::now() + Duration::from_secs(2);
+                while started.load(Ordering::SeqCst) < 2 && Instant::now() < timeout {
+                    std::thread::yield_now();
+                }
+
+                if started.load(Ordering::SeqCst) == 2 {
+                    overlapped.fetch_add(1, Ordering::SeqCst);
+                }
+            }));
+        }
+
+        scheduler.run(&world(), &mut job_scheduler);
+
+        assert_eq!(2, overlapped.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+
+use crate::ecs::{EntityId, World, WorldSettings};
+use crate::math::*;
+use crate::ClientId;
+
+/// A cell coordinate in the spatial grid.
+pub type Cell = (i32, i32, i32);
+
+/// A uniform grid over entity positions. Entities are only moved between cells when they cross a cell boundary.
+pub struct SpatialGrid {
+    cell_size: Num,
+    cells: HashMap<Cell, Vec<EntityId>>,
+    entity_cells: Vec<Option<Cell>>,
+}
+
+impl SpatialGrid {
+    /// Creates a new grid with the given cell size.
+    pub fn new(cell_size: Num, max_entities: usize) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            entity_cells: vec![None; max_entities],
+        }
+    }
+
+    /// Returns the cell a position falls into.
+    pub fn cell(&self, position: Vec3) -> Cell {
+        (
+            (position.x / self.cell_size).floor() as i32,
+            (position.y / self.cell_size).floor() as i32,
+            (position.z / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Inserts or moves the entity to the cell for the given position.
+    pub fn update(&mut self, entity: EntityId, position: Vec3) {
+        if entity >= self.entity_cells.len() {
+            return;
+        }
+
+        let cell = self.cell(position);
+        if self.entity_cells[entity] == Some(cell) {
+            return;
+        }
+
+        self.remove(entity);
+        self.cells.entry(cell).or_default().push(entity);
+        self.entity_cells[entity] = Some(cell);
+    }
+
+    /// Removes the entity from the grid.
+    pub fn remove(&mut self, entity: EntityId) {
+        if entity >= self.entity_cells.len() {
+            return;
+        }
+
+        if let Some(cell) = self.entity_cells[entity].take() {
+            let remove_cell = match self.cells.get_mut(&cell) {
+                Some(entities) => {
+                    entities.retain(|e| *e != entity);
+                    entities.is_empty()
+                }
+                None => false,
+            };
+
+            if remove_cell {
+                self.cells.remove(&cell);
+            }
+        }
+    }
+
+    /// Writes all entities in cells overlapping the sphere to the results, sorted by entity id. Entities may be further than the radius.
+    pub fn query(&self, center: Vec3, radius: Num, results: &mut Vec<EntityId>) {
+        results.clear();
+
+        let extent = Vec3::new(radius, radius, radius);
+        let min = self.cell(center - extent);
+        let max = self.cell(center + extent);
+
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    if let Some(entities) = self.cells.get(&(x, y, z)) {
+                        results.extend_from_slice(entities);
+                    }
+                }
+            }
+        }
+
+        // Cells are stored in a hash map, so sort to keep the results deterministic.
+        results.sort_unstable();
+    }
+}
+
+/// Rules for determining whether an entity is in scope for a client and how often it is updated.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ScopeRules {
+    /// Entities further than this from the client are out of scope.
+    pub max_distance: Num,
+    /// Entities within this distance accumulate their full priority each tick. Past it, priority falls off with distance.
+    pub full_priority_distance: Num,
+    /// The maximum number of entity updates to send a client each tick.
+    pub max_updates_per_tick: usize,
+}
+
+/// The scoping state for a single client.
+struct ClientScope {
+    accumulators: Vec<Num>,
+    visible: Vec<EntityId>,
+}
+
+impl ClientScope {
+    fn new(max_entities: usize) -> Self {
+        Self {
+            accumulators: vec![0.; max_entities],
+            visible: vec![],
+        }
+    }
+}
+
+/// Determines which entities each client can see and which of those should be updated this tick.
+pub struct InterestManager {
+    rules: ScopeRules,
+    max_entities: usize,
+    grid: SpatialGrid,
+    tracked: Vec<bool>,
+    seen: Vec<bool>,
+    clients: HashMap<ClientId, ClientScope>,
+    candidates: Vec<EntityId>,
+}
+
+impl InterestManager {
+    /// Creates a new interest manager. The cell size should be on the order of the scoping distance.
+    pub fn new(world_settings: &WorldSettings, cell_size: Num, rules: ScopeRules) -> Self {
+        let max_entities = world_settings.max_entities;
+
+        Self {
+            rules,
+            max_entities,
+            grid: SpatialGrid::new(cell_size, max_entities),
+            tracked: vec![false; max_entities],
+            seen: vec![false; max_entities],
+            clients: HashMap::with_capacity(world_settings.max_clients),
+            candidates: Vec::with_capacity(max_entities),
+        }
+    }
+
+    /// Returns the rules used for scoping.
+    pub fn rules(&self) -> ScopeRules {
+        self.rules
+    }
+
+    /// Syncs the grid with all ghostable entities that have a transform. Should be called once per tick before scoping.
+    pub fn update_entities(&mut self, world: &World) {
+        for seen in self.seen.iter_mut() {
+            *seen = false;
+        }
+
+        let transforms = world.transforms();
+        for (entity, _) in world.ghostables().iter() {
+            if let Some(transformed) = transforms.get(entity) {
+                self.grid.update(entity, transformed.current().position);
+                self.seen[entity] = true;
+                self.tracked[entity] = true;
+            }
+        }
+
+        // Remove any entities that are no longer ghostable
+        for entity in 0..self.tracked.len() {
+            if self.tracked[entity] && !self.seen[entity] {
+                self.grid.remove(entity);
+                self.tracked[entity] = false;
+            }
+        }
+    }
+
+    /// Scopes entities for the client viewing from the given position. Updates each `Ghostable`'s clients and returns the entities to update this tick, most important first.
+    pub fn scope(&mut self, world: &mut World, client: ClientId, viewer: Vec3) -> Vec<EntityId> {
+        self.grid
+            .query(viewer, self.rules.max_distance, &mut self.candidates);
+
+        let max_distance_sqrd = self.rules.max_distance * self.rules.max_distance;
+        let max_entities = self.max_entities;
+        let scope = self
+            .clients
+            .entry(client)
+            .or_insert_with(|| ClientScope::new(max_entities));
+
+        // Find visible entities and accumulate their priority
+        let mut visible = Vec::with_capacity(self.candidates.len());
+        {
+            let transforms = world.transforms();
+            let ghostables = world.ghostables();
+
+            for entity in self.candidates.iter().copied() {
+                let (transformed, ghostable) =
+                    match (transforms.get(entity), ghostables.get(entity)) {
+                        (Some(t), Some(g)) => (t, g),
+                        _ => continue,
+                    };
+
+                let distance_sqrd = (transformed.current().position - viewer).len_squared();
+                if distance_sqrd > max_distance_sqrd {
+                    continue;
+                }
+
+                let distance = distance_sqrd.sqrt();
+                let falloff = {
+                    if distance <= self.rules.full_priority_distance {
+                        1.
+                    } else {
+                        self.rules.full_priority_distance / distance
+                    }
+                };
+
+                scope.accumulators[entity] += ghostable.priority * falloff;
+                visible.push(entity);
+            }
+        }
+
+        // Update ghostables that have left or entered scope. Both lists are sorted.
+        {
+            let ghostables = world.ghostables_mut();
+            for entity in scope.visible.iter() {
+                if visible.binary_search(entity).is_err() {
+                    scope.accumulators[*entity] = 0.;
+                    if let Some(ghostable) = ghostables.get_mut(*entity) {
+                        ghostable.remove_client(client);
+                    }
+                }
+            }
+
+            for entity in visible.iter() {
+                if let Some(ghostable) = ghostables.get_mut(*entity) {
+                    ghostable.add_client(client);
+                }
+            }
+        }
+
+        // Pick the highest priority entities, breaking ties by id for determinism.
+        let mut updates = visible.clone();
+        {
+            let accumulators = &scope.accumulators;
+            updates.sort_by(|a, b| {
+                accumulators[*b]
+                    .partial_cmp(&accumulators[*a])
+                    .unwrap_or(std::cmp::Ordering::Equal)
+                    .then(a.cmp(b))
+            });
+        }
+        updates.truncate(self.rules.max_updates_per_tick);
+
+        for entity in updates.iter() {
+            scope.accumulators[*entity] = 0.;
+        }
+
+        scope.visible = visible;
+
+        updates
+    }
+
+    /// Removes the client from all scoping, for use when they disconnect.
+    pub fn remove_client(&mut self, world: &mut World, client: ClientId) {
+        let scope = match self.clients.remove(&client) {
+            Some(scope) => scope,
+            None => return,
+        };
+
+        let ghostables = world.ghostables_mut();
+        for entity in scope.visible {
+            if let Some(ghostable) = ghostables.get_mut(entity) {
+                ghostable.remove_client(client);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::components::{
+        ghosting::Ghostable,
+        spatial::{Transform, Transformed},
+    };
+
+    const SETTINGS: WorldSettings = WorldSettings {
+        max_clients: 2,
+        max_entities: 8,
+    };
+
+    const RULES: ScopeRules = ScopeRules {
+        max_distance: 10.,
+        full_priority_distance: 2.,
+        max_updates_per_tick: 8,
+    };
+
+    fn add_entity(world: &mut World, entity: EntityId, position: Vec3, priority: Num) {
+        world
+            .transforms_mut()
+            .add(entity, Transformed::new(Transform::new(position)));
+        world
+            .ghostables_mut()
+            .add(entity, Ghostable::new(entity as u32, priority, &SETTINGS));
+    }
+
+    #[test]
+    fn spatial_grid_update_moves_between_cells() {
+        let mut grid = SpatialGrid::new(4., 4);
+        let mut results = vec![];
+
+        grid.update(1, Vec3::new(1., 1., 1.));
+        grid.query(Vec3::new(1., 1., 1.), 1., &mut results);
+        assert_eq!(vec![1], results);
+
+        grid.update(1, Vec3::new(100., 1., 1.));
+        grid.query(Vec3::new(1., 1., 1.), 1., &mut results);
+        assert_eq!(Vec::<EntityId>::new(), results);
+
+        grid.query(Vec3::new(100., 1., 1.), 1., &mut results);
+        assert_eq!(vec![1], results);
+
+        grid.remove(1);
+        grid.query(Vec3::new(100., 1., 1.), 1., &mut results);
+        assert_eq!(Vec::<EntityId>::new(), results);
+        assert_eq!(true, grid.cells.is_empty());
+    }
+
+    #[test]
+    fn interest_manager_scope_excludes_distant_entities() {
+        let mut world = World::new(&SETTINGS);
+        add_entity(&mut world, 0, Vec3::new(1., 0., 0.), 1.);
+        add_entity(&mut world, 1, Vec3::new(50., 0., 0.), 1.);
+
+        let mut interest = InterestManager::new(&SETTINGS, 5., RULES);
+        interest.update_entities(&world);
+
+        let client = 1;
+        let updates = interest.scope(&mut world, client, Vec3::default());
+        assert_eq!(vec![0], updates);
+        assert_eq!(true, world.ghostables().get(0).unwrap().is_scoped_for(client));
+        assert_eq!(false, world.ghostables().get(1).unwrap().is_scoped_for(client));
+
+        // Move the entity out of range and ensure it's unscoped
+        world.transforms_mut().get_mut(0).unwrap().current_mut().position = Vec3::new(-50., 0., 0.);
+        interest.update_entities(&world);
+
+        let updates = interest.scope(&mut world, client, Vec3::default());
+        assert_eq!(Vec::<EntityId>::new(), updates);
+        assert_eq!(false, world.ghostables().get(0).unwrap().is_scoped_for(client));
+    }
+
+    #[test]
+    fn interest_manager_distant_entities_update_less_often() {
+        let mut world = World::new(&SETTINGS);
+        add_entity(&mut world, 0, Vec3::new(8., 0., 0.), 1.);
+        add_entity(&mut world, 1, Vec3::new(1., 0., 0.), 1.);
+
+        let rules = ScopeRules {
+            max_updates_per_tick: 1,
+            ..RULES
+        };
+        let mut interest = InterestManager::new(&SETTINGS, 5., rules);
+        interest.update_entities(&world);
+
+        let mut near_updates = 0;
+        let mut far_updates = 0;
+        for _ in 0..10 {
+            for entity in interest.scope(&mut world, 0, Vec3::default()) {
+                match entity {
+                    0 => far_updates += 1,
+                    _ => near_updates += 1,
+                }
+            }
+        }
+
+        assert_eq!(true, near_updates > far_updates);
+        assert_eq!(true, far_updates > 0);
+    }
+
+    #[test]
+    fn interest_manager_scopes_any_client_id() {
+        let mut world = World::new(&SETTINGS);
+        add_entity(&mut world, 0, Vec3::new(1., 0., 0.), 1.);
+
+        let mut interest = InterestManager::new(&SETTINGS, 5., RULES);
+        interest.update_entities(&world);
+
+        // Ids aren't limited to the max number of clients.
+        let client = SETTINGS.max_clients as ClientId + 10;
+        assert_eq!(vec![0], interest.scope(&mut world, client, Vec3::default()));
+        assert_eq!(true, world.ghostables().get(0).unwrap().is_scoped_for(client));
+
+        interest.remove_client(&mut world, client);
+        assert_eq!(false, world.ghostables().get(0).unwrap().is_scoped_for(client));
+    }
+
+    #[test]
+    fn interest_manager_update_entities_removes_non_ghostables() {
+        let mut world = World::new(&SETTINGS);
+        add_entity(&mut world, 3, Vec3::new(1., 0., 0.), 1.);
+
+        let mut interest = InterestManager::new(&SETTINGS, 5., RULES);
+        interest.update_entities(&world);
+        assert_eq!(vec![3], interest.scope(&mut world, 0, Vec3::default()));
+
+        world.ghostables_mut().remove(3);
+        interest.update_entities(&world);
+        assert_eq!(
+            Vec::<EntityId>::new(),
+            interest.scope(&mut world, 0, Vec3::default())
+        );
+    }
+}
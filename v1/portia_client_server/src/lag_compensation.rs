@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+
+use crate::ecs::{EntityId, World};
+use crate::math::*;
+
+/// A simulation tick on the server.
+pub type Tick = u64;
+
+/// The world space bounds of a collidable entity on a given tick.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct HistoricalEntity {
+    entity: EntityId,
+    bounds: Aabb,
+}
+
+/// A snapshot of all collidable entities on a given tick.
+struct HistoricalFrame {
+    tick: Tick,
+    entities: Vec<HistoricalEntity>,
+}
+
+/// Keeps a bounded history of `Transformed` and `Collidable` state so that hits can be evaluated against the world as a client saw it.
+pub struct LagCompensation {
+    max_compensation_ticks: Tick,
+    frames: VecDeque<HistoricalFrame>,
+}
+
+impl LagCompensation {
+    /// Creates a new history that will rewind at most `max_compensation_ticks` into the past.
+    pub fn new(max_compensation_ticks: Tick) -> Self {
+        let capacity = max_compensation_ticks as usize + 1;
+
+        Self {
+            max_compensation_ticks,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// The maximum number of ticks that may be rewound.
+    pub fn max_compensation_ticks(&self) -> Tick {
+        self.max_compensation_ticks
+    }
+
+    /// Records the state of all entities with both a transform and a collidable for the given tick. Drops any history outside of the compensation window.
+    pub fn record(&mut self, tick: Tick, world: &World) {
+        // Reuse the oldest frame's allocation if the window is full.
+        let mut frame = {
+            if self.frames.len() > self.max_compensation_ticks as usize {
+                let mut frame = self.frames.pop_front().unwrap();
+                frame.entities.clear();
+                frame.tick = tick;
+                frame
+            } else {
+                HistoricalFrame {
+                    tick,
+                    entities: Vec::with_capacity(world.collidables().len()),
+                }
+            }
+        };
+
+        let transforms = world.transforms();
+        for (entity, collidable) in world.collidables().iter() {
+            if let Some(transformed) = transforms.get(entity) {
+                frame.entities.push(HistoricalEntity {
+                    entity,
+                    bounds: collidable.bounds.offset(transformed.current().position),
+                });
+            }
+        }
+
+        self.frames.push_back(frame);
+    }
+
+    /// Calculates the tick a client was viewing, based on their round trip time and interpolation delay. Clamped to the compensation window.
+    pub fn client_view_tick(
+        &self,
+        current_tick: Tick,
+        tick_rate: u32,
+        rtt_ms: u32,
+        interpolation_delay_ms: u32,
+    ) -> Tick {
+        // Only half the round trip has elapsed by the time the client's command arrives.
+        let latency_ms = rtt_ms / 2 + interpolation_delay_ms;
+        let latency_ticks = ticks_from_ms(latency_ms, tick_rate).min(self.max_compensation_ticks);
+
+        current_tick.saturating_sub(latency_ticks)
+    }
+
+    /// Casts the ray against the world as it was on the given tick, returning the entities hit ordered from nearest to furthest.
+    /// Ticks outside of the recorded history are clamped to the oldest or newest recorded tick.
+    pub fn rewind_query(&self, tick: Tick, ray: Ray) -> Vec<EntityId> {
+        let frame = match self.frame(tick) {
+            Some(frame) => frame,
+            None => return vec![],
+        };
+
+        let mut hits: Vec<(Num, EntityId)> = frame
+            .entities
+            .iter()
+            .filter_map(|e| e.bounds.raycast(&ray).map(|hit| (hit.distance, e.entity)))
+            .collect();
+
+        // Ties are broken by entity id so results are stable across runs.
+        hits.sort_by(|a, b| {
+            a.0.partial_cmp(&b.0)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.1.cmp(&b.1))
+        });
+
+        hits.into_iter().map(|(_, entity)| entity).collect()
+    }
+
+    /// Returns the frame for the given tick, clamped to the recorded history.
+    fn frame(&self, tick: Tick) -> Option<&HistoricalFrame> {
+        let oldest = self.frames.front()?;
+        let newest = self.frames.back()?;
+
+        if tick <= oldest.tick {
+            return Some(oldest);
+        }
+
+        if tick >= newest.tick {
+            return Some(newest);
+        }
+
+        let index = (tick - oldest.tick) as usize;
+        match self.frames.get(index) {
+            Some(frame) if frame.tick == tick => Some(frame),
+            // Ticks were skipped, so fall back to a search.
+            _ => self.frames.iter().rev().find(|frame| frame.tick <= tick),
+        }
+    }
+}
+
+/// Converts milliseconds to ticks, rounding up.
+pub fn ticks_from_ms(ms: u32, tick_rate: u32) -> Tick {
+    let ms_per_second = 1000;
+    (ms as Tick * tick_rate as Tick).div_ceil(ms_per_second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ecs::{
+        components::spatial::{Collidable, Transform, Transformed},
+        WorldSettings,
+    };
+
+    fn world() -> World {
+        World::new(&WorldSettings {
+            max_clients: 2,
+            max_entities: 4,
+        })
+    }
+
+    fn unit_box() -> Collidable {
+        Collidable::new(Aabb::new(
+            Vec3::new(-0.5, -0.5, -0.5),
+            Vec3::new(0.5, 0.5, 0.5),
+        ))
+    }
+
+    fn move_entity(world: &mut World, entity: EntityId, position: Vec3) {
+        world
+            .transforms_mut()
+            .get_mut(entity)
+            .unwrap()
+            .current_mut()
+            .position = position;
+    }
+
+    #[test]
+    fn lag_compensation_rewind_query_uses_historical_positions() {
+        let mut world = world();
+        let entity = 1;
+        world
+            .transforms_mut()
+            .add(entity, Transformed::new(Transform::new(Vec3::new(5., 0., 0.))));
+        world.collidables_mut().add(entity, unit_box());
+
+        let mut history = LagCompensation::new(10);
+        history.record(0, &world);
+
+        move_entity(&mut world, entity, Vec3::new(5., 10., 0.));
+        history.record(1, &world);
+
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 100.);
+
+        assert_eq!(vec![entity], history.rewind_query(0, ray));
+        assert_eq!(Vec::<EntityId>::new(), history.rewind_query(1, ray));
+    }
+
+    #[test]
+    fn lag_compensation_rewind_query_orders_by_distance() {
+        let mut world = world();
+        for (entity, x) in [(0, 8.), (1, 2.), (2, 5.)].iter() {
+            world
+                .transforms_mut()
+                .add(*entity, Transformed::new(Transform::new(Vec3::new(*x, 0., 0.))));
+            world.collidables_mut().add(*entity, unit_box());
+        }
+
+        let mut history = LagCompensation::new(10);
+        history.record(0, &world);
+
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(vec![1, 2, 0], history.rewind_query(0, ray));
+    }
+
+    #[test]
+    fn lag_compensation_history_is_bounded_by_window() {
+        let mut world = world();
+        let entity = 0;
+        world
+            .transforms_mut()
+            .add(entity, Transformed::new(Transform::new(Vec3::new(5., 0., 0.))));
+        world.collidables_mut().add(entity, unit_box());
+
+        let max_ticks = 3;
+        let mut history = LagCompensation::new(max_ticks);
+        history.record(0, &world);
+
+        move_entity(&mut world, entity, Vec3::new(5., 10., 0.));
+        for tick in 1..10 {
+            history.record(tick, &world);
+        }
+
+        assert_eq!(max_ticks as usize + 1, history.frames.len());
+
+        // Tick 0 has fallen out of the window, so the oldest available state is used instead.
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 100.);
+        assert_eq!(Vec::<EntityId>::new(), history.rewind_query(0, ray));
+    }
+
+    #[test]
+    fn lag_compensation_client_view_tick_clamps_to_window() {
+        let history = LagCompensation::new(6);
+        let tick_rate = 60;
+
+        // 100ms rtt + 50ms interpolation = 100ms = 6 ticks
+        assert_eq!(94, history.client_view_tick(100, tick_rate, 100, 50));
+        assert_eq!(97, history.client_view_tick(100, tick_rate, 50, 25));
+
+        // Clamped to the max window
+        assert_eq!(94, history.client_view_tick(100, tick_rate, 1000, 200));
+        assert_eq!(0, history.client_view_tick(2, tick_rate, 1000, 200));
+    }
+
+    #[test]
+    fn ticks_from_ms_rounds_up() {
+        assert_eq!(0, ticks_from_ms(0, 60));
+        assert_eq!(1, ticks_from_ms(1, 60));
+        assert_eq!(6, ticks_from_ms(100, 60));
+        assert_eq!(60, ticks_from_ms(1000, 60));
+        assert_eq!(257_698_038, ticks_from_ms(u32::MAX, 60));
+    }
+}
@@ -1,8 +1,13 @@
-mod ecs;
+pub mod ecs;
+pub mod interest_management;
+pub mod lag_compensation;
+pub mod session;
 mod math{
     pub use game_math::f32::*;
 }
 
+/// The netcode model a session uses.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum MultiplayerMode{
     DeterministicRollback,
     ClientServer
@@ -10,13 +15,117 @@ pub enum MultiplayerMode{
 
 pub type ClientId = u32;
 
+use ecs::{EntityId, World, WorldSettings};
+use interest_management::{InterestManager, ScopeRules};
+use lag_compensation::{LagCompensation, Tick};
+
+/// Settings for creating a Server.
+pub struct ServerSettings {
+    pub world_settings: WorldSettings,
+    pub max_outgoing_packet_bytes: usize,
+    pub outbound_tick_rate: u32,
+    pub tick_rate: u32,
+    pub max_compensation_ticks: Tick,
+    pub scope_cell_size: math::Num,
+    pub scope_rules: ScopeRules,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            world_settings: WorldSettings {
+                max_clients: 16,
+                max_entities: 1024,
+            },
+            max_outgoing_packet_bytes: 420,
+            outbound_tick_rate: 20,
+            tick_rate: 60,
+            max_compensation_ticks: 12,
+            scope_cell_size: 32.,
+            scope_rules: ScopeRules {
+                max_distance: 64.,
+                full_priority_distance: 16.,
+                max_updates_per_tick: 64,
+            },
+        }
+    }
+}
+
 pub struct Server {
     clients: Vec<Client>,
     max_outgoing_packet_bytes: usize,
     max_clients: u32,
     outbound_tick_rate: u32,
+    tick_rate: u32,
+    current_tick: Tick,
+    world: World,
+    lag_compensation: LagCompensation,
+    interest_management: InterestManager,
 }
 impl Server {
+    pub fn new(settings: &ServerSettings) -> Self {
+        let world_settings = &settings.world_settings;
+
+        Self {
+            clients: Vec::with_capacity(world_settings.max_clients),
+            max_outgoing_packet_bytes: settings.max_outgoing_packet_bytes,
+            max_clients: world_settings.max_clients as u32,
+            outbound_tick_rate: settings.outbound_tick_rate,
+            tick_rate: settings.tick_rate,
+            current_tick: 0,
+            world: World::new(world_settings),
+            lag_compensation: LagCompensation::new(settings.max_compensation_ticks),
+            interest_management: InterestManager::new(
+                world_settings,
+                settings.scope_cell_size,
+                settings.scope_rules,
+            ),
+        }
+    }
+
+    /// Adds a client to the server. Returns None if the server is full.
+    pub fn add_client(&mut self, client: Client) -> Option<ClientId> {
+        if self.clients.len() >= self.max_clients as usize {
+            return None;
+        }
+
+        let id = client.id;
+        self.clients.push(client);
+
+        Some(id)
+    }
+
+    /// Removes a client from the server, dropping them from all ghost scoping.
+    pub fn remove_client(&mut self, id: ClientId) {
+        self.clients.retain(|client| client.id != id);
+        self.interest_management.remove_client(&mut self.world, id);
+    }
+
+    /// The clients connected to the server.
+    pub fn clients(&self) -> &Vec<Client> {
+        &self.clients
+    }
+
+    /// Returns a mutable reference to the client with the given id, if connected.
+    pub fn client_mut(&mut self, id: ClientId) -> Option<&mut Client> {
+        self.clients.iter_mut().find(|client| client.id == id)
+    }
+
+    /// The current simulation tick.
+    pub fn current_tick(&self) -> Tick {
+        self.current_tick
+    }
+
+    /// The world the server simulates.
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    /// Returns a mutable reference to the world, for spawning and updating entities.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
     pub fn main_loop(&mut self) {
         loop {
             // Receive network messages
@@ -29,29 +138,107 @@ impl Server {
     }
 
     pub fn inbound_network(&mut self) {
-        println!("Receive network messages");
+        // Receive network messages
     }
 
     pub fn outbound_network(&mut self) {
-        for client in &self.clients {
-            // Calculate visible entities
-            // Calculate updates
+        for client in self.clients.iter_mut() {
+            // Calculate visible entities and updates
+            client.ghost_updates =
+                self.interest_management
+                    .scope(&mut self.world, client.id, client.view_position);
             // build message
             // send message
         }
-
-        println!("Send network messages");
     }
 
     pub fn tick(&mut self) {
-        println!("Server tick.");
+        // Record the state after simulating so shots can be rewound to it later.
+        self.lag_compensation.record(self.current_tick, &self.world);
+        self.interest_management.update_entities(&self.world);
+        self.current_tick += 1;
+    }
+
+    /// Returns the tick the client was viewing when they sent their latest command.
+    pub fn client_view_tick(&self, client: &Client) -> Tick {
+        self.lag_compensation.client_view_tick(
+            self.current_tick,
+            self.tick_rate,
+            client.rtt_ms,
+            client.interpolation_delay_ms,
+        )
+    }
+
+    /// Casts the ray against the world as it was on the given tick. Returns the entities hit, nearest first.
+    pub fn rewind_query(&self, tick: Tick, ray: math::Ray) -> Vec<EntityId> {
+        self.lag_compensation.rewind_query(tick, ray)
     }
 }
 
 pub struct Client {
+    id: ClientId,
     address: Address,
     max_packet_bytes: usize,
     outbound_tick_rate: u32,
+    rtt_ms: u32,
+    interpolation_delay_ms: u32,
+    view_position: math::Vec3,
+    ghost_updates: Vec<EntityId>,
+}
+
+impl Client {
+    pub fn new(id: ClientId, address: Address, max_packet_bytes: usize, outbound_tick_rate: u32) -> Self {
+        Self {
+            id,
+            address,
+            max_packet_bytes,
+            outbound_tick_rate,
+            rtt_ms: 0,
+            interpolation_delay_ms: 0,
+            view_position: math::Vec3::default(),
+            ghost_updates: vec![],
+        }
+    }
+
+    /// The id of the client.
+    pub fn id(&self) -> ClientId {
+        self.id
+    }
+
+    /// The latest measured round trip time to the client.
+    pub fn rtt_ms(&self) -> u32 {
+        self.rtt_ms
+    }
+
+    /// Updates the round trip time, used when rewinding for lag compensation.
+    pub fn set_rtt_ms(&mut self, rtt_ms: u32) {
+        self.rtt_ms = rtt_ms;
+    }
+
+    /// How far behind the latest state the client renders other entities.
+    pub fn interpolation_delay_ms(&self) -> u32 {
+        self.interpolation_delay_ms
+    }
+
+    /// Updates the interpolation delay, used when rewinding for lag compensation.
+    pub fn set_interpolation_delay_ms(&mut self, interpolation_delay_ms: u32) {
+        self.interpolation_delay_ms = interpolation_delay_ms;
+    }
+
+    /// Where the client is viewing the world from. Entities are scoped around it.
+    pub fn view_position(&self) -> math::Vec3 {
+        self.view_position
+    }
+
+    /// Moves the position the client is viewing the world from.
+    pub fn set_view_position(&mut self, view_position: math::Vec3) {
+        self.view_position = view_position;
+    }
+
+    /// The entities picked to update the client with on the latest outbound tick, most important first.
+    pub fn ghost_updates(&self) -> &[EntityId] {
+        &self.ghost_updates
+    }
 }
 
 pub struct Address {}
@@ -60,6 +247,12 @@ pub struct Address {}
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use ecs::components::{
+        ghosting::Ghostable,
+        spatial::{Collidable, Transform, Transformed},
+    };
+    use math::{Aabb, Ray, Vec3};
 
     macro_rules! count_items{
         ($name:ident) => {1};
@@ -78,4 +271,95 @@ mod tests {
         assert_eq!(2, Y);
         assert_eq!(3, Z);
     }
+
+    #[test]
+    fn server_rewind_query_uses_client_latency() {
+        let mut server = Server::new(&ServerSettings::default());
+        let id = server
+            .add_client(Client::new(0, Address {}, 420, 20))
+            .unwrap();
+
+        let entity = 0;
+        let world = server.world_mut();
+        world.transforms_mut().add(
+            entity,
+            Transformed::new(Transform::new(Vec3::new(5., 0., 0.))),
+        );
+        world.collidables_mut().add(
+            entity,
+            Collidable::new(Aabb::new(
+                Vec3::new(-0.5, -0.5, -0.5),
+                Vec3::new(0.5, 0.5, 0.5),
+            )),
+        );
+
+        for _ in 0..10 {
+            server.tick();
+        }
+
+        // Move out of the way of the shot after the client saw it.
+        server
+            .world_mut()
+            .transforms_mut()
+            .get_mut(entity)
+            .unwrap()
+            .current_mut()
+            .position = Vec3::new(5., 10., 0.);
+        for _ in 0..3 {
+            server.tick();
+        }
+
+        let ray = Ray::new(Vec3::default(), Vec3::new(1., 0., 0.), 100.);
+
+        // Without any latency the server only sees the current state.
+        let client = &server.clients()[0];
+        let tick = server.client_view_tick(client);
+        assert_eq!(server.current_tick(), tick);
+        assert_eq!(Vec::<EntityId>::new(), server.rewind_query(tick, ray));
+
+        // 100ms rtt + 50ms interpolation = 100ms = 6 ticks
+        let client = server.client_mut(id).unwrap();
+        client.set_rtt_ms(100);
+        client.set_interpolation_delay_ms(50);
+
+        let client = &server.clients()[0];
+        let tick = server.client_view_tick(client);
+        assert_eq!(server.current_tick() - 6, tick);
+        assert_eq!(vec![entity], server.rewind_query(tick, ray));
+    }
+
+    #[test]
+    fn server_outbound_network_scopes_entities_per_client() {
+        let settings = ServerSettings::default();
+        let mut server = Server::new(&settings);
+        let near = server
+            .add_client(Client::new(3, Address {}, 420, 20))
+            .unwrap();
+        let far = server
+            .add_client(Client::new(7, Address {}, 420, 20))
+            .unwrap();
+        server
+            .client_mut(far)
+            .unwrap()
+            .set_view_position(Vec3::new(1000., 0., 0.));
+
+        let entity = 2;
+        let world = server.world_mut();
+        world.transforms_mut().add(
+            entity,
+            Transformed::new(Transform::new(Vec3::new(5., 0., 0.))),
+        );
+        world
+            .ghostables_mut()
+            .add(entity, Ghostable::new(0, 1., &settings.world_settings));
+
+        server.tick();
+        server.outbound_network();
+
+        assert_eq!(&[entity], server.client_mut(near).unwrap().ghost_updates());
+        assert_eq!(0, server.client_mut(far).unwrap().ghost_updates().len());
+
+        let ghostable = server.world().ghostables();
+        assert_eq!(&vec![near], ghostable.get(entity).unwrap().clients());
+    }
 }
@@ -0,0 +1,494 @@
+use networking::rollback::prelude::*;
+
+use crate::{Address, Client, ClientId, MultiplayerMode, Server, ServerSettings};
+
+/// Events shared by every multiplayer mode.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SessionEvent {
+    /// A player was added to the lobby.
+    JoinedLobby(PlayerId),
+    /// A player is connected and their inputs will be simulated.
+    Connected(PlayerId),
+    /// A player was disconnected.
+    Disconnected(PlayerId),
+    /// The frame gave a different state when simulated again by a sync test.
+    Desync(FrameId),
+}
+
+/// A common interface for all netcode models, so games can switch between them through configuration.
+pub trait MultiplayerSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    /// The netcode model driving the session.
+    fn mode(&self) -> MultiplayerMode;
+
+    /// Adds a player on this machine.
+    fn add_local_player(&mut self) -> PlayerId;
+
+    /// Adds a player on another machine. Returns an error if the mode can't sync remote players yet.
+    fn add_remote_player(&mut self, address: Address) -> Result<PlayerId, String>;
+
+    /// Disconnects the player. Their inputs will no longer be registered.
+    fn disconnect(&mut self, player_id: PlayerId);
+
+    /// Register local input for the player.
+    fn register_local_input(&mut self, player_id: PlayerId, input: Input);
+
+    /// Tick the session, returning any events that occurred.
+    fn tick(&mut self) -> Vec<SessionEvent>;
+
+    /// Returns a reference to the current game state.
+    fn state(&self) -> &Game;
+}
+
+/// Builder for a multiplayer session. The game picks the mode and everything else is driven through `MultiplayerSession`.
+pub struct SessionBuilder {
+    mode: MultiplayerMode,
+    game_version: &'static str,
+    input_delay: FrameId,
+    rollback_modes: Vec<RollbackNetcodeModes>,
+    server_settings: ServerSettings,
+}
+
+impl SessionBuilder {
+    pub fn new(game_version: &'static str) -> Self {
+        Self {
+            mode: MultiplayerMode::DeterministicRollback,
+            game_version,
+            input_delay: 0,
+            rollback_modes: vec![],
+            server_settings: ServerSettings::default(),
+        }
+    }
+
+    /// Sets the netcode model.
+    pub fn mode(mut self, mode: MultiplayerMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the number of frames local input is delayed by.
+    pub fn input_delay(mut self, input_delay: FrameId) -> Self {
+        self.input_delay = input_delay;
+        self
+    }
+
+    /// Sets the test modes for rollback sessions.
+    pub fn rollback_modes(mut self, rollback_modes: Vec<RollbackNetcodeModes>) -> Self {
+        self.rollback_modes = rollback_modes;
+        self
+    }
+
+    /// Sets the server settings for client/server sessions.
+    pub fn server_settings(mut self, server_settings: ServerSettings) -> Self {
+        self.server_settings = server_settings;
+        self
+    }
+
+    /// Creates the session for the selected mode.
+    pub fn build<Game, Input>(self) -> Box<dyn MultiplayerSession<Game, Input> + Send>
+    where
+        Game: RollbackGameState<Input> + Send + 'static,
+        Input: GameInput + Send + 'static,
+    {
+        match self.mode {
+            MultiplayerMode::DeterministicRollback => Box::new(RollbackSession::new(
+                self.game_version,
+                self.input_delay,
+                self.rollback_modes,
+            )),
+            MultiplayerMode::ClientServer => Box::new(ClientServerSession::new(
+                self.input_delay,
+                &self.server_settings,
+            )),
+        }
+    }
+}
+
+/// A session driven by `RollbackNetcode`.
+pub struct RollbackSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    netcode: RollbackNetcode<Game, Input>,
+    connected: Vec<bool>,
+    events: Vec<SessionEvent>,
+}
+
+impl<Game, Input> RollbackSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    pub fn new(
+        game_version: &'static str,
+        input_delay: FrameId,
+        rollback_modes: Vec<RollbackNetcodeModes>,
+    ) -> Self {
+        Self {
+            netcode: RollbackNetcode::new(game_version, input_delay, rollback_modes),
+            connected: vec![],
+            events: vec![],
+        }
+    }
+
+    fn add_player(&mut self) -> PlayerId {
+        let player_id = self.netcode.add_player();
+        self.connected.push(true);
+
+        self.events.push(SessionEvent::JoinedLobby(player_id));
+        self.events.push(SessionEvent::Connected(player_id));
+
+        player_id
+    }
+
+    fn is_connected(&self, player_id: PlayerId) -> bool {
+        match self.connected.get(player_id as usize) {
+            Some(connected) => *connected,
+            None => false,
+        }
+    }
+}
+
+impl<Game, Input> MultiplayerSession<Game, Input> for RollbackSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    fn mode(&self) -> MultiplayerMode {
+        MultiplayerMode::DeterministicRollback
+    }
+
+    fn add_local_player(&mut self) -> PlayerId {
+        self.add_player()
+    }
+
+    fn add_remote_player(&mut self, _address: Address) -> Result<PlayerId, String> {
+        // The netcode doesn't sync inputs over the network yet, so a remote player would never receive any.
+        Err("Rollback sessions don't support remote players yet.".into())
+    }
+
+    fn disconnect(&mut self, player_id: PlayerId) {
+        if self.is_connected(player_id) {
+            self.connected[player_id as usize] = false;
+            self.events.push(SessionEvent::Disconnected(player_id));
+        }
+    }
+
+    fn register_local_input(&mut self, player_id: PlayerId, input: Input) {
+        if self.is_connected(player_id) {
+            self.netcode.register_local_input(player_id, input);
+        }
+    }
+
+    fn tick(&mut self) -> Vec<SessionEvent> {
+        for event in self.netcode.tick() {
+            match event {
+                RollbackEvent::Disconnected(player_id) => self.disconnect(player_id),
+                RollbackEvent::Desync(frame) => self.events.push(SessionEvent::Desync(frame)),
+            }
+        }
+
+        self.events.drain(..).collect()
+    }
+
+    fn state(&self) -> &Game {
+        self.netcode.state()
+    }
+}
+
+/// A session driven by an authoritative `Server` with a `Client` per player. The server runs locally as a listen server.
+///
+/// This is a local stub. The game is simulated here from each player's queued inputs, and nothing is sent or received over the network.
+/// The server only tracks the connected clients and ticks alongside the game. Its `World` isn't the game state.
+pub struct ClientServerSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    server: Server,
+    input_delay: FrameId,
+    max_packet_bytes: usize,
+    outbound_tick_rate: u32,
+    players: Vec<Option<PlayerInputs<Input>>>,
+    state: Game,
+    events: Vec<SessionEvent>,
+}
+
+/// The queued inputs for a player. The front of the queue is applied each tick.
+struct PlayerInputs<Input>
+where
+    Input: GameInput,
+{
+    client_id: ClientId,
+    inputs: std::collections::VecDeque<Input>,
+    last_input: Input,
+}
+
+impl<Game, Input> ClientServerSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    pub fn new(input_delay: FrameId, server_settings: &ServerSettings) -> Self {
+        Self {
+            server: Server::new(server_settings),
+            input_delay,
+            max_packet_bytes: server_settings.max_outgoing_packet_bytes,
+            outbound_tick_rate: server_settings.outbound_tick_rate,
+            players: vec![],
+            state: Game::new(),
+            events: vec![],
+        }
+    }
+
+    /// The server driving the session.
+    pub fn server(&self) -> &Server {
+        &self.server
+    }
+
+    fn add_player(&mut self, address: Address) -> PlayerId {
+        let player_id = self.players.len() as PlayerId;
+        self.events.push(SessionEvent::JoinedLobby(player_id));
+
+        let client_id = player_id as ClientId;
+        let client = Client::new(
+            client_id,
+            address,
+            self.max_packet_bytes,
+            self.outbound_tick_rate,
+        );
+
+        match self.server.add_client(client) {
+            Some(client_id) => {
+                // Pad the queue with empty inputs to match the input delay.
+                let mut inputs = std::collections::VecDeque::new();
+                for _ in 0..self.input_delay {
+                    inputs.push_back(Input::default());
+                }
+
+                self.players.push(Some(PlayerInputs {
+                    client_id,
+                    inputs,
+                    last_input: Input::default(),
+                }));
+                self.events.push(SessionEvent::Connected(player_id));
+            }
+            None => {
+                // Server is full
+                self.players.push(None);
+                self.events.push(SessionEvent::Disconnected(player_id));
+            }
+        }
+
+        player_id
+    }
+}
+
+impl<Game, Input> MultiplayerSession<Game, Input> for ClientServerSession<Game, Input>
+where
+    Game: RollbackGameState<Input>,
+    Input: GameInput,
+{
+    fn mode(&self) -> MultiplayerMode {
+        MultiplayerMode::ClientServer
+    }
+
+    fn add_local_player(&mut self) -> PlayerId {
+        self.add_player(Address {})
+    }
+
+    fn add_remote_player(&mut self, address: Address) -> Result<PlayerId, String> {
+        Ok(self.add_player(address))
+    }
+
+    fn disconnect(&mut self, player_id: PlayerId) {
+        if let Some(player) = self.players.get_mut(player_id as usize) {
+            if let Some(inputs) = player.take() {
+                self.server.remove_client(inputs.client_id);
+                self.events.push(SessionEvent::Disconnected(player_id));
+            }
+        }
+    }
+
+    fn register_local_input(&mut self, player_id: PlayerId, input: Input) {
+        if let Some(Some(player)) = self.players.get_mut(player_id as usize) {
+            player.inputs.push_back(input);
+        }
+    }
+
+    fn tick(&mut self) -> Vec<SessionEvent> {
+        self.server.inbound_network();
+
+        // The server is authoritative, so missing inputs repeat the last one received.
+        for (player_id, player) in self.players.iter_mut().enumerate() {
+            if let Some(player) = player {
+                if let Some(input) = player.inputs.pop_front() {
+                    player.last_input = input;
+                }
+
+                self.state
+                    .add_input(player_id as PlayerId, player.last_input);
+            }
+        }
+
+        self.state.tick();
+        self.server.tick();
+        self.server.outbound_network();
+
+        self.events.drain(..).collect()
+    }
+
+    fn state(&self) -> &Game {
+        &self.state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[derive(Copy, Clone, Default, Debug, PartialEq)]
+    struct TestInput {
+        value: i32,
+    }
+
+    impl GameInput for TestInput {}
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct TestGame {
+        inputs: Vec<i32>,
+        total: i32,
+    }
+
+    impl RollbackGameState<TestInput> for TestGame {
+        fn new() -> Self {
+            Self {
+                inputs: vec![0; 2],
+                total: 0,
+            }
+        }
+
+        fn add_input(&mut self, player_id: PlayerId, input: TestInput) {
+            self.inputs[player_id as usize] = input.value;
+        }
+
+        fn tick(&mut self) {
+            self.total += self.inputs.iter().sum::<i32>();
+        }
+    }
+
+    fn run_session(mode: MultiplayerMode) -> (Vec<SessionEvent>, TestGame) {
+        let mut session = SessionBuilder::new("test")
+            .mode(mode)
+            .input_delay(0)
+            .build::<TestGame, TestInput>();
+
+        assert_eq!(mode, session.mode());
+
+        let p1 = session.add_local_player();
+        let p2 = session.add_local_player();
+
+        let mut events = vec![];
+        for _ in 0..3 {
+            session.register_local_input(p1, TestInput { value: 1 });
+            session.register_local_input(p2, TestInput { value: 2 });
+            events.append(&mut session.tick());
+        }
+
+        session.disconnect(p2);
+        events.append(&mut session.tick());
+
+        (events, session.state().clone())
+    }
+
+    #[test]
+    fn session_events_are_the_same_for_all_modes() {
+        let expected = vec![
+            SessionEvent::JoinedLobby(0),
+            SessionEvent::Connected(0),
+            SessionEvent::JoinedLobby(1),
+            SessionEvent::Connected(1),
+            SessionEvent::Disconnected(1),
+        ];
+
+        let (rollback_events, _) = run_session(MultiplayerMode::DeterministicRollback);
+        let (client_server_events, _) = run_session(MultiplayerMode::ClientServer);
+
+        assert_eq!(expected, rollback_events);
+        assert_eq!(expected, client_server_events);
+    }
+
+    #[test]
+    fn session_client_server_applies_inputs() {
+        let (_, state) = run_session(MultiplayerMode::ClientServer);
+
+        // Four ticks of both players' inputs. The test game holds on to the disconnected player's last input.
+        assert_eq!(4 * (1 + 2), state.total);
+    }
+
+    #[test]
+    fn session_client_server_removes_disconnected_clients() {
+        let mut session: ClientServerSession<TestGame, TestInput> =
+            ClientServerSession::new(0, &ServerSettings::default());
+
+        let p1 = session.add_local_player();
+        let p2 = session.add_remote_player(Address {}).unwrap();
+        assert_eq!(2, session.server().clients().len());
+
+        session.disconnect(p1);
+        assert_eq!(1, session.server().clients().len());
+        assert_eq!(p2 as ClientId, session.server().clients()[0].id());
+    }
+
+    #[test]
+    fn session_rollback_rejects_remote_players() {
+        let mut session = SessionBuilder::new("test").build::<TestGame, TestInput>();
+
+        assert_eq!(true, session.add_remote_player(Address {}).is_err());
+        assert_eq!(Vec::<SessionEvent>::new(), session.tick());
+    }
+
+    /// Counts the ticks run across every copy of the game, so simulating a frame again gives a different state.
+    #[derive(Clone, Debug, PartialEq)]
+    struct DesyncGame {
+        ticks: usize,
+    }
+
+    static DESYNC_GAME_TICKS: AtomicUsize = AtomicUsize::new(0);
+
+    impl RollbackGameState<TestInput> for DesyncGame {
+        fn new() -> Self {
+            Self { ticks: 0 }
+        }
+
+        fn add_input(&mut self, _player_id: PlayerId, _input: TestInput) {}
+
+        fn tick(&mut self) {
+            self.ticks = DESYNC_GAME_TICKS.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn session_rollback_sync_test_reports_desyncs() {
+        let sync_test = || {
+            SessionBuilder::new("test")
+                .input_delay(0)
+                .rollback_modes(vec![RollbackNetcodeModes::SyncTest])
+        };
+
+        let mut session = sync_test().build::<TestGame, TestInput>();
+        let player = session.add_local_player();
+        session.register_local_input(player, TestInput { value: 1 });
+        assert_eq!(false, session.tick().contains(&SessionEvent::Desync(0)));
+
+        let mut session = sync_test().build::<DesyncGame, TestInput>();
+        let player = session.add_local_player();
+        session.register_local_input(player, TestInput { value: 1 });
+        assert_eq!(true, session.tick().contains(&SessionEvent::Desync(0)));
+    }
+}
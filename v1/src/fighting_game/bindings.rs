@@ -0,0 +1,389 @@
+use networking::rollback::PlayerId;
+use portia::input::{GamepadAxis, GamepadButton, GamepadId, Key};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// How far an axis bound as a button must be pushed to count as pressed.
+const AXIS_PRESS_THRESHOLD: f32 = 0.5;
+
+/// A keyboard key, gamepad button or gamepad axis that can act as a button.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Binding {
+    Key(Key),
+    Button(GamepadButton),
+    /// Pressed when the axis is pushed past half way in the positive direction.
+    AxisPositive(GamepadAxis),
+    /// Pressed when the axis is pushed past half way in the negative direction.
+    AxisNegative(GamepadAxis),
+}
+
+/// An analog axis used for movement.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AxisBinding {
+    pub axis: GamepadAxis,
+    #[serde(default)]
+    pub inverted: bool,
+}
+
+/// Where a player's input comes from.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Device {
+    Keyboard,
+    Gamepad(GamepadId),
+}
+
+/// The held state of a device, used to resolve bindings.
+pub trait DeviceState {
+    fn key_held(&self, key: Key) -> bool;
+    fn button_held(&self, button: GamepadButton) -> bool;
+    fn axis(&self, axis: GamepadAxis) -> f32;
+}
+
+/// Maps physical inputs to each field of the game's Input. Any number of bindings can be used for each.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Bindings {
+    /// Analog movement. Positive x is right and positive y is up.
+    #[serde(default)]
+    pub move_x_axis: Vec<AxisBinding>,
+    #[serde(default)]
+    pub move_y_axis: Vec<AxisBinding>,
+    /// Analog values closer to 0 than this are ignored. Ranges from 0 to 1.
+    #[serde(default)]
+    pub deadzone: f32,
+
+    /// Digital movement. Overrides analog movement while held.
+    pub up: Vec<Binding>,
+    pub down: Vec<Binding>,
+    pub left: Vec<Binding>,
+    pub right: Vec<Binding>,
+
+    pub jump: Vec<Binding>,
+    pub short_hop: Vec<Binding>,
+    pub light_attack: Vec<Binding>,
+    pub heavy_attack: Vec<Binding>,
+    pub special: Vec<Binding>,
+    pub shield: Vec<Binding>,
+    pub grab: Vec<Binding>,
+}
+
+impl Bindings {
+    /// Arrow keys to move, with the attacks on the bottom row of letters.
+    pub fn keyboard() -> Self {
+        let keys = |keys: &[Key]| keys.iter().map(|k| Binding::Key(*k)).collect();
+
+        Self {
+            move_x_axis: vec![],
+            move_y_axis: vec![],
+            deadzone: 0.0,
+
+            up: keys(&[Key::Up]),
+            down: keys(&[Key::Down]),
+            left: keys(&[Key::Left]),
+            right: keys(&[Key::Right]),
+
+            jump: keys(&[Key::Space]),
+            short_hop: keys(&[Key::A]),
+            light_attack: keys(&[Key::Z]),
+            heavy_attack: keys(&[Key::X]),
+            special: keys(&[Key::C]),
+            shield: keys(&[Key::V]),
+            grab: keys(&[Key::B]),
+        }
+    }
+
+    /// The left stick or d-pad to move, with the attacks on the face buttons.
+    pub fn gamepad() -> Self {
+        let buttons = |buttons: &[GamepadButton]| {
+            buttons
+                .iter()
+                .map(|b| Binding::Button(*b))
+                .collect::<Vec<Binding>>()
+        };
+        let stick = |axis: GamepadAxis| {
+            vec![AxisBinding {
+                axis,
+                inverted: false,
+            }]
+        };
+
+        Self {
+            move_x_axis: stick(GamepadAxis::LeftStickX),
+            move_y_axis: stick(GamepadAxis::LeftStickY),
+            deadzone: 0.2,
+
+            up: buttons(&[GamepadButton::DPadUp]),
+            down: buttons(&[GamepadButton::DPadDown]),
+            left: buttons(&[GamepadButton::DPadLeft]),
+            right: buttons(&[GamepadButton::DPadRight]),
+
+            jump: buttons(&[GamepadButton::North, GamepadButton::East]),
+            short_hop: vec![],
+            light_attack: buttons(&[GamepadButton::South]),
+            heavy_attack: vec![Binding::AxisPositive(GamepadAxis::RightStickX)],
+            special: buttons(&[GamepadButton::West]),
+            shield: buttons(&[GamepadButton::LeftTrigger, GamepadButton::RightTrigger]),
+            grab: buttons(&[GamepadButton::RightBumper]),
+        }
+    }
+
+    /// Reads the game input from the device.
+    pub fn resolve<D>(&self, device: &D) -> super::Input
+    where
+        D: DeviceState,
+    {
+        let held = |bindings: &Vec<Binding>| bindings.iter().any(|b| is_held(*b, device));
+
+        super::Input {
+            move_x_axis: self.resolve_axis(device, &self.move_x_axis, &self.left, &self.right),
+            move_y_axis: self.resolve_axis(device, &self.move_y_axis, &self.down, &self.up),
+
+            jump_pressed: held(&self.jump),
+            short_hop_pressed: held(&self.short_hop),
+            light_atk_pressed: held(&self.light_attack),
+            heavy_atk_pressed: held(&self.heavy_attack),
+            special_pressed: held(&self.special),
+            shield_pressed: held(&self.shield),
+            grab_pressed: held(&self.grab),
+        }
+    }
+
+    /// Digital directions win over analog axes. Of the analog axes, the one pushed furthest is used.
+    fn resolve_axis<D>(
+        &self,
+        device: &D,
+        axes: &[AxisBinding],
+        negative: &[Binding],
+        positive: &[Binding],
+    ) -> i8
+    where
+        D: DeviceState,
+    {
+        if positive.iter().any(|b| is_held(*b, device)) {
+            return i8::MAX;
+        }
+        if negative.iter().any(|b| is_held(*b, device)) {
+            return i8::MIN;
+        }
+
+        let value = axes
+            .iter()
+            .map(|binding| match binding.inverted {
+                true => -device.axis(binding.axis),
+                false => device.axis(binding.axis),
+            })
+            .fold(0.0, |furthest: f32, value| {
+                if value.abs() > furthest.abs() {
+                    value
+                } else {
+                    furthest
+                }
+            });
+
+        apply_deadzone(value, self.deadzone)
+    }
+}
+
+/// Zeroes values inside the deadzone and rescales the rest so movement starts from 0 at its edge.
+pub fn apply_deadzone(value: f32, deadzone: f32) -> i8 {
+    let magnitude = value.abs().min(1.0);
+    if magnitude <= deadzone || deadzone >= 1.0 {
+        return 0;
+    }
+
+    let scaled = (magnitude - deadzone) / (1.0 - deadzone) * i8::MAX as f32;
+    (scaled.round() * value.signum()) as i8
+}
+
+fn is_held<D>(binding: Binding, device: &D) -> bool
+where
+    D: DeviceState,
+{
+    match binding {
+        Binding::Key(key) => device.key_held(key),
+        Binding::Button(button) => device.button_held(button),
+        Binding::AxisPositive(axis) => device.axis(axis) >= AXIS_PRESS_THRESHOLD,
+        Binding::AxisNegative(axis) => device.axis(axis) <= -AXIS_PRESS_THRESHOLD,
+    }
+}
+
+/// A local player and the device they play with.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerBindings {
+    pub player: PlayerId,
+    pub device: Device,
+    pub bindings: Bindings,
+}
+
+/// The bindings for every local player.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub players: Vec<PlayerBindings>,
+}
+
+impl Default for Profile {
+    /// The keyboard controls the first player and the first gamepad the second.
+    fn default() -> Self {
+        Self {
+            players: vec![
+                PlayerBindings {
+                    player: 0,
+                    device: Device::Keyboard,
+                    bindings: Bindings::keyboard(),
+                },
+                PlayerBindings {
+                    player: 1,
+                    device: Device::Gamepad(0),
+                    bindings: Bindings::gamepad(),
+                },
+            ],
+        }
+    }
+}
+
+impl Profile {
+    /// Parses a profile from RON.
+    pub fn parse(text: &str) -> Result<Self, String> {
+        ron::de::from_str(text).map_err(|e| {
+            format!(
+                "line {}, column {}: {}",
+                e.position.line, e.position.col, e.code
+            )
+        })
+    }
+
+    pub fn to_ron(&self) -> String {
+        let config = ron::ser::PrettyConfig::new();
+        ron::ser::to_string_pretty(self, config).expect("Profiles should always serialize!")
+    }
+
+    /// Loads the profile from the file. If it doesn't exist, the default profile is written to it.
+    pub fn load_or_create(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => Self::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                let profile = Self::default();
+                profile.save(path)?;
+                Ok(profile)
+            }
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        std::fs::write(path, self.to_ron()).map_err(|e| e.to_string())
+    }
+
+    /// The ids of every player in the profile, sorted and without duplicates.
+    pub fn player_ids(&self) -> Vec<PlayerId> {
+        let mut ids: Vec<PlayerId> = self.players.iter().map(|p| p.player).collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct TestDevice {
+        keys: Vec<Key>,
+        buttons: Vec<GamepadButton>,
+        axes: Vec<(GamepadAxis, f32)>,
+    }
+
+    impl DeviceState for TestDevice {
+        fn key_held(&self, key: Key) -> bool {
+            self.keys.contains(&key)
+        }
+
+        fn button_held(&self, button: GamepadButton) -> bool {
+            self.buttons.contains(&button)
+        }
+
+        fn axis(&self, axis: GamepadAxis) -> f32 {
+            self.axes
+                .iter()
+                .find(|(a, _)| *a == axis)
+                .map(|(_, value)| *value)
+                .unwrap_or(0.0)
+        }
+    }
+
+    #[test]
+    fn bindings_deadzone_rescales_axis() {
+        assert_eq!(0, apply_deadzone(0.1, 0.2));
+        assert_eq!(0, apply_deadzone(-0.2, 0.2));
+        assert_eq!(64, apply_deadzone(0.6, 0.2));
+        assert_eq!(-127, apply_deadzone(-1.0, 0.2));
+        assert_eq!(127, apply_deadzone(1.5, 0.2));
+    }
+
+    #[test]
+    fn bindings_resolve_keyboard_and_gamepad() {
+        let keyboard = TestDevice {
+            keys: vec![Key::Left, Key::Z],
+            ..TestDevice::default()
+        };
+        let input = Bindings::keyboard().resolve(&keyboard);
+        assert_eq!(i8::MIN, input.move_x_axis);
+        assert_eq!(0, input.move_y_axis);
+        assert_eq!(true, input.light_atk_pressed);
+        assert_eq!(false, input.jump_pressed);
+
+        let gamepad = TestDevice {
+            buttons: vec![GamepadButton::North],
+            axes: vec![
+                (GamepadAxis::LeftStickX, 0.1),
+                (GamepadAxis::LeftStickY, -1.0),
+                (GamepadAxis::RightStickX, 0.9),
+            ],
+            ..TestDevice::default()
+        };
+        let input = Bindings::gamepad().resolve(&gamepad);
+        assert_eq!(0, input.move_x_axis);
+        assert_eq!(-127, input.move_y_axis);
+        assert_eq!(true, input.jump_pressed);
+        assert_eq!(true, input.heavy_atk_pressed);
+
+        // The d-pad overrides the stick.
+        let gamepad = TestDevice {
+            buttons: vec![GamepadButton::DPadUp],
+            ..gamepad
+        };
+        assert_eq!(i8::MAX, Bindings::gamepad().resolve(&gamepad).move_y_axis);
+    }
+
+    #[test]
+    fn bindings_profile_round_trips_through_file() {
+        let path = std::env::temp_dir()
+            .join("final_destination_bindings_test")
+            .join("bindings.ron");
+        let _ = std::fs::remove_file(&path);
+
+        let created = Profile::load_or_create(&path).unwrap();
+        assert_eq!(Profile::default(), created);
+        assert_eq!(true, path.exists());
+
+        let mut profile = created;
+        profile.players[1].player = 0;
+        profile.players[1].bindings.jump = vec![Binding::Key(Key::W)];
+        profile.save(&path).unwrap();
+
+        let loaded = Profile::load_or_create(&path).unwrap();
+        assert_eq!(profile, loaded);
+        assert_eq!(vec![0], loaded.player_ids());
+
+        assert_eq!(
+            true,
+            Profile::parse("(players: [(player: 0)])")
+                .unwrap_err()
+                .ends_with("missing field `device`")
+        );
+    }
+}
@@ -0,0 +1,119 @@
+use super::character_stats::{CharacterError, CharacterStats};
+use portia::file_system::{Asset, AssetState, FileSystem, LoadableAsset};
+use std::time::SystemTime;
+
+/// A character file read and validated on a background thread.
+pub struct CharacterFile {
+    pub result: Result<CharacterStats, Vec<CharacterError>>,
+}
+
+impl LoadableAsset for CharacterFile {
+    fn asset_from_file(file: &'static str) -> Self {
+        let result = match std::fs::read_to_string(FileSystem::res_dir().join(file)) {
+            Ok(text) => CharacterStats::parse(&text),
+            Err(e) => Err(vec![CharacterError::Io {
+                message: e.to_string(),
+            }]),
+        };
+
+        Self { result }
+    }
+}
+
+/// The result of a character file finishing loading.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharacterEvent {
+    Loaded {
+        file: &'static str,
+    },
+    /// The file failed to load. The last valid version of the character is kept.
+    Failed {
+        file: &'static str,
+        errors: Vec<CharacterError>,
+    },
+}
+
+struct LoadedCharacter {
+    file: &'static str,
+    asset: Option<Asset<CharacterFile>>,
+    modified: Option<SystemTime>,
+    stats: Option<CharacterStats>,
+}
+
+/// Loads character files from the res directory. When hot reloading, files are reloaded whenever they change on disk.
+pub struct CharacterLoader {
+    hot_reload: bool,
+    characters: Vec<LoadedCharacter>,
+}
+
+impl CharacterLoader {
+    /// Creates a new character loader.
+    pub fn new(hot_reload: bool) -> Self {
+        Self {
+            hot_reload,
+            characters: vec![],
+        }
+    }
+
+    /// Starts loading the character file in the background.
+    pub fn load(&mut self, file: &'static str) {
+        if self.find_index(file).is_some() {
+            return;
+        }
+
+        self.characters.push(LoadedCharacter {
+            file,
+            asset: Some(Asset::load(file)),
+            modified: modified_time(file),
+            stats: None,
+        });
+    }
+
+    /// Returns the last valid version of the character, if it has loaded.
+    pub fn character(&self, file: &'static str) -> Option<&CharacterStats> {
+        self.find_index(file)
+            .and_then(|i| self.characters[i].stats.as_ref())
+    }
+
+    /// Receives any finished loads and starts reloading changed files. Returns the characters that finished loading.
+    pub fn update(&mut self) -> Vec<CharacterEvent> {
+        let mut events = vec![];
+
+        for character in self.characters.iter_mut() {
+            if let Some(asset) = &mut character.asset {
+                if asset.try_receive() == AssetState::Ready {
+                    let file = character.file;
+                    let result = asset.consume().map(|f| f.result);
+                    character.asset = None;
+
+                    match result {
+                        Some(Ok(stats)) => {
+                            character.stats = Some(stats);
+                            events.push(CharacterEvent::Loaded { file });
+                        }
+                        Some(Err(errors)) => events.push(CharacterEvent::Failed { file, errors }),
+                        None => {}
+                    }
+                }
+            } else if self.hot_reload {
+                let modified = modified_time(character.file);
+                if modified != character.modified {
+                    character.modified = modified;
+                    character.asset = Some(Asset::load(character.file));
+                }
+            }
+        }
+
+        events
+    }
+
+    fn find_index(&self, file: &'static str) -> Option<usize> {
+        self.characters.iter().position(|c| c.file == file)
+    }
+}
+
+fn modified_time(file: &'static str) -> Option<SystemTime> {
+    std::fs::metadata(FileSystem::res_dir().join(file))
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
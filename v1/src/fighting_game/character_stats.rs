@@ -0,0 +1,462 @@
+use super::motion::Motion;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// A selectable skin for a character
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Skin {
+    pub file: String,
+    pub thumbnail: String,
+}
+
+/// A character definition. Loaded from a RON file so designers can tune it without recompiling.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CharacterStats {
+    pub name: String,
+    pub primary_skin: Skin,
+    #[serde(default)]
+    pub alternate_skins: Vec<Skin>,
+    pub stats: Stats,
+
+    pub required_states: RequiredStates,
+    #[serde(default)]
+    pub command_specials: Vec<CommandSpecial>,
+    pub states: Vec<State>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub weight: u32,
+    pub gravity_percentage: u32,
+    pub walk_speed: u32,
+    pub run_speed: u32,
+    pub air_move_speed: u32,
+    pub air_acceleration: u32,
+    pub jump_velocity: u32,
+    pub short_hop_velocity: u32,
+    pub fall_speed: u32,
+    pub fast_fall_speed: u32,
+    pub shield_health: u32,
+    pub num_jumps: u32,
+}
+
+/// A handle for how states are referenced.
+pub type StateId = u32;
+
+/// States that every character must fulfill
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RequiredStates {
+    pub jump: StateId,
+    pub special: SpecialStates,
+    pub ground: GroundStates,
+    pub air: AirStates,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GroundStates {
+    pub tilts: DirectionalGroundStates,
+    pub smashes: DirectionalGroundStates,
+    pub jab: StateId,
+    pub grab: StateId,
+    pub idle: StateId,
+    pub crouch: StateId,
+    pub shielding: StateId,
+    pub stunned: StateId,
+    pub dashing: StateId,
+    pub dash_attack: StateId,
+    pub grabbed: StateId,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpecialStates {
+    pub forward_special: StateId,
+    pub up_special: StateId,
+    pub down_special: StateId,
+    pub neutral_special: StateId,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DirectionalGroundStates {
+    pub forward: StateId,
+    pub down: StateId,
+    pub up: StateId,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AirStates {
+    pub forward_air: StateId,
+    pub back_air: StateId,
+    pub up_air: StateId,
+    pub down_air: StateId,
+    pub air_dodge: StateId,
+    pub neutral_air: StateId,
+    pub fall: StateId,
+    pub helpless: StateId,
+    /// Hanging from a ledge. Held until the character climbs, jumps or lets go.
+    pub ledge_hang: StateId,
+}
+
+/// A special move performed by pressing special after a motion. Earlier entries take priority.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommandSpecial {
+    pub motion: Motion,
+    pub state: StateId,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct State {
+    pub id: StateId,
+    pub name: String,
+    pub frames: Vec<Frame>,
+    pub animation: String,
+}
+
+/// An action a character can take. Directions are relative to the direction the character is facing.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Input {
+    Jump,
+    ShortJump,
+    Shield,
+    Grab,
+    Special(DirectionalInput),
+    Tilt(DirectionalInput),
+    Smash(DirectionalInput),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DirectionalInput {
+    None,
+    Up,
+    Down,
+    Forward,
+    Back,
+}
+
+/// The frame data. Contains boxes, stun data, recovery data, etc. Also includes velocity to apply to character. Oriented facing right.
+/// Everything but the duration may be left out of a character file, defaulting to empty.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Frame {
+    /// Optional parameter for how long this frame is active for.
+    pub duration: Option<u32>,
+    pub push_box: Option<Aabb>,
+    pub hit_boxes: Vec<Aabb>,
+    pub hurt_boxes: Vec<Aabb>,
+    pub grab_boxes: Vec<Aabb>,
+    /// Frames of hitstun when a hit box connects. For grab boxes, how long the target is held.
+    pub hit_stun: u32,
+    pub block_stun: u32,
+    /// Damage added to the target's percent, and taken from shields when blocked.
+    pub damage: u32,
+    /// Knockback at 0% damage against a weight of 100.
+    pub knockback: (i32, i32),
+    /// How much the knockback grows with the target's damage, as a percentage per 100% damage.
+    pub knockback_growth: u32,
+    /// When two characters hit each other, only the higher priority hit lands. Equal priorities trade.
+    pub priority: u32,
+    /// Frames of landing lag if the character lands during this frame.
+    pub recovery: u32,
+    /// Velocity set when the frame is entered. Ignored if zero.
+    pub velocity: (i32, i32),
+    /// The actions that may cancel this frame.
+    pub cancels: Vec<Cancel>,
+}
+
+/// A kind of action that may cancel a frame.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Cancel {
+    Jump,
+    Shield,
+    Grab,
+    Attack,
+    Special,
+}
+
+impl State {
+    /// Returns the index of the frame shown on the given frame of the state, or None once the state is finished.
+    /// A frame without a duration is held until the character leaves the state.
+    pub fn frame_index(&self, state_frame: u32) -> Option<usize> {
+        let mut start = 0;
+        for (i, frame) in self.frames.iter().enumerate() {
+            match frame.duration {
+                Some(duration) => {
+                    start += duration;
+                    if state_frame < start {
+                        return Some(i);
+                    }
+                }
+                None => return Some(i),
+            }
+        }
+
+        None
+    }
+
+    /// Returns the frame of the state the frame at the given index starts on.
+    pub fn frame_start(&self, index: usize) -> u32 {
+        self.frames[..index]
+            .iter()
+            .map(|frame| frame.duration.unwrap_or(0))
+            .sum()
+    }
+}
+
+/// A axis aligned bounding box. (0,0) is the center of the character, so x and y are the offset of the box's center from that.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Aabb {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// An error found when loading a character.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CharacterError {
+    /// The file could not be read.
+    Io { message: String },
+    /// The file is not a valid character definition.
+    Parse {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+    /// Two states share the same id.
+    DuplicateState {
+        id: StateId,
+        first: String,
+        second: String,
+    },
+    /// A required state refers to a state that doesn't exist.
+    MissingState { required: &'static str, id: StateId },
+    /// A command special refers to a state that doesn't exist.
+    MissingCommandState { motion: Motion, id: StateId },
+    /// A state has no frames.
+    EmptyState { id: StateId, name: String },
+}
+
+impl fmt::Display for CharacterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CharacterError::Io { message } => write!(f, "unable to read file: {}", message),
+            CharacterError::Parse {
+                line,
+                column,
+                message,
+            } => write!(f, "line {}, column {}: {}", line, column, message),
+            CharacterError::DuplicateState { id, first, second } => {
+                write!(f, "states '{}' and '{}' both use id {}", first, second, id)
+            }
+            CharacterError::MissingState { required, id } => write!(
+                f,
+                "required_states.{} refers to state {}, which does not exist",
+                required, id
+            ),
+            CharacterError::MissingCommandState { motion, id } => write!(
+                f,
+                "command special {:?} refers to state {}, which does not exist",
+                motion, id
+            ),
+            CharacterError::EmptyState { id, name } => {
+                write!(f, "state '{}' ({}) has no frames", name, id)
+            }
+        }
+    }
+}
+
+impl CharacterStats {
+    /// Parses and validates a character from RON. Returns every problem found.
+    pub fn parse(text: &str) -> Result<Self, Vec<CharacterError>> {
+        let character: Self = ron::de::from_str(text).map_err(|e| {
+            vec![CharacterError::Parse {
+                line: e.position.line,
+                column: e.position.col,
+                message: e.code.to_string(),
+            }]
+        })?;
+
+        character.validate()?;
+
+        Ok(character)
+    }
+
+    /// Checks that state ids are unique, states have frames and every required and command state resolves.
+    pub fn validate(&self) -> Result<(), Vec<CharacterError>> {
+        let mut errors = vec![];
+
+        for (i, state) in self.states.iter().enumerate() {
+            if let Some(first) = self.states[..i].iter().find(|s| s.id == state.id) {
+                errors.push(CharacterError::DuplicateState {
+                    id: state.id,
+                    first: first.name.clone(),
+                    second: state.name.clone(),
+                });
+            }
+
+            if state.frames.is_empty() {
+                errors.push(CharacterError::EmptyState {
+                    id: state.id,
+                    name: state.name.clone(),
+                });
+            }
+        }
+
+        for (required, id) in self.required_states.ids() {
+            if self.state(id).is_none() {
+                errors.push(CharacterError::MissingState { required, id });
+            }
+        }
+
+        for command in self.command_specials.iter() {
+            if self.state(command.state).is_none() {
+                errors.push(CharacterError::MissingCommandState {
+                    motion: command.motion,
+                    id: command.state,
+                });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Returns the state with the given id.
+    pub fn state(&self, id: StateId) -> Option<&State> {
+        self.states.iter().find(|state| state.id == id)
+    }
+}
+
+impl RequiredStates {
+    /// Returns every required state along with its path in the character file.
+    pub fn ids(&self) -> Vec<(&'static str, StateId)> {
+        let ground = &self.ground;
+        let special = &self.special;
+        let air = &self.air;
+
+        vec![
+            ("jump", self.jump),
+            ("special.forward_special", special.forward_special),
+            ("special.up_special", special.up_special),
+            ("special.down_special", special.down_special),
+            ("special.neutral_special", special.neutral_special),
+            ("ground.tilts.forward", ground.tilts.forward),
+            ("ground.tilts.down", ground.tilts.down),
+            ("ground.tilts.up", ground.tilts.up),
+            ("ground.smashes.forward", ground.smashes.forward),
+            ("ground.smashes.down", ground.smashes.down),
+            ("ground.smashes.up", ground.smashes.up),
+            ("ground.jab", ground.jab),
+            ("ground.grab", ground.grab),
+            ("ground.idle", ground.idle),
+            ("ground.crouch", ground.crouch),
+            ("ground.shielding", ground.shielding),
+            ("ground.stunned", ground.stunned),
+            ("ground.dashing", ground.dashing),
+            ("ground.dash_attack", ground.dash_attack),
+            ("ground.grabbed", ground.grabbed),
+            ("air.forward_air", air.forward_air),
+            ("air.back_air", air.back_air),
+            ("air.up_air", air.up_air),
+            ("air.down_air", air.down_air),
+            ("air.air_dodge", air.air_dodge),
+            ("air.neutral_air", air.neutral_air),
+            ("air.fall", air.fall),
+            ("air.helpless", air.helpless),
+            ("air.ledge_hang", air.ledge_hang),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../res/characters/example.ron");
+
+    #[test]
+    fn example_character_is_valid() {
+        let character = CharacterStats::parse(EXAMPLE).unwrap();
+
+        assert_eq!(
+            true,
+            character
+                .state(character.required_states.ground.idle)
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn parse_reports_line_of_syntax_error() {
+        let text = EXAMPLE.replacen("weight:", "weight =", 1);
+        let errors = CharacterStats::parse(&text).unwrap_err();
+
+        let line = EXAMPLE.lines().position(|l| l.contains("weight:")).unwrap() + 1;
+
+        match &errors[..] {
+            [CharacterError::Parse { line: l, .. }] => assert_eq!(line, *l),
+            _ => panic!("expected a parse error, got {:?}", errors),
+        }
+    }
+
+    #[test]
+    fn validate_reports_missing_and_duplicate_states() {
+        let mut character = CharacterStats::parse(EXAMPLE).unwrap();
+        let idle = character.required_states.ground.idle;
+
+        character.required_states.air.helpless = 999;
+        character.states.retain(|state| state.id != idle);
+        let mut duplicate = character.states[0].clone();
+        duplicate.name = "copy".into();
+        duplicate.frames.clear();
+        character.states.push(duplicate.clone());
+
+        let errors = character.validate().unwrap_err();
+
+        assert_eq!(
+            vec![
+                CharacterError::DuplicateState {
+                    id: duplicate.id,
+                    first: character.states[0].name.clone(),
+                    second: "copy".into(),
+                },
+                CharacterError::EmptyState {
+                    id: duplicate.id,
+                    name: "copy".into(),
+                },
+                CharacterError::MissingState {
+                    required: "ground.idle",
+                    id: idle,
+                },
+                CharacterError::MissingState {
+                    required: "air.helpless",
+                    id: 999,
+                },
+            ],
+            errors
+        );
+        assert_eq!(
+            "required_states.air.helpless refers to state 999, which does not exist",
+            errors[3].to_string()
+        );
+    }
+
+    #[test]
+    fn validate_reports_missing_command_states() {
+        let mut character = CharacterStats::parse(EXAMPLE).unwrap();
+        character.command_specials.push(CommandSpecial {
+            motion: Motion::ChargeDownUp,
+            state: 999,
+        });
+
+        assert_eq!(
+            Err(vec![CharacterError::MissingCommandState {
+                motion: Motion::ChargeDownUp,
+                id: 999,
+            }]),
+            character.validate()
+        );
+    }
+}
@@ -0,0 +1,477 @@
+use super::{stage::Stage, GameState, Input};
+use game_math::random::Random;
+use std::collections::VecDeque;
+
+/// How close the opponent needs to be to attack them.
+const ATTACK_RANGE: i32 = 700;
+/// How close an attacking opponent needs to be to shield.
+const THREAT_RANGE: i32 = 900;
+/// Further than this, the CPU walks straight in.
+const APPROACH_RANGE: i32 = 1500;
+/// How far below a ledge the CPU uses its up special instead of jumping.
+const RECOVERY_SPECIAL_DEPTH: i32 = 600;
+/// How long the CPU sticks with a spacing decision.
+const SPACING_FRAMES: u32 = 12;
+
+/// How well the CPU plays.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Difficulty {
+    /// How many frames old the state the CPU reacts to is.
+    pub reaction_delay: u32,
+    /// The percent chance each frame's input is fumbled.
+    pub execution_error: u32,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::level(Self::DEFAULT_LEVEL)
+    }
+}
+
+impl Difficulty {
+    pub const MIN_LEVEL: u32 = 1;
+    pub const MAX_LEVEL: u32 = 9;
+    pub const DEFAULT_LEVEL: u32 = 5;
+
+    /// Creates a difficulty from a level between `MIN_LEVEL` and `MAX_LEVEL`. The highest level reacts within a few frames and never fumbles.
+    pub fn level(level: u32) -> Self {
+        let level = level.clamp(Self::MIN_LEVEL, Self::MAX_LEVEL);
+
+        Self {
+            reaction_delay: 3 + (Self::MAX_LEVEL - level) * 3,
+            execution_error: (Self::MAX_LEVEL - level) * 5,
+        }
+    }
+}
+
+/// What the CPU knows about the opponent.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Opponent {
+    position: [i32; 3],
+    attacking: bool,
+}
+
+/// What the CPU saw on a frame.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Observation {
+    position: [i32; 3],
+    velocity: (i32, i32),
+    in_air: bool,
+    actionable: bool,
+    hanging: bool,
+    opponent: Option<Opponent>,
+}
+
+/// A computer controlled player. Produces input from the game state like a human would, so it can be registered as local input.
+/// The same seed and game state always give the same input, so matches against it can be replayed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cpu {
+    player: usize,
+    difficulty: Difficulty,
+    random: Random,
+    /// Oldest first. The oldest is what the CPU reacts to.
+    observations: VecDeque<Observation>,
+    prev_input: Input,
+    /// Movement kept for a while when spacing, so the CPU doesn't jitter in place.
+    spacing: Input,
+    remaining_spacing: u32,
+}
+
+impl Cpu {
+    pub fn new(player: usize, difficulty: Difficulty, seed: u64) -> Self {
+        Self {
+            player,
+            difficulty,
+            random: Random::new(seed),
+            observations: VecDeque::new(),
+            prev_input: Input::default(),
+            spacing: Input::default(),
+            remaining_spacing: 0,
+        }
+    }
+
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.difficulty = difficulty;
+    }
+
+    /// Observes the state and returns the input for the next frame. Call once per frame.
+    pub fn update(&mut self, state: &GameState) -> Input {
+        let observation = match observe(state, self.player) {
+            Some(observation) => observation,
+            None => return Input::default(),
+        };
+
+        self.observations.push_back(observation);
+        while self.observations.len() > self.difficulty.reaction_delay as usize + 1 {
+            self.observations.pop_front();
+        }
+
+        let observation = self.observations[0];
+        let mut input = self.decide(&observation, &state.stage);
+
+        if self.random.next_bounded(100) < self.difficulty.execution_error {
+            input = self.fumble(input);
+        }
+
+        // Buttons only count on the frame they're pressed, so let go before pressing again. Shields are held.
+        if has_buttons(&self.prev_input) {
+            input = Input {
+                shield_pressed: input.shield_pressed,
+                ..release_buttons(input)
+            };
+        }
+
+        self.prev_input = input;
+        input
+    }
+
+    fn decide(&mut self, observation: &Observation, stage: &Stage) -> Input {
+        if observation.hanging {
+            return self.get_up(observation, stage);
+        }
+
+        if is_off_stage(observation.position, stage) {
+            return recover(observation, stage);
+        }
+
+        let opponent = match observation.opponent {
+            Some(opponent) => opponent,
+            None => return Input::default(),
+        };
+
+        let [x, y, _] = observation.position;
+        let dx = opponent.position[0] - x;
+        let distance = dx.abs();
+        let towards = axis(dx);
+
+        if opponent.attacking && distance <= THREAT_RANGE && !observation.in_air {
+            self.remaining_spacing = 0;
+            return Input {
+                shield_pressed: true,
+                ..Input::default()
+            };
+        }
+
+        if distance <= ATTACK_RANGE && observation.actionable {
+            self.remaining_spacing = 0;
+            return self.attack(towards, opponent.position[1] - y);
+        }
+
+        if distance > APPROACH_RANGE {
+            self.remaining_spacing = 0;
+            return Input {
+                move_x_axis: towards,
+                ..Input::default()
+            };
+        }
+
+        if self.remaining_spacing == 0 {
+            self.remaining_spacing = SPACING_FRAMES;
+            self.spacing = match self.random.next_bounded(4) {
+                0 => Input {
+                    move_x_axis: -towards,
+                    ..Input::default()
+                },
+                1 => Input::default(),
+                2 => Input {
+                    move_x_axis: towards,
+                    short_hop_pressed: true,
+                    ..Input::default()
+                },
+                _ => Input {
+                    move_x_axis: towards,
+                    ..Input::default()
+                },
+            };
+        }
+        self.remaining_spacing -= 1;
+
+        self.spacing
+    }
+
+    /// Picks an attack towards the opponent. Opponents above get anti-air.
+    fn attack(&mut self, towards: i8, height: i32) -> Input {
+        if height > ATTACK_RANGE / 2 {
+            return Input {
+                move_y_axis: i8::MAX,
+                light_atk_pressed: true,
+                ..Input::default()
+            };
+        }
+
+        match self.random.next_bounded(4) {
+            0 => Input {
+                light_atk_pressed: true,
+                ..Input::default()
+            },
+            1 => Input {
+                move_x_axis: towards,
+                light_atk_pressed: true,
+                ..Input::default()
+            },
+            2 => Input {
+                move_x_axis: towards,
+                heavy_atk_pressed: true,
+                ..Input::default()
+            },
+            _ => Input {
+                grab_pressed: true,
+                ..Input::default()
+            },
+        }
+    }
+
+    /// Climbs up from a ledge, sometimes jumping instead.
+    fn get_up(&mut self, observation: &Observation, stage: &Stage) -> Input {
+        if self.random.next_bounded(4) == 0 {
+            return Input {
+                jump_pressed: true,
+                ..Input::default()
+            };
+        }
+
+        Input {
+            move_x_axis: axis(stage_center(stage) - observation.position[0]),
+            ..Input::default()
+        }
+    }
+
+    /// Makes a mistake with the input, as a human might.
+    fn fumble(&mut self, input: Input) -> Input {
+        match self.random.next_bounded(3) {
+            0 => Input {
+                move_x_axis: input.move_x_axis.saturating_neg(),
+                ..input
+            },
+            1 => release_buttons(input),
+            _ => Input {
+                move_y_axis: [-i8::MAX, 0, i8::MAX][self.random.next_bounded(3) as usize],
+                ..input
+            },
+        }
+    }
+}
+
+/// Heads for the nearest ledge, jumping when below it and using the up special when far below.
+fn recover(observation: &Observation, stage: &Stage) -> Input {
+    let [x, y, _] = observation.position;
+    let ledge = stage
+        .ledges
+        .iter()
+        .min_by_key(|ledge| (ledge.position[0] - x).abs() + (ledge.position[1] - y).abs());
+    let (target_x, ledge_y) = match ledge {
+        Some(ledge) => (ledge.position[0], ledge.position[1]),
+        None => (stage_center(stage), y),
+    };
+
+    let mut input = Input {
+        move_x_axis: axis(target_x - x),
+        ..Input::default()
+    };
+
+    let falling = observation.velocity.1 <= 0;
+    if observation.in_air && falling && y < ledge_y - RECOVERY_SPECIAL_DEPTH {
+        input.move_y_axis = i8::MAX;
+        input.special_pressed = true;
+    } else if observation.in_air && falling && y < ledge_y {
+        input.jump_pressed = true;
+    }
+
+    input
+}
+
+fn observe(state: &GameState, player: usize) -> Option<Observation> {
+    let character = state.characters.get(player)?;
+    if character.fighter.is_eliminated() {
+        return None;
+    }
+
+    let data = &state.character_data[character.fighter.character_data_index()];
+    let [x, y, _] = character.position;
+
+    // Go after the closest opponent still in.
+    let opponent = state
+        .characters
+        .iter()
+        .enumerate()
+        .filter(|(index, c)| *index != player && !c.fighter.is_eliminated())
+        .min_by_key(|(_, c)| (c.position[0] - x).abs() + (c.position[1] - y).abs())
+        .map(|(_, c)| Opponent {
+            position: c.position,
+            attacking: !c.hit_boxes.is_empty() || !c.grab_boxes.is_empty(),
+        });
+
+    Some(Observation {
+        position: character.position,
+        velocity: character.fighter.velocity(),
+        in_air: character.fighter.in_air(),
+        actionable: character.fighter.is_actionable(data),
+        hanging: character.fighter.is_hanging(data),
+        opponent,
+    })
+}
+
+/// Whether there's no solid ground below the position.
+fn is_off_stage(position: [i32; 3], stage: &Stage) -> bool {
+    let [x, y, _] = position;
+
+    !stage
+        .solids
+        .iter()
+        .any(|solid| x >= solid.min[0] && x <= solid.max[0] && y >= solid.max[1])
+}
+
+fn stage_center(stage: &Stage) -> i32 {
+    match stage.solids.first() {
+        Some(solid) => (solid.min[0] + solid.max[0]) / 2,
+        None => 0,
+    }
+}
+
+/// Converts a distance into a fully held axis towards it. Symmetric, so it can be negated.
+fn axis(distance: i32) -> i8 {
+    match distance.signum() {
+        -1 => -i8::MAX,
+        1 => i8::MAX,
+        _ => 0,
+    }
+}
+
+fn has_buttons(input: &Input) -> bool {
+    *input != release_buttons(*input)
+}
+
+fn release_buttons(input: Input) -> Input {
+    Input {
+        move_x_axis: input.move_x_axis,
+        move_y_axis: input.move_y_axis,
+        ..Input::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighting_game::tests::settled;
+    use networking::rollback::prelude::*;
+
+    fn perfect() -> Difficulty {
+        Difficulty {
+            reaction_delay: 0,
+            execution_error: 0,
+        }
+    }
+
+    /// Runs the game with a CPU for each player, returning every input they made.
+    fn play(state: &mut GameState, cpus: &mut [Cpu], frames: usize) -> Vec<Input> {
+        let mut inputs = vec![];
+
+        for _ in 0..frames {
+            for cpu in cpus.iter_mut() {
+                let input = cpu.update(state);
+                state.add_input(cpu.player() as PlayerId, input);
+                inputs.push(input);
+            }
+            state.tick();
+        }
+
+        inputs
+    }
+
+    #[test]
+    fn cpu_is_deterministic_with_a_seed() {
+        let run = |seed: u64| {
+            let mut state = settled(-1000, 1000);
+            let mut cpus = [
+                Cpu::new(0, Difficulty::level(3), seed),
+                Cpu::new(1, Difficulty::level(7), seed + 1),
+            ];
+            let inputs = play(&mut state, &mut cpus, 600);
+
+            (state, inputs)
+        };
+
+        let (state, inputs) = run(7);
+        let (replayed_state, replayed_inputs) = run(7);
+        assert_eq!(inputs, replayed_inputs);
+        assert_eq!(true, state == replayed_state);
+        assert_eq!(
+            true,
+            state.characters.iter().any(|c| c.fighter.damage() > 0)
+        );
+
+        let (_, other_inputs) = run(8);
+        assert_eq!(false, inputs == other_inputs);
+    }
+
+    #[test]
+    fn cpu_approaches_and_attacks() {
+        let mut state = settled(-1500, 1500);
+        let mut cpu = Cpu::new(0, perfect(), 0);
+
+        let input = cpu.update(&state);
+        assert_eq!(i8::MAX, input.move_x_axis);
+
+        for _ in 0..300 {
+            let input = cpu.update(&state);
+            state.add_input(0, input);
+            state.add_input(1, Input::default());
+            state.tick();
+        }
+
+        assert_eq!(true, state.characters[1].fighter.damage() > 0);
+    }
+
+    #[test]
+    fn cpu_reacts_after_the_delay() {
+        let state = settled(-1500, 1500);
+        let mut cpu = Cpu::new(
+            0,
+            Difficulty {
+                reaction_delay: 10,
+                execution_error: 0,
+            },
+            0,
+        );
+
+        // The opponent is suddenly behind the CPU, but it keeps going the old way until it notices.
+        let mut moved = state.clone();
+        moved.characters[1].position[0] = -3000;
+
+        assert_eq!(i8::MAX, cpu.update(&state).move_x_axis);
+        for _ in 0..10 {
+            assert_eq!(i8::MAX, cpu.update(&moved).move_x_axis);
+        }
+        assert_eq!(-i8::MAX, cpu.update(&moved).move_x_axis);
+    }
+
+    #[test]
+    fn cpu_recovers_to_the_stage() {
+        let mut state = settled(0, 1500);
+        let ledge = state.stage.ledges[0];
+        state.characters[0].position = [ledge.position[0] - 1000, ledge.position[1] - 200, 0];
+        state.characters[0].prev_position = state.characters[0].position;
+        state.characters[0]
+            .fighter
+            .leave_ground(&state.character_data[0].clone());
+
+        let mut cpu = Cpu::new(0, perfect(), 0);
+        let mut reached_stage = false;
+        for _ in 0..180 {
+            let input = cpu.update(&state);
+            state.add_input(0, input);
+            state.add_input(1, Input::default());
+            state.tick();
+
+            let character = &state.characters[0];
+            reached_stage |= character.ledge.is_some() || !character.fighter.in_air();
+        }
+
+        assert_eq!(true, reached_stage);
+        assert_eq!(3, state.characters[0].fighter.remaining_lives());
+    }
+}
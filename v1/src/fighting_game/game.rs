@@ -0,0 +1,1296 @@
+use crate::fighting_game::{character_stats, motion};
+use character_stats::{Cancel, CharacterStats, DirectionalInput, Frame, StateId};
+use motion::{MotionWindows, Motions};
+
+/// The gravity applied each frame at 100% gravity, in units per frame.
+const BASE_GRAVITY: i32 = 10;
+/// How quickly characters slow down on the ground, in units per frame.
+const GROUND_FRICTION: i32 = 10;
+/// The minimum number of frames characters freeze for when a hit connects.
+const BASE_HITSTOP: u32 = 4;
+/// How long a character is stunned for when their shield breaks.
+const SHIELD_BREAK_STUN: u32 = 180;
+/// The most damage a character can take.
+const MAX_DAMAGE: u32 = 999;
+/// How long a character is invincible for after grabbing a ledge.
+const LEDGE_INVINCIBILITY: u32 = 30;
+/// How long a character must hang from a ledge before they can climb or let go.
+const LEDGE_MIN_HANG: u32 = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GameRules {
+    pub shield_regen: u32,
+    pub num_lives: u32,
+    /// How many frames an action pressed early is kept for, so it comes out as soon as the character can act.
+    pub input_buffer: u32,
+    pub motion_windows: MotionWindows,
+    /// How long a character is invincible for after losing a life.
+    pub respawn_invincibility: u32,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Direction {
+    Left,
+    Right,
+}
+
+/// The input for a character on a single frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Input {
+    /// The held horizontal direction. Positive is right.
+    pub x: i8,
+    /// The held vertical direction. Positive is up.
+    pub y: i8,
+    /// The action pressed this frame.
+    pub action: Option<character_stats::Input>,
+    /// Whether a dash was input this frame.
+    pub dash: bool,
+    pub shield_held: bool,
+    /// The motions completed this frame, for command specials.
+    pub motions: Motions,
+}
+
+/// How a character received a hit.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum HitResult {
+    Hit,
+    Blocked,
+    ShieldBroken,
+}
+
+pub type RemainingFrames = u32;
+
+/// The number of frames both characters freeze for when a hit with the given damage connects.
+pub fn hitstop(damage: u32) -> RemainingFrames {
+    BASE_HITSTOP + damage / 3
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Character {
+    rules: GameRules,
+
+    character_data_index: usize,
+    input: Input,
+    /// The last input with an action, kept until remaining_buffer runs out.
+    buffered_input: Input,
+
+    remaining_shield: u32,
+    remaining_lives: u32,
+    damage: u32,
+
+    remaining_jumps: u32,
+    short_hop: bool,
+    fast_falling: bool,
+    in_air: bool,
+    facing: Direction,
+    velocity: (i32, i32),
+
+    state_frame: u32,
+    frame_index: usize,
+    state_id: character_stats::StateId,
+    /// The characters the current move has hit, so each move only hits once.
+    hit_mask: u32,
+
+    remaining_helpless: RemainingFrames,
+    remaining_stun: RemainingFrames,
+    remaining_grounded: RemainingFrames,
+    remaining_invincibility: RemainingFrames,
+    remaining_recovery: RemainingFrames,
+    remaining_blockstun: RemainingFrames,
+    remaining_hitstop: RemainingFrames,
+    remaining_buffer: RemainingFrames,
+}
+
+impl Character {
+    /// Creates a new character standing idle, using the character data at the given index of the world.
+    pub fn new(
+        game_rules: GameRules,
+        character_data_index: usize,
+        character: &CharacterStats,
+    ) -> Self {
+        Self {
+            rules: game_rules,
+            character_data_index,
+            input: Input::default(),
+            buffered_input: Input::default(),
+
+            remaining_shield: character.stats.shield_health,
+            remaining_lives: game_rules.num_lives,
+            damage: 0,
+
+            remaining_jumps: character.stats.num_jumps,
+            short_hop: false,
+            fast_falling: false,
+            in_air: false,
+            facing: Direction::Right,
+            velocity: (0, 0),
+
+            state_frame: 0,
+            frame_index: 0,
+            state_id: character.required_states.ground.idle,
+            hit_mask: 0,
+
+            remaining_helpless: 0,
+            remaining_stun: 0,
+            remaining_grounded: 0,
+            remaining_invincibility: 0,
+            remaining_recovery: 0,
+            remaining_blockstun: 0,
+            remaining_hitstop: 0,
+            remaining_buffer: 0,
+        }
+    }
+
+    pub fn rules(&self) -> &GameRules {
+        &self.rules
+    }
+
+    /// The index of the character's data in the world.
+    pub fn character_data_index(&self) -> usize {
+        self.character_data_index
+    }
+
+    pub fn facing(&self) -> Direction {
+        self.facing
+    }
+
+    pub fn face(&mut self, direction: Direction) {
+        self.facing = direction;
+    }
+
+    /// The damage percent taken.
+    pub fn damage(&self) -> u32 {
+        self.damage
+    }
+
+    pub fn remaining_shield(&self) -> u32 {
+        self.remaining_shield
+    }
+
+    pub fn remaining_lives(&self) -> u32 {
+        self.remaining_lives
+    }
+
+    /// Whether the character has lost all of their lives.
+    pub fn is_eliminated(&self) -> bool {
+        self.remaining_lives == 0
+    }
+
+    pub fn in_air(&self) -> bool {
+        self.in_air
+    }
+
+    /// The velocity in units per frame. Positive y is up.
+    pub fn velocity(&self) -> (i32, i32) {
+        self.velocity
+    }
+
+    pub fn remaining_stun(&self) -> RemainingFrames {
+        self.remaining_stun
+    }
+
+    pub fn remaining_blockstun(&self) -> RemainingFrames {
+        self.remaining_blockstun
+    }
+
+    /// Whether the character is frozen from a hit connecting.
+    pub fn in_hitstop(&self) -> bool {
+        self.remaining_hitstop > 0
+    }
+
+    pub fn is_invincible(&self) -> bool {
+        self.remaining_invincibility > 0
+    }
+
+    pub fn is_shielding(&self, character: &CharacterStats) -> bool {
+        self.state_id == character.required_states.ground.shielding
+    }
+
+    pub fn is_grabbed(&self, character: &CharacterStats) -> bool {
+        self.state_id == character.required_states.ground.grabbed
+    }
+
+    pub fn is_hanging(&self, character: &CharacterStats) -> bool {
+        self.state_id == character.required_states.air.ledge_hang
+    }
+
+    /// Whether the character is free to act, such as standing idle or falling.
+    pub fn is_actionable(&self, character: &CharacterStats) -> bool {
+        !self.is_helpless_state(character) && self.is_neutral_state(character)
+    }
+
+    /// Whether the character is falling and able to catch a ledge. Helpless characters can still grab ledges.
+    pub fn can_grab_ledge(&self, character: &CharacterStats) -> bool {
+        self.in_air
+            && self.velocity.1 <= 0
+            && self.remaining_stun == 0
+            && self.remaining_hitstop == 0
+            && !self.is_hanging(character)
+    }
+
+    /// Whether the current move has already hit the character at the index.
+    pub fn has_hit(&self, target: usize) -> bool {
+        target < 32 && self.hit_mask & (1 << target) != 0
+    }
+
+    /// Records that the current move hit the character at the index.
+    pub fn mark_hit(&mut self, target: usize) {
+        if target < 32 {
+            self.hit_mask |= 1 << target;
+        }
+    }
+
+    /// The frame data for the current frame.
+    pub fn frame<'a>(&self, character: &'a CharacterStats) -> Option<&'a Frame> {
+        character
+            .state(self.state_id)
+            .and_then(|state| state.frames.get(self.frame_index))
+    }
+
+    /// Converts a held direction into one relative to the direction the character is facing. Vertical directions take priority.
+    pub fn relative_direction(&self, x: i8, y: i8) -> DirectionalInput {
+        if y > 0 {
+            DirectionalInput::Up
+        } else if y < 0 {
+            DirectionalInput::Down
+        } else if x == 0 {
+            DirectionalInput::None
+        } else if (x > 0) == (self.facing == Direction::Right) {
+            DirectionalInput::Forward
+        } else {
+            DirectionalInput::Back
+        }
+    }
+
+    /// Registers the input to use for the next tick. Actions are buffered until they can be performed.
+    pub fn register_input(&mut self, input: Input) {
+        if input.action.is_some() {
+            self.buffered_input = input;
+            self.remaining_buffer = self.rules.input_buffer;
+        } else {
+            self.remaining_buffer = checked_decrement(self.remaining_buffer);
+        }
+
+        self.input = input;
+    }
+
+    pub fn tick(&mut self, character: &CharacterStats) {
+        // Everything is frozen during hitstop
+        if self.remaining_hitstop > 0 {
+            self.remaining_hitstop -= 1;
+            return;
+        }
+
+        // Decrease remaining frames
+        {
+            self.remaining_helpless = checked_decrement(self.remaining_helpless);
+            self.remaining_stun = checked_decrement(self.remaining_stun);
+            self.remaining_grounded = checked_decrement(self.remaining_grounded);
+            self.remaining_invincibility = checked_decrement(self.remaining_invincibility);
+            self.remaining_recovery = checked_decrement(self.remaining_recovery);
+            self.remaining_blockstun = checked_decrement(self.remaining_blockstun);
+        }
+
+        // Try to increase shield health
+        {
+            let shield_health = character.stats.shield_health;
+
+            if self.state_id != character.required_states.ground.shielding
+                && self.remaining_shield < shield_health
+            {
+                self.remaining_shield = add_with_max(
+                    self.remaining_shield,
+                    self.rules.shield_regen,
+                    shield_health,
+                );
+            }
+        }
+
+        // Try to reset jumps
+        {
+            let max_jumps = character.stats.num_jumps;
+
+            if self.remaining_jumps < max_jumps
+                && !self.in_air
+                && !self.is_helpless_state(character)
+            {
+                self.remaining_jumps = max_jumps;
+            }
+        }
+
+        // State transitions
+        self.advance_state(character);
+
+        // Apply inputs
+        self.apply_input(character);
+
+        // Movement. Collision checks are done by the caller, which should call land() or leave_ground().
+        self.apply_movement(character);
+    }
+
+    /// Lands the character on the ground. Moves are cancelled, applying the landing lag of the current frame.
+    pub fn land(&mut self, character: &CharacterStats) {
+        if !self.in_air {
+            return;
+        }
+
+        self.in_air = false;
+        self.fast_falling = false;
+        self.velocity.1 = 0;
+        self.remaining_jumps = character.stats.num_jumps;
+
+        if self.remaining_stun > 0 {
+            return;
+        }
+
+        let landing_lag = self
+            .frame(character)
+            .map(|frame| frame.recovery)
+            .unwrap_or(0);
+        self.remaining_recovery = self.remaining_recovery.max(landing_lag);
+        self.enter_state(character, character.required_states.ground.idle);
+    }
+
+    /// Puts the character in the air, such as when walking off a ledge.
+    pub fn leave_ground(&mut self, character: &CharacterStats) {
+        if self.in_air {
+            return;
+        }
+
+        self.in_air = true;
+
+        let ground = &character.required_states.ground;
+        let state = self.state_id;
+        if state == ground.idle
+            || state == ground.crouch
+            || state == ground.dashing
+            || state == ground.shielding
+        {
+            self.enter_state(character, character.required_states.air.fall);
+        }
+    }
+
+    /// Stops upward movement, such as when hitting a ceiling.
+    pub fn hit_ceiling(&mut self) {
+        self.velocity.1 = self.velocity.1.min(0);
+    }
+
+    /// Stops horizontal movement, such as when hitting a wall.
+    pub fn hit_wall(&mut self) {
+        self.velocity.0 = 0;
+    }
+
+    /// Hangs from a ledge, facing the stage. Jumps are refreshed and the character is briefly invincible.
+    pub fn grab_ledge(&mut self, character: &CharacterStats, facing: Direction) {
+        self.facing = facing;
+        self.velocity = (0, 0);
+        self.in_air = true;
+        self.fast_falling = false;
+        self.remaining_jumps = character.stats.num_jumps;
+        self.remaining_helpless = 0;
+        self.remaining_invincibility = self.remaining_invincibility.max(LEDGE_INVINCIBILITY);
+        self.enter_state(character, character.required_states.air.ledge_hang);
+    }
+
+    /// Takes a life and resets the character to fall back in. Once all lives are lost the character is eliminated.
+    pub fn lose_life(&mut self, character: &CharacterStats) {
+        *self = Self {
+            remaining_lives: checked_decrement(self.remaining_lives),
+            facing: self.facing,
+            ..Self::new(self.rules, self.character_data_index, character)
+        };
+
+        self.respawn(character);
+    }
+
+    /// Removes the character from the match.
+    pub fn eliminate(&mut self) {
+        self.remaining_lives = 0;
+    }
+
+    /// Resets the character to fall back in with a single life at the given damage.
+    pub fn start_sudden_death(&mut self, character: &CharacterStats, damage: u32) {
+        *self = Self {
+            remaining_lives: 1,
+            damage: damage.min(MAX_DAMAGE),
+            facing: self.facing,
+            ..Self::new(self.rules, self.character_data_index, character)
+        };
+
+        self.respawn(character);
+    }
+
+    /// Makes the character fall back in, invincible for a while if they have lives left.
+    fn respawn(&mut self, character: &CharacterStats) {
+        if !self.is_eliminated() {
+            self.remaining_invincibility = self.rules.respawn_invincibility;
+        }
+
+        self.in_air = true;
+        self.enter_state(character, character.required_states.air.fall);
+    }
+
+    /// Puts the character in hitstun for the given number of frames.
+    pub fn apply_hit_stun(&mut self, character: &CharacterStats, frames: RemainingFrames) {
+        self.remaining_stun = frames;
+        self.remaining_blockstun = 0;
+        self.fast_falling = false;
+        self.enter_state(character, character.required_states.ground.stunned);
+    }
+
+    /// Puts the character in blockstun for the given number of frames. The character can't drop shield until it ends.
+    pub fn apply_block_stun(&mut self, frames: RemainingFrames) {
+        self.remaining_blockstun = frames;
+    }
+
+    /// Freezes the character for the given number of frames.
+    pub fn apply_hitstop(&mut self, frames: RemainingFrames) {
+        self.remaining_hitstop = self.remaining_hitstop.max(frames);
+    }
+
+    /// The knockback the frame would apply to this character, facing right. Grows with damage and shrinks with weight.
+    pub fn knockback(&self, character: &CharacterStats, frame: &Frame) -> (i32, i32) {
+        let scale = 100 + self.damage as i32 * frame.knockback_growth as i32 / 100;
+        let weight = character.stats.weight.max(1) as i32;
+
+        let (x, y) = frame.knockback;
+        (x * scale / weight, y * scale / weight)
+    }
+
+    /// Receives a hit from the frame of an attacker facing the given direction. Shields block hits until they break.
+    pub fn take_hit(
+        &mut self,
+        character: &CharacterStats,
+        frame: &Frame,
+        from: Direction,
+    ) -> HitResult {
+        if self.is_shielding(character) {
+            if self.remaining_shield > frame.damage {
+                self.remaining_shield -= frame.damage;
+                self.apply_block_stun(frame.block_stun);
+                return HitResult::Blocked;
+            }
+
+            self.remaining_shield = 0;
+            self.velocity = (0, 0);
+            self.apply_hit_stun(character, SHIELD_BREAK_STUN);
+            return HitResult::ShieldBroken;
+        }
+
+        self.damage = add_with_max(self.damage, frame.damage, MAX_DAMAGE);
+
+        let (x, y) = self.knockback(character, frame);
+        let sign = match from {
+            Direction::Left => -1,
+            Direction::Right => 1,
+        };
+
+        self.apply_hit_stun(character, frame.hit_stun);
+        self.velocity = (x * sign, y);
+        if y > 0 {
+            self.in_air = true;
+        }
+
+        HitResult::Hit
+    }
+
+    /// Grabs the character, holding them for the hit stun of the frame. Grabs go through shields.
+    pub fn take_grab(&mut self, character: &CharacterStats, frame: &Frame) {
+        self.remaining_stun = frame.hit_stun;
+        self.remaining_blockstun = 0;
+        self.velocity = (0, 0);
+        self.enter_state(character, character.required_states.ground.grabbed);
+    }
+
+    /// Returns whether the character is in a 'helpless' state and can't do anything
+    fn is_helpless_state(&self, character: &CharacterStats) -> bool {
+        if self.remaining_helpless > 0
+            || self.remaining_stun > 0
+            || self.remaining_grounded > 0
+            || self.remaining_recovery > 0
+            || self.remaining_blockstun > 0
+            || self.state_id == character.required_states.air.helpless
+        {
+            return true;
+        }
+
+        false
+    }
+
+    /// Returns whether the character is in a state that any action can interrupt.
+    fn is_neutral_state(&self, character: &CharacterStats) -> bool {
+        let required = &character.required_states;
+        let state = self.state_id;
+
+        state == required.ground.idle
+            || state == required.ground.crouch
+            || state == required.ground.dashing
+            || state == required.ground.shielding
+            || state == required.air.fall
+            || (state == required.jump && self.in_air)
+    }
+
+    fn facing_sign(&self) -> i32 {
+        match self.facing {
+            Direction::Left => -1,
+            Direction::Right => 1,
+        }
+    }
+
+    fn turn_around(&mut self) {
+        self.facing = match self.facing {
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        };
+    }
+
+    fn enter_state(&mut self, character: &CharacterStats, state_id: StateId) {
+        self.state_id = state_id;
+        self.state_frame = 0;
+        self.hit_mask = 0;
+        self.enter_frame(character, 0);
+    }
+
+    /// Returns to idle, or falling if in the air.
+    fn enter_neutral(&mut self, character: &CharacterStats) {
+        let required = &character.required_states;
+        let state = match self.in_air {
+            true => required.air.fall,
+            false => required.ground.idle,
+        };
+
+        self.enter_state(character, state);
+    }
+
+    fn enter_frame(&mut self, character: &CharacterStats, index: usize) {
+        self.frame_index = index;
+
+        let state = match character.state(self.state_id) {
+            Some(state) => state,
+            None => return,
+        };
+
+        if let Some(frame) = state.frames.get(index) {
+            let (x, y) = frame.velocity;
+            if x != 0 || y != 0 {
+                self.velocity = (x * self.facing_sign(), y);
+                if y > 0 {
+                    self.in_air = true;
+                }
+            }
+        }
+
+        // Leave the ground on the last frame of the jump, after the jump squat.
+        if self.state_id == character.required_states.jump && index + 1 == state.frames.len() {
+            let stats = &character.stats;
+            let velocity = match self.short_hop {
+                true => stats.short_hop_velocity,
+                false => stats.jump_velocity,
+            };
+
+            // Air jumps are used up when input, ground jumps when leaving the ground.
+            if !self.in_air {
+                self.remaining_jumps = checked_decrement(self.remaining_jumps);
+            }
+
+            self.velocity.1 = velocity as i32;
+            self.in_air = true;
+            self.fast_falling = false;
+        }
+    }
+
+    /// Moves the current state forward a frame, returning to neutral when it finishes.
+    fn advance_state(&mut self, character: &CharacterStats) {
+        let required = &character.required_states;
+
+        let held =
+            self.state_id == required.ground.stunned || self.state_id == required.ground.grabbed;
+        if held && self.remaining_stun == 0 {
+            self.enter_neutral(character);
+            return;
+        }
+
+        self.state_frame = self.state_frame.saturating_add(1);
+
+        let index = character
+            .state(self.state_id)
+            .and_then(|state| state.frame_index(self.state_frame));
+
+        match index {
+            Some(index) => {
+                if index != self.frame_index {
+                    self.enter_frame(character, index);
+                }
+            }
+            None => {
+                if self.in_air && self.state_id == required.special.up_special {
+                    self.enter_state(character, required.air.helpless);
+                } else {
+                    self.enter_neutral(character);
+                }
+            }
+        }
+    }
+
+    fn apply_input(&mut self, character: &CharacterStats) {
+        if self.is_helpless_state(character) {
+            return;
+        }
+
+        let required = &character.required_states;
+
+        if self.state_id == required.air.ledge_hang {
+            self.apply_ledge_input(character);
+            return;
+        }
+
+        // Actions pressed a little early come out once the character is free to act.
+        let mut input = self.input;
+        if input.action.is_none() && self.remaining_buffer > 0 && self.is_neutral_state(character) {
+            input.action = self.buffered_input.action;
+            input.motions = self.buffered_input.motions;
+        }
+
+        // Release held states
+        if !self.in_air {
+            let released = (self.state_id == required.ground.shielding && !input.shield_held)
+                || (self.state_id == required.ground.crouch && input.y >= 0)
+                || (self.state_id == required.ground.dashing && input.x == 0);
+
+            if released {
+                self.enter_state(character, required.ground.idle);
+            }
+        }
+
+        let neutral = self.is_neutral_state(character);
+
+        if let Some(action) = input.action {
+            if (neutral || self.can_cancel(character, action))
+                && self.perform_action(character, action, input.motions)
+            {
+                self.remaining_buffer = 0;
+                return;
+            }
+        }
+
+        if self.in_air || !neutral {
+            return;
+        }
+
+        // Movement driven ground states
+        let moving_backwards = input.x != 0 && (input.x > 0) != (self.facing == Direction::Right);
+
+        if input.shield_held {
+            if self.state_id != required.ground.shielding {
+                self.enter_state(character, required.ground.shielding);
+            }
+        } else if input.y < 0 {
+            if self.state_id != required.ground.crouch {
+                self.enter_state(character, required.ground.crouch);
+            }
+        } else if input.dash && input.x != 0 {
+            if moving_backwards {
+                self.turn_around();
+            }
+
+            if self.state_id != required.ground.dashing {
+                self.enter_state(character, required.ground.dashing);
+            }
+        } else if moving_backwards && self.state_id != required.ground.dashing {
+            self.turn_around();
+        }
+    }
+
+    /// Jumps, climbs up towards the stage or lets go of the ledge.
+    fn apply_ledge_input(&mut self, character: &CharacterStats) {
+        use character_stats::Input;
+
+        if self.state_frame < LEDGE_MIN_HANG {
+            return;
+        }
+
+        let required = &character.required_states;
+        let input = self.input;
+
+        if let Some(action) = input.action {
+            if action == Input::Jump || action == Input::ShortJump {
+                self.perform_action(character, action, input.motions);
+                return;
+            }
+        }
+
+        match self.relative_direction(input.x, input.y) {
+            DirectionalInput::Up | DirectionalInput::Forward => {
+                // The caller moves the character on top of the ledge once they're out of the air.
+                self.in_air = false;
+                self.enter_state(character, required.ground.idle);
+            }
+            DirectionalInput::Down | DirectionalInput::Back => {
+                self.enter_state(character, required.air.fall);
+            }
+            DirectionalInput::None => {}
+        }
+    }
+
+    /// Whether the current frame can be cancelled by the action.
+    fn can_cancel(&self, character: &CharacterStats, action: character_stats::Input) -> bool {
+        use character_stats::Input;
+
+        let cancel = match action {
+            Input::Jump | Input::ShortJump => Cancel::Jump,
+            Input::Shield => Cancel::Shield,
+            Input::Grab => Cancel::Grab,
+            Input::Tilt(_) | Input::Smash(_) => Cancel::Attack,
+            Input::Special(_) => Cancel::Special,
+        };
+
+        self.frame(character)
+            .map(|frame| frame.cancels.contains(&cancel))
+            .unwrap_or(false)
+    }
+
+    /// Enters the state for the action, using the motions for command specials. Returns false if the action can't be done.
+    fn perform_action(
+        &mut self,
+        character: &CharacterStats,
+        action: character_stats::Input,
+        motions: Motions,
+    ) -> bool {
+        use character_stats::Input;
+
+        let required = &character.required_states;
+        let ground = &required.ground;
+        let air = &required.air;
+        let special = &required.special;
+
+        let state = match action {
+            Input::Jump | Input::ShortJump => {
+                if self.remaining_jumps == 0 {
+                    return false;
+                }
+
+                if self.in_air {
+                    self.remaining_jumps -= 1;
+                }
+
+                self.short_hop = action == Input::ShortJump;
+                self.enter_state(character, required.jump);
+
+                // Air jumps skip the jump squat.
+                if self.in_air {
+                    if let Some(state) = character.state(required.jump) {
+                        let last = state.frames.len().saturating_sub(1);
+                        self.state_frame = state.frame_start(last);
+                        self.enter_frame(character, last);
+                    }
+                }
+
+                return true;
+            }
+            Input::Shield => match self.in_air {
+                true => air.air_dodge,
+                false => ground.shielding,
+            },
+            Input::Grab => match self.in_air {
+                true => return false,
+                false => ground.grab,
+            },
+            Input::Special(direction) => match command_special(character, motions) {
+                Some(state) => state,
+                None => match direction {
+                    DirectionalInput::Up => special.up_special,
+                    DirectionalInput::Down => special.down_special,
+                    DirectionalInput::None => special.neutral_special,
+                    DirectionalInput::Forward => special.forward_special,
+                    DirectionalInput::Back => {
+                        self.turn_around();
+                        special.forward_special
+                    }
+                },
+            },
+            Input::Tilt(direction) | Input::Smash(direction) if self.in_air => match direction {
+                DirectionalInput::Up => air.up_air,
+                DirectionalInput::Down => air.down_air,
+                DirectionalInput::None => air.neutral_air,
+                DirectionalInput::Forward => air.forward_air,
+                DirectionalInput::Back => air.back_air,
+            },
+            Input::Tilt(_) | Input::Smash(_) if self.state_id == ground.dashing => {
+                ground.dash_attack
+            }
+            Input::Tilt(direction) => match direction {
+                DirectionalInput::Up => ground.tilts.up,
+                DirectionalInput::Down => ground.tilts.down,
+                DirectionalInput::None => ground.jab,
+                DirectionalInput::Forward => ground.tilts.forward,
+                DirectionalInput::Back => {
+                    self.turn_around();
+                    ground.tilts.forward
+                }
+            },
+            Input::Smash(direction) => match direction {
+                DirectionalInput::Up => ground.smashes.up,
+                DirectionalInput::Down => ground.smashes.down,
+                DirectionalInput::None | DirectionalInput::Forward => ground.smashes.forward,
+                DirectionalInput::Back => {
+                    self.turn_around();
+                    ground.smashes.forward
+                }
+            },
+        };
+
+        self.enter_state(character, state);
+        true
+    }
+
+    fn apply_movement(&mut self, character: &CharacterStats) {
+        let stats = &character.stats;
+        let required = &character.required_states;
+        let input = self.input;
+        let actionable = !self.is_helpless_state(character);
+
+        if self.is_hanging(character) {
+            self.velocity = (0, 0);
+        } else if self.in_air {
+            // Drift
+            if actionable {
+                let target = input.x as i32 * stats.air_move_speed as i32;
+                self.velocity.0 = approach(self.velocity.0, target, stats.air_acceleration as i32);
+            }
+
+            // Fast fall once past the peak of the jump
+            if actionable && input.y < 0 && self.velocity.1 <= 0 {
+                self.fast_falling = true;
+            }
+
+            let gravity = BASE_GRAVITY * stats.gravity_percentage as i32 / 100;
+            let max_fall_speed = match self.fast_falling {
+                true => stats.fast_fall_speed,
+                false => stats.fall_speed,
+            } as i32;
+
+            self.velocity.1 = match self.fast_falling {
+                true => -max_fall_speed,
+                false => (self.velocity.1 - gravity).max(-max_fall_speed),
+            };
+        } else {
+            self.velocity.1 = 0;
+
+            if actionable && self.state_id == required.ground.idle {
+                self.velocity.0 = input.x as i32 * stats.walk_speed as i32;
+            } else if self.state_id == required.ground.dashing {
+                self.velocity.0 = self.facing_sign() * stats.run_speed as i32;
+            } else {
+                self.velocity.0 = approach(self.velocity.0, 0, GROUND_FRICTION);
+            }
+        }
+    }
+}
+
+/// The first command special whose motion was completed.
+fn command_special(character: &CharacterStats, motions: Motions) -> Option<StateId> {
+    character
+        .command_specials
+        .iter()
+        .find(|command| motions.contains(command.motion))
+        .map(|command| command.state)
+}
+
+/// Moves the value towards the target by at most the step.
+fn approach(value: i32, target: i32, step: i32) -> i32 {
+    if value < target {
+        (value + step).min(target)
+    } else {
+        (value - step).max(target)
+    }
+}
+
+fn checked_decrement(value: u32) -> u32 {
+    if let Some(value) = value.checked_sub(1) {
+        value
+    } else {
+        0
+    }
+}
+
+fn add_with_max(value: u32, addition: u32, max: u32) -> u32 {
+    let mut value = value + addition;
+    if value > max {
+        value = max;
+    }
+
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use character_stats::Input as Action;
+
+    fn example() -> CharacterStats {
+        CharacterStats::parse(include_str!("../../res/characters/example.ron")).unwrap()
+    }
+
+    fn character(data: &CharacterStats) -> Character {
+        let rules = GameRules {
+            shield_regen: 1,
+            num_lives: 3,
+            input_buffer: 5,
+            motion_windows: MotionWindows::default(),
+            respawn_invincibility: 120,
+        };
+
+        Character::new(rules, 0, data)
+    }
+
+    fn press(character: &mut Character, data: &CharacterStats, action: Action) {
+        character.register_input(Input {
+            action: Some(action),
+            ..Input::default()
+        });
+        character.tick(data);
+        character.register_input(Input::default());
+    }
+
+    #[test]
+    fn character_attack_goes_through_startup_active_recovery() {
+        let data = example();
+        let mut character = character(&data);
+        let ftilt = data.required_states.ground.tilts.forward;
+
+        press(
+            &mut character,
+            &data,
+            Action::Tilt(DirectionalInput::Forward),
+        );
+        assert_eq!(ftilt, character.state_id);
+
+        let mut active = vec![];
+        while character.state_id == ftilt {
+            active.push(!character.frame(&data).unwrap().hit_boxes.is_empty());
+            character.tick(&data);
+        }
+
+        // 5 startup, 3 active and 12 recovery frames.
+        let expected: Vec<bool> = (0..20).map(|frame| (5..8).contains(&frame)).collect();
+        assert_eq!(expected, active);
+        assert_eq!(data.required_states.ground.idle, character.state_id);
+    }
+
+    #[test]
+    fn character_attacks_only_interrupted_in_cancel_windows() {
+        let data = example();
+        let mut character = character(&data);
+        let ground = &data.required_states.ground;
+
+        // Forward tilt has no cancels.
+        press(
+            &mut character,
+            &data,
+            Action::Tilt(DirectionalInput::Forward),
+        );
+        press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+        assert_eq!(ground.tilts.forward, character.state_id);
+
+        let mut character = super::Character::new(character.rules, 0, &data);
+
+        // Jab can be cancelled into another attack while active.
+        press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+        press(&mut character, &data, Action::Tilt(DirectionalInput::Up));
+        assert_eq!(ground.jab, character.state_id);
+
+        character.tick(&data);
+        assert_eq!(false, character.frame(&data).unwrap().hit_boxes.is_empty());
+        press(&mut character, &data, Action::Tilt(DirectionalInput::Up));
+        assert_eq!(ground.tilts.up, character.state_id);
+    }
+
+    #[test]
+    fn character_jumps_and_lands_with_landing_lag() {
+        let data = example();
+        let mut character = character(&data);
+        let required = &data.required_states;
+
+        press(&mut character, &data, Action::Jump);
+        assert_eq!(required.jump, character.state_id);
+        assert_eq!(false, character.in_air());
+
+        // Jump squat
+        for _ in 0..3 {
+            character.tick(&data);
+        }
+        assert_eq!(true, character.in_air());
+        assert_eq!(true, character.velocity().1 > 0);
+
+        // Double jump skips the squat.
+        press(&mut character, &data, Action::Jump);
+        assert_eq!(required.jump, character.state_id);
+        assert_eq!(
+            data.stats.jump_velocity as i32 - BASE_GRAVITY,
+            character.velocity().1
+        );
+
+        // No more jumps.
+        let state_frame = character.state_frame;
+        press(&mut character, &data, Action::Jump);
+        assert_eq!(state_frame + 1, character.state_frame);
+
+        press(&mut character, &data, Action::Tilt(DirectionalInput::Down));
+        assert_eq!(required.air.down_air, character.state_id);
+
+        character.land(&data);
+        assert_eq!(required.ground.idle, character.state_id);
+        assert_eq!(16, character.remaining_recovery);
+
+        // Can't act during landing lag.
+        press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+        assert_eq!(required.ground.idle, character.state_id);
+
+        for _ in 0..15 {
+            character.tick(&data);
+        }
+        press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+        assert_eq!(required.ground.jab, character.state_id);
+    }
+
+    #[test]
+    fn character_up_special_in_air_ends_helpless() {
+        let data = example();
+        let mut character = character(&data);
+        let required = &data.required_states;
+
+        character.leave_ground(&data);
+        assert_eq!(required.air.fall, character.state_id);
+
+        press(&mut character, &data, Action::Special(DirectionalInput::Up));
+        assert_eq!(required.special.up_special, character.state_id);
+
+        for _ in 0..30 {
+            character.tick(&data);
+        }
+        assert_eq!(required.air.helpless, character.state_id);
+
+        press(&mut character, &data, Action::Jump);
+        assert_eq!(required.air.helpless, character.state_id);
+
+        character.land(&data);
+        assert_eq!(required.ground.idle, character.state_id);
+    }
+
+    #[test]
+    fn character_hit_stun_and_block_stun() {
+        let data = example();
+        let mut character = character(&data);
+        let ground = &data.required_states.ground;
+
+        character.apply_hit_stun(&data, 5);
+        for _ in 0..4 {
+            press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+            assert_eq!(ground.stunned, character.state_id);
+        }
+
+        // The last press was buffered, so it comes out as soon as the stun ends.
+        character.tick(&data);
+        assert_eq!(ground.jab, character.state_id);
+        while character.state_id == ground.jab {
+            character.tick(&data);
+        }
+        assert_eq!(ground.idle, character.state_id);
+
+        // Shield can't be dropped during blockstun.
+        character.register_input(Input {
+            shield_held: true,
+            ..Input::default()
+        });
+        character.tick(&data);
+        assert_eq!(ground.shielding, character.state_id);
+
+        character.apply_block_stun(3);
+        character.register_input(Input::default());
+        character.tick(&data);
+        character.tick(&data);
+        assert_eq!(ground.shielding, character.state_id);
+        character.tick(&data);
+        assert_eq!(ground.idle, character.state_id);
+    }
+
+    #[test]
+    fn character_back_inputs_turn_around() {
+        let data = example();
+        let mut character = character(&data);
+
+        assert_eq!(DirectionalInput::Back, character.relative_direction(-1, 0));
+
+        press(&mut character, &data, Action::Smash(DirectionalInput::Back));
+        assert_eq!(Direction::Left, character.facing());
+        assert_eq!(
+            data.required_states.ground.smashes.forward,
+            character.state_id
+        );
+        assert_eq!(
+            DirectionalInput::Forward,
+            character.relative_direction(-1, 0)
+        );
+    }
+
+    #[test]
+    fn character_hangs_from_ledge_until_climbing() {
+        let data = example();
+        let mut character = character(&data);
+        let required = &data.required_states;
+
+        character.leave_ground(&data);
+        press(&mut character, &data, Action::Jump);
+        assert_eq!(data.stats.num_jumps - 1, character.remaining_jumps);
+
+        character.grab_ledge(&data, Direction::Left);
+        assert_eq!(required.air.ledge_hang, character.state_id);
+        assert_eq!(data.stats.num_jumps, character.remaining_jumps);
+        assert_eq!(true, character.is_invincible());
+
+        // Held in place, and can't climb straight away.
+        let forward = Input {
+            x: -1,
+            ..Input::default()
+        };
+        character.register_input(forward);
+        character.tick(&data);
+        assert_eq!(true, character.is_hanging(&data));
+        assert_eq!((0, 0), character.velocity());
+
+        for _ in 0..LEDGE_MIN_HANG {
+            character.tick(&data);
+        }
+        assert_eq!(required.ground.idle, character.state_id);
+        assert_eq!(false, character.in_air());
+    }
+
+    #[test]
+    fn character_respawns_invincible_until_eliminated() {
+        let data = example();
+        let mut character = character(&data);
+        let frame = data
+            .state(data.required_states.ground.tilts.forward)
+            .unwrap()
+            .frames[1]
+            .clone();
+
+        character.take_hit(&data, &frame, Direction::Right);
+        character.lose_life(&data);
+        assert_eq!(2, character.remaining_lives());
+        assert_eq!(0, character.damage());
+        assert_eq!(true, character.in_air());
+        assert_eq!(true, character.is_invincible());
+
+        for _ in 0..character.rules().respawn_invincibility {
+            character.tick(&data);
+        }
+        assert_eq!(false, character.is_invincible());
+
+        character.start_sudden_death(&data, 300);
+        assert_eq!((1, 300), (character.remaining_lives(), character.damage()));
+        assert_eq!(true, character.is_invincible());
+
+        character.lose_life(&data);
+        assert_eq!(true, character.is_eliminated());
+        assert_eq!(false, character.is_invincible());
+    }
+
+    #[test]
+    fn character_buffers_actions_until_recovery_ends() {
+        let data = example();
+        let mut character = character(&data);
+        let ground = &data.required_states.ground;
+
+        press(
+            &mut character,
+            &data,
+            Action::Tilt(DirectionalInput::Forward),
+        );
+        while character.state_frame < 16 {
+            character.tick(&data);
+        }
+
+        // Pressed during recovery, comes out on the first free frame.
+        press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+        assert_eq!(ground.tilts.forward, character.state_id);
+        for _ in 0..3 {
+            character.register_input(Input::default());
+            character.tick(&data);
+        }
+        assert_eq!(ground.jab, character.state_id);
+
+        // Pressed too early, it's dropped.
+        let mut character = super::Character::new(character.rules, 0, &data);
+        press(
+            &mut character,
+            &data,
+            Action::Tilt(DirectionalInput::Forward),
+        );
+        press(&mut character, &data, Action::Tilt(DirectionalInput::None));
+        while character.state_id == ground.tilts.forward {
+            character.register_input(Input::default());
+            character.tick(&data);
+        }
+        assert_eq!(ground.idle, character.state_id);
+    }
+
+    #[test]
+    fn character_command_specials_need_motions() {
+        let data = example();
+        let mut character = character(&data);
+        let special = Action::Special(DirectionalInput::Forward);
+
+        let mut motions = Motions::default();
+        motions.insert(motion::Motion::QuarterCircleForward);
+        motions.insert(motion::Motion::DragonPunch);
+        character.register_input(Input {
+            action: Some(special),
+            motions,
+            ..Input::default()
+        });
+        character.tick(&data);
+        assert_eq!(data.command_specials[0].state, character.state_id);
+
+        let mut character = super::Character::new(character.rules, 0, &data);
+        press(&mut character, &data, special);
+        assert_eq!(
+            data.required_states.special.forward_special,
+            character.state_id
+        );
+    }
+
+    #[test]
+    fn character_is_deterministic() {
+        let data = example();
+        let run = || {
+            let mut character = character(&data);
+            let mut states = vec![];
+            for frame in 0..200 {
+                let input = Input {
+                    x: [1, 0, -1][frame % 3],
+                    y: [0, 0, 0, -1][frame % 4],
+                    action: match frame % 17 {
+                        0 => Some(Action::Jump),
+                        5 => Some(Action::Tilt(DirectionalInput::Forward)),
+                        11 => Some(Action::Special(DirectionalInput::Up)),
+                        _ => None,
+                    },
+                    dash: frame % 7 == 1,
+                    shield_held: frame % 29 < 3,
+                    motions: Motions::default(),
+                };
+
+                character.register_input(input);
+                character.tick(&data);
+                if character.in_air() && frame % 13 == 1 {
+                    character.land(&data);
+                }
+                states.push(character.clone());
+            }
+            states
+        };
+
+        assert_eq!(run(), run());
+    }
+}
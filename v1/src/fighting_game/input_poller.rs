@@ -1,106 +1,248 @@
 type GameInput = super::Input;
 
-use portia::input::Input;
+use super::bindings::{Device, DeviceState, Profile};
+use networking::rollback::PlayerId;
+use portia::input::{Gamepad, GamepadAxis, GamepadButton, GamepadId, Input, Key, PressState};
 
+#[derive(Default)]
+struct KeyboardState {
+    held: Vec<Key>,
+}
+
+impl DeviceState for KeyboardState {
+    fn key_held(&self, key: Key) -> bool {
+        self.held.contains(&key)
+    }
+
+    fn button_held(&self, _button: GamepadButton) -> bool {
+        false
+    }
+
+    fn axis(&self, _axis: GamepadAxis) -> f32 {
+        0.0
+    }
+}
+
+struct GamepadState {
+    id: GamepadId,
+    held: Vec<GamepadButton>,
+    axes: Vec<(GamepadAxis, f32)>,
+}
+
+impl DeviceState for GamepadState {
+    fn key_held(&self, _key: Key) -> bool {
+        false
+    }
+
+    fn button_held(&self, button: GamepadButton) -> bool {
+        self.held.contains(&button)
+    }
+
+    fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes
+            .iter()
+            .find(|(a, _)| *a == axis)
+            .map(|(_, value)| *value)
+            .unwrap_or(0.0)
+    }
+}
+
+/// Tracks the held state of local devices and converts them into game input using a bindings profile.
 pub struct InputPoller {
-    input: GameInput,
-    up_held: bool,
-    down_held: bool,
-    right_held: bool,
-    left_held: bool,
-    jump_held: bool,
-    frame: usize,
+    profile: Profile,
+    keyboard: KeyboardState,
+    gamepads: Vec<GamepadState>,
 }
 
 impl InputPoller {
-    pub fn new() -> Self {
+    pub fn new(profile: Profile) -> Self {
         Self {
-            up_held: false,
-            down_held: false,
-            right_held: false,
-            left_held: false,
-            jump_held: false,
-            input: GameInput::default(),
-            frame: 0,
+            profile,
+            keyboard: KeyboardState::default(),
+            gamepads: vec![],
         }
     }
 
-    pub fn poll(&mut self, inputs: &Vec<portia::input::Input>) -> GameInput {
-        self.frame = self.frame.wrapping_add(1);
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
 
-        // Simple polling for input.
-        // TODO: make configurable.
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+    }
+
+    /// Updates the held devices, then returns the input for each local player. Players using several devices get them combined.
+    pub fn poll(&mut self, inputs: &Vec<Input>) -> Vec<(PlayerId, GameInput)> {
         for input in inputs {
             match input {
-                Input::Key { key, state } => match key {
-                    portia::input::Key::Up => match state {
-                        portia::input::PressState::Released => {
-                            self.up_held = false;
-                        }
-                        portia::input::PressState::Pressed => {
-                            self.up_held = true;
-                        }
-                    },
-                    portia::input::Key::Down => match state {
-                        portia::input::PressState::Released => {
-                            self.down_held = false;
-                        }
-                        portia::input::PressState::Pressed => {
-                            self.down_held = true;
-                        }
-                    },
-                    portia::input::Key::Left => match state {
-                        portia::input::PressState::Released => {
-                            self.left_held = false;
-                        }
-                        portia::input::PressState::Pressed => {
-                            self.left_held = true;
-                        }
-                    },
-                    portia::input::Key::Right => match state {
-                        portia::input::PressState::Released => {
-                            self.right_held = false;
-                        }
-                        portia::input::PressState::Pressed => {
-                            self.right_held = true;
-                        }
-                    },
-                    portia::input::Key::Space => match state {
-                        portia::input::PressState::Released => {
-                            self.jump_held = false;
-                        }
-                        portia::input::PressState::Pressed => {
-                            self.jump_held = true;
-                        }
-                    },
-                },
+                Input::Key { key, state } => {
+                    self.keyboard.held.retain(|k| k != key);
+                    if *state == PressState::Pressed {
+                        self.keyboard.held.push(*key);
+                    }
+                }
+                Input::Gamepad { id, event } => self.update_gamepad(*id, event),
                 _ => {}
             }
         }
 
-        let axis_max = i8::MAX;
-        let axis_min = i8::MIN;
+        self.profile
+            .player_ids()
+            .into_iter()
+            .map(|player| {
+                let input = self
+                    .profile
+                    .players
+                    .iter()
+                    .filter(|p| p.player == player)
+                    .map(|p| match p.device {
+                        Device::Keyboard => p.bindings.resolve(&self.keyboard),
+                        Device::Gamepad(id) => match self.gamepads.iter().find(|g| g.id == id) {
+                            Some(gamepad) => p.bindings.resolve(gamepad),
+                            None => GameInput::default(),
+                        },
+                    })
+                    .fold(GameInput::default(), combine);
 
-        if self.up_held {
-            self.input.move_y_axis = axis_max;
-        } else if self.down_held {
-            self.input.move_y_axis = axis_min;
-        } else {
-            self.input.move_y_axis = 0;
+                (player, input)
+            })
+            .collect()
+    }
+
+    fn update_gamepad(&mut self, id: GamepadId, event: &Gamepad) {
+        if let Gamepad::Disconnected = event {
+            self.gamepads.retain(|g| g.id != id);
+            return;
         }
 
-        if self.right_held {
-            self.input.move_x_axis = axis_max;
-        } else if self.left_held {
-            self.input.move_x_axis = axis_min;
-        } else {
-            self.input.move_x_axis = 0;
+        let index = match self.gamepads.iter().position(|g| g.id == id) {
+            Some(index) => index,
+            None => {
+                self.gamepads.push(GamepadState {
+                    id,
+                    held: vec![],
+                    axes: vec![],
+                });
+                self.gamepads.len() - 1
+            }
+        };
+        let gamepad = &mut self.gamepads[index];
+
+        match event {
+            Gamepad::Button { button, state } => {
+                gamepad.held.retain(|b| b != button);
+                if *state == PressState::Pressed {
+                    gamepad.held.push(*button);
+                }
+            }
+            Gamepad::Axis { axis, value } => {
+                gamepad.axes.retain(|(a, _)| a != axis);
+                gamepad.axes.push((*axis, *value));
+            }
+            Gamepad::Connected | Gamepad::Disconnected => {}
         }
+    }
+}
 
-        if self.jump_held {
-            self.input.jump_pressed = true;
+/// Combines the input of two devices. Buttons held on either count, and the axis pushed furthest wins.
+fn combine(a: GameInput, b: GameInput) -> GameInput {
+    let furthest = |a: i8, b: i8| {
+        if (b as i16).abs() > (a as i16).abs() {
+            b
+        } else {
+            a
         }
+    };
+
+    GameInput {
+        move_x_axis: furthest(a.move_x_axis, b.move_x_axis),
+        move_y_axis: furthest(a.move_y_axis, b.move_y_axis),
+
+        jump_pressed: a.jump_pressed || b.jump_pressed,
+        short_hop_pressed: a.short_hop_pressed || b.short_hop_pressed,
+        light_atk_pressed: a.light_atk_pressed || b.light_atk_pressed,
+        heavy_atk_pressed: a.heavy_atk_pressed || b.heavy_atk_pressed,
+        special_pressed: a.special_pressed || b.special_pressed,
+        shield_pressed: a.shield_pressed || b.shield_pressed,
+        grab_pressed: a.grab_pressed || b.grab_pressed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighting_game::bindings::{Bindings, PlayerBindings};
+
+    fn key(key: Key, state: PressState) -> Input {
+        Input::Key { key, state }
+    }
+
+    #[test]
+    fn input_poller_tracks_devices_per_player() {
+        let mut poller = InputPoller::new(Profile::default());
+
+        let inputs = poller.poll(&vec![
+            key(Key::Right, PressState::Pressed),
+            key(Key::Space, PressState::Pressed),
+            Input::Gamepad {
+                id: 0,
+                event: Gamepad::Axis {
+                    axis: GamepadAxis::LeftStickX,
+                    value: -1.0,
+                },
+            },
+        ]);
+
+        assert_eq!(2, inputs.len());
+        assert_eq!((0, i8::MAX), (inputs[0].0, inputs[0].1.move_x_axis));
+        assert_eq!(true, inputs[0].1.jump_pressed);
+        assert_eq!((1, -127), (inputs[1].0, inputs[1].1.move_x_axis));
+        assert_eq!(false, inputs[1].1.jump_pressed);
+
+        // Held until released or disconnected.
+        let inputs = poller.poll(&vec![
+            key(Key::Space, PressState::Released),
+            Input::Gamepad {
+                id: 0,
+                event: Gamepad::Disconnected,
+            },
+        ]);
+        assert_eq!(i8::MAX, inputs[0].1.move_x_axis);
+        assert_eq!(false, inputs[0].1.jump_pressed);
+        assert_eq!(GameInput::default(), inputs[1].1);
+    }
+
+    #[test]
+    fn input_poller_combines_devices_for_one_player() {
+        let profile = Profile {
+            players: vec![
+                PlayerBindings {
+                    player: 0,
+                    device: Device::Keyboard,
+                    bindings: Bindings::keyboard(),
+                },
+                PlayerBindings {
+                    player: 0,
+                    device: Device::Gamepad(3),
+                    bindings: Bindings::gamepad(),
+                },
+            ],
+        };
+        let mut poller = InputPoller::new(profile);
+
+        let inputs = poller.poll(&vec![
+            key(Key::Z, PressState::Pressed),
+            Input::Gamepad {
+                id: 3,
+                event: Gamepad::Button {
+                    button: GamepadButton::DPadDown,
+                    state: PressState::Pressed,
+                },
+            },
+        ]);
 
-        self.input
+        assert_eq!(1, inputs.len());
+        assert_eq!(true, inputs[0].1.light_atk_pressed);
+        assert_eq!(i8::MIN, inputs[0].1.move_y_axis);
     }
 }
@@ -0,0 +1,554 @@
+use super::{update_boxes, GameState, Input};
+use networking::rollback::prelude::*;
+use portia::gui::{Color, RenderCommand, RenderQueue, ScreenPoint};
+use std::cmp::Reverse;
+
+const HUD_FONT: &str = "Tuffy_Bold.ttf";
+const HUD_FONT_SIZE: f32 = 32.0;
+const ANNOUNCEMENT_FONT_SIZE: f32 = 96.0;
+const FRAMES_PER_SECOND: u32 = 60;
+
+/// The rules for the flow of a match, in frames.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MatchRules {
+    /// How long characters wait before they can act.
+    pub countdown: u32,
+    /// How long the fight lasts before time runs out, if there's a limit.
+    pub time_limit: Option<u32>,
+    /// The damage characters start sudden death with.
+    pub sudden_death_damage: u32,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        Self {
+            countdown: 3 * FRAMES_PER_SECOND,
+            time_limit: Some(8 * 60 * FRAMES_PER_SECOND),
+            sudden_death_damage: 300,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MatchResults {
+    /// Missing if every remaining character was eliminated on the same frame.
+    pub winner: Option<usize>,
+    /// Character indices from first to last place.
+    pub placements: Vec<usize>,
+    /// Which players have asked for a rematch. It starts once everyone has.
+    pub rematch_votes: Vec<bool>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MatchPhase {
+    /// Characters fall in but can't act yet.
+    Countdown {
+        remaining: u32,
+    },
+    Fight {
+        remaining_time: Option<u32>,
+    },
+    /// Time ran out with characters tied for lives, so they fight on with a single life each.
+    SuddenDeath,
+    Results(MatchResults),
+}
+
+/// A match from countdown to results. Everything the flow depends on is in here, so it can be rolled back.
+#[derive(Clone, PartialEq)]
+pub struct Match {
+    pub rules: MatchRules,
+    pub phase: MatchPhase,
+    pub game: GameState,
+    /// Character indices in the order they were eliminated.
+    eliminated: Vec<usize>,
+    inputs: Vec<Input>,
+    prev_inputs: Vec<Input>,
+    /// How many rematches have been played.
+    rematches: u32,
+}
+
+impl RollbackGameState<Input> for Match {
+    fn new() -> Self {
+        Self::with_rules(MatchRules::default())
+    }
+
+    fn add_input(&mut self, player_id: PlayerId, input: Input) {
+        let player = player_id as usize;
+        if player >= self.inputs.len() {
+            return;
+        }
+        self.inputs[player] = input;
+
+        // Characters can only act while fighting.
+        let input = match self.phase {
+            MatchPhase::Fight { .. } | MatchPhase::SuddenDeath => input,
+            MatchPhase::Countdown { .. } | MatchPhase::Results(_) => Input::default(),
+        };
+        self.game.add_input(player_id, input);
+    }
+
+    fn tick(&mut self) {
+        match self.phase.clone() {
+            MatchPhase::Countdown { remaining } => {
+                self.game.tick();
+
+                self.phase = match remaining.saturating_sub(1) {
+                    0 => MatchPhase::Fight {
+                        remaining_time: self.rules.time_limit,
+                    },
+                    remaining => MatchPhase::Countdown { remaining },
+                };
+            }
+            MatchPhase::Fight { remaining_time } => {
+                self.game.tick();
+                self.record_eliminations();
+
+                let remaining_time = remaining_time.map(|time| time.saturating_sub(1));
+                if self.num_alive() <= 1 {
+                    self.finish();
+                } else if remaining_time == Some(0) {
+                    self.time_up();
+                } else {
+                    self.phase = MatchPhase::Fight { remaining_time };
+                }
+            }
+            MatchPhase::SuddenDeath => {
+                self.game.tick();
+                self.record_eliminations();
+
+                if self.num_alive() <= 1 {
+                    self.finish();
+                }
+            }
+            MatchPhase::Results(mut results) => {
+                for (player, vote) in results.rematch_votes.iter_mut().enumerate() {
+                    let input = self.inputs[player];
+                    let prev_input = self.prev_inputs[player];
+                    *vote |= input.light_atk_pressed && !prev_input.light_atk_pressed;
+                }
+
+                if results.rematch_votes.iter().all(|vote| *vote) {
+                    *self = self.rematch();
+                    return;
+                }
+
+                self.phase = MatchPhase::Results(results);
+            }
+        }
+
+        self.prev_inputs = self.inputs.clone();
+    }
+}
+
+impl Match {
+    pub fn with_rules(rules: MatchRules) -> Self {
+        let game = GameState::new();
+        let num_characters = game.characters.len();
+
+        Self {
+            rules,
+            phase: MatchPhase::Countdown {
+                remaining: rules.countdown,
+            },
+            game,
+            eliminated: vec![],
+            inputs: vec![Input::default(); num_characters],
+            prev_inputs: vec![Input::default(); num_characters],
+            rematches: 0,
+        }
+    }
+
+    pub fn rematches(&self) -> u32 {
+        self.rematches
+    }
+
+    /// Starts the same match over. Nothing carries over apart from the rules, so every player gets the same state.
+    fn rematch(&self) -> Self {
+        Self {
+            rematches: self.rematches + 1,
+            ..Self::with_rules(self.rules)
+        }
+    }
+
+    fn num_alive(&self) -> usize {
+        self.game.characters.len() - self.eliminated.len()
+    }
+
+    fn record_eliminations(&mut self) {
+        for (index, character) in self.game.characters.iter().enumerate() {
+            if character.fighter.is_eliminated() && !self.eliminated.contains(&index) {
+                self.eliminated.push(index);
+            }
+        }
+    }
+
+    /// Characters still in are ranked by remaining lives, followed by the rest in reverse order of elimination.
+    fn placements(&self) -> Vec<usize> {
+        let mut alive: Vec<usize> = (0..self.game.characters.len())
+            .filter(|index| !self.eliminated.contains(index))
+            .collect();
+        alive.sort_by_key(|index| Reverse(self.remaining_lives(*index)));
+
+        alive
+            .into_iter()
+            .chain(self.eliminated.iter().rev().copied())
+            .collect()
+    }
+
+    fn remaining_lives(&self, index: usize) -> u32 {
+        self.game.characters[index].fighter.remaining_lives()
+    }
+
+    fn finish(&mut self) {
+        let placements = self.placements();
+        let winner = match self.num_alive() {
+            0 => None,
+            _ => Some(placements[0]),
+        };
+
+        self.phase = MatchPhase::Results(MatchResults {
+            winner,
+            placements,
+            rematch_votes: vec![false; self.inputs.len()],
+        });
+    }
+
+    /// Ends the match if one character has the most lives, otherwise those tied for the most go to sudden death.
+    fn time_up(&mut self) {
+        let placements = self.placements();
+        let most_lives = self.remaining_lives(placements[0]);
+        let (leaders, others): (Vec<usize>, Vec<usize>) = placements
+            .into_iter()
+            .filter(|index| !self.eliminated.contains(index))
+            .partition(|index| self.remaining_lives(*index) == most_lives);
+
+        if leaders.len() == 1 {
+            self.finish();
+            return;
+        }
+
+        // The rest are out, with fewer lives placing lower.
+        for index in others.into_iter().rev() {
+            self.eliminate(index);
+        }
+
+        for index in leaders {
+            let data = self.game.character_data
+                [self.game.characters[index].fighter.character_data_index()]
+            .clone();
+            let stage = &self.game.stage;
+            let spawn = stage.spawn_points[index % stage.spawn_points.len()];
+            let character = &mut self.game.characters[index];
+
+            character
+                .fighter
+                .start_sudden_death(&data, self.rules.sudden_death_damage);
+            character.position = spawn;
+            character.prev_position = spawn;
+            character.ledge = None;
+            character.ledge_cooldown = 0;
+            character.platform_drop = 0;
+            update_boxes(character, &data);
+        }
+
+        self.phase = MatchPhase::SuddenDeath;
+    }
+
+    fn eliminate(&mut self, index: usize) {
+        let character = &mut self.game.characters[index];
+        let data = &self.game.character_data[character.fighter.character_data_index()];
+
+        character.fighter.eliminate();
+        update_boxes(character, data);
+        self.eliminated.push(index);
+    }
+
+    /// Draws the countdown, timer, each character's damage and lives, and the results.
+    pub fn render(&self, queue: &mut RenderQueue) {
+        let mut text = |text: String, font_size: f32, x: f32, y: f32| {
+            queue.push(RenderCommand::Text {
+                font_size,
+                position: ScreenPoint { x, y },
+                font: HUD_FONT,
+                text,
+                color: Color::white(),
+            });
+        };
+
+        match &self.phase {
+            MatchPhase::Countdown { remaining } => {
+                let seconds = remaining.div_ceil(FRAMES_PER_SECOND);
+                text(seconds.to_string(), ANNOUNCEMENT_FONT_SIZE, -0.05, 0.2);
+            }
+            MatchPhase::Fight { remaining_time } => {
+                if let Some(time) = remaining_time {
+                    let seconds = time.div_ceil(FRAMES_PER_SECOND);
+                    let clock = format!("{}:{:02}", seconds / 60, seconds % 60);
+                    text(clock, HUD_FONT_SIZE, -0.05, 0.9);
+                }
+            }
+            MatchPhase::SuddenDeath => {
+                text("Sudden death".to_string(), HUD_FONT_SIZE, -0.15, 0.9);
+            }
+            MatchPhase::Results(results) => {
+                let announcement = match results.winner {
+                    Some(winner) => format!("Player {} wins!", winner + 1),
+                    None => "Draw!".to_string(),
+                };
+                text(announcement, ANNOUNCEMENT_FONT_SIZE, -0.4, 0.4);
+
+                for (place, index) in results.placements.iter().enumerate() {
+                    let line = format!("{}. Player {}", place + 1, index + 1);
+                    let y = 0.1 - place as f32 * 0.08;
+                    text(line, HUD_FONT_SIZE, -0.2, y);
+                }
+
+                let votes = results.rematch_votes.iter().filter(|vote| **vote).count();
+                let rematch = format!(
+                    "Light attack to rematch ({}/{})",
+                    votes,
+                    results.rematch_votes.len()
+                );
+                text(rematch, HUD_FONT_SIZE, -0.3, -0.5);
+
+                if self.rematches() > 0 {
+                    let played = format!("Rematches played: {}", self.rematches());
+                    text(played, HUD_FONT_SIZE, -0.3, -0.58);
+                }
+            }
+        }
+
+        let num_characters = self.game.characters.len() as f32;
+        for (index, character) in self.game.characters.iter().enumerate() {
+            let status = match character.fighter.is_eliminated() {
+                true => format!("P{} out", index + 1),
+                false => format!(
+                    "P{} {}% x{}",
+                    index + 1,
+                    character.fighter.damage(),
+                    character.fighter.remaining_lives()
+                ),
+            };
+            let x = -0.9 + 1.8 * index as f32 / num_characters;
+            text(status, HUD_FONT_SIZE, x, -0.85);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules() -> MatchRules {
+        MatchRules {
+            countdown: 10,
+            time_limit: Some(600),
+            sudden_death_damage: 300,
+        }
+    }
+
+    fn run(game: &mut Match, frames: usize, inputs: [Input; 2]) {
+        for _ in 0..frames {
+            game.add_input(0, inputs[0]);
+            game.add_input(1, inputs[1]);
+            game.tick();
+        }
+    }
+
+    fn jump() -> Input {
+        Input {
+            jump_pressed: true,
+            ..Input::default()
+        }
+    }
+
+    fn confirm() -> Input {
+        Input {
+            light_atk_pressed: true,
+            ..Input::default()
+        }
+    }
+
+    /// Knocks the character out of the blast zone.
+    fn knock_out(game: &mut Match, index: usize) {
+        let character = &mut game.game.characters[index];
+        character.position = [0, -10000, 0];
+        character.prev_position = character.position;
+        run(game, 1, [Input::default(); 2]);
+    }
+
+    #[test]
+    fn match_counts_down_before_the_fight() {
+        let mut game = Match::with_rules(rules());
+
+        run(&mut game, 9, [jump(), Input::default()]);
+        assert_eq!(MatchPhase::Countdown { remaining: 1 }, game.phase);
+        assert_eq!(false, game.game.characters[0].input.jump_pressed);
+
+        run(&mut game, 1, [Input::default(); 2]);
+        assert_eq!(
+            MatchPhase::Fight {
+                remaining_time: Some(600)
+            },
+            game.phase
+        );
+
+        run(&mut game, 1, [jump(), Input::default()]);
+        assert_eq!(true, game.game.characters[0].input.jump_pressed);
+        assert_eq!(
+            MatchPhase::Fight {
+                remaining_time: Some(599)
+            },
+            game.phase
+        );
+    }
+
+    #[test]
+    fn match_ends_when_one_character_is_left() {
+        let mut game = Match::with_rules(rules());
+        run(&mut game, 10, [Input::default(); 2]);
+
+        knock_out(&mut game, 1);
+        let character = &game.game.characters[1].fighter;
+        assert_eq!(2, character.remaining_lives());
+        assert_eq!(true, character.is_invincible());
+
+        knock_out(&mut game, 1);
+        knock_out(&mut game, 1);
+
+        match &game.phase {
+            MatchPhase::Results(results) => {
+                assert_eq!(Some(0), results.winner);
+                assert_eq!(vec![0, 1], results.placements);
+            }
+            phase => panic!("Expected results, got {:?}", phase),
+        }
+    }
+
+    #[test]
+    fn match_time_out_with_tied_lives_goes_to_sudden_death() {
+        let mut game = Match::with_rules(rules());
+        run(&mut game, 609, [Input::default(); 2]);
+        assert_eq!(
+            MatchPhase::Fight {
+                remaining_time: Some(1)
+            },
+            game.phase
+        );
+
+        run(&mut game, 1, [Input::default(); 2]);
+        assert_eq!(MatchPhase::SuddenDeath, game.phase);
+        for character in game.game.characters.iter() {
+            assert_eq!(1, character.fighter.remaining_lives());
+            assert_eq!(300, character.fighter.damage());
+        }
+
+        knock_out(&mut game, 0);
+        match &game.phase {
+            MatchPhase::Results(results) => assert_eq!(Some(1), results.winner),
+            phase => panic!("Expected results, got {:?}", phase),
+        }
+    }
+
+    #[test]
+    fn match_time_out_with_a_leader_ends_the_match() {
+        let mut game = Match::with_rules(rules());
+        run(&mut game, 10, [Input::default(); 2]);
+        knock_out(&mut game, 0);
+
+        run(&mut game, 599, [Input::default(); 2]);
+        match &game.phase {
+            MatchPhase::Results(results) => {
+                assert_eq!(Some(1), results.winner);
+                assert_eq!(vec![1, 0], results.placements);
+            }
+            phase => panic!("Expected results, got {:?}", phase),
+        }
+    }
+
+    #[test]
+    fn match_flows_through_rollback_and_rematches() {
+        let mut game = RollbackNetcode::<Match, Input>::new("test", 0, vec![]);
+        game.add_player();
+        game.add_player();
+
+        // The netcode may hold a frame back while confirming inputs, so allow for some latency.
+        let rules = MatchRules::default();
+        let mut frames = 0;
+        while let MatchPhase::Countdown { .. } = game.state().phase {
+            game.register_local_input(0, Input::default());
+            game.register_local_input(1, Input::default());
+            game.tick();
+            frames += 1;
+        }
+        assert_eq!(
+            true,
+            frames >= rules.countdown && frames <= rules.countdown + 5
+        );
+        assert_eq!(
+            MatchPhase::Fight {
+                remaining_time: rules.time_limit
+            },
+            game.state().phase
+        );
+
+        let mut finished = game.state().clone();
+        for _ in 0..3 {
+            knock_out(&mut finished, 1);
+        }
+        let results = finished.phase.clone();
+
+        // Everyone needs to ask for a rematch, and holding the button doesn't count twice.
+        run(&mut finished, 2, [confirm(), Input::default()]);
+        assert_eq!(false, results == finished.phase);
+        run(&mut finished, 1, [confirm(), confirm()]);
+
+        let expected = Match {
+            rematches: 1,
+            ..Match::with_rules(rules)
+        };
+        assert_eq!(true, expected == finished);
+    }
+
+    #[test]
+    fn match_sync_test_agrees_across_runs() {
+        let play = || {
+            let mut game = RollbackNetcode::<Match, Input>::new(
+                "test",
+                2,
+                vec![RollbackNetcodeModes::SyncTest],
+            );
+            game.add_player();
+            game.add_player();
+
+            for frame in 0..900 {
+                for player in 0..2 {
+                    let input = Input {
+                        move_x_axis: [1, 0, -1, 1][(frame / 7 + player) % 4],
+                        move_y_axis: [0, 0, -1][(frame / 11) % 3],
+                        jump_pressed: (frame + player * 5) % 37 == 1,
+                        light_atk_pressed: (frame + player) % 9 < 2,
+                        heavy_atk_pressed: (frame + player * 3) % 23 == 1,
+                        special_pressed: frame % 41 == 1,
+                        shield_pressed: (frame + player) % 31 < 4,
+                        grab_pressed: frame % 19 == player,
+                        ..Input::default()
+                    };
+                    game.register_local_input(player as PlayerId, input);
+                }
+
+                // Every frame is simulated twice by the sync test, which reports a desync if they differ.
+                assert_eq!(Vec::<RollbackEvent>::new(), game.tick());
+            }
+
+            game.state().clone()
+        };
+
+        let state = play();
+        assert_eq!(true, state == play());
+        assert_eq!(
+            true,
+            state.game.characters.iter().any(|c| c.fighter.damage() > 0)
+        );
+    }
+}
@@ -2,18 +2,24 @@ use core::num;
 
 use networking::rollback::prelude::*;
 
+pub mod bindings;
+pub mod character_loader;
+pub mod character_stats;
+pub mod cpu;
+pub mod game;
 pub mod input_poller;
+pub mod match_flow;
+pub mod motion;
+pub mod stage;
+pub mod training;
 
+use character_stats::CharacterStats;
 use game_math::f32::*;
+use portia_client_server::session::MultiplayerSession;
+use stage::{Stage, Support};
+use std::sync::Arc;
 
-#[derive(Copy, Clone, PartialEq, Debug)]
-pub enum CharacterState {
-    Idle,
-    Walking,
-    Dashing,
-    Falling,
-}
-pub type RollbackGame = RollbackNetcode<GameState, Input>;
+pub type Session = Box<dyn MultiplayerSession<match_flow::Match, Input> + Send>;
 
 #[derive(Copy, Clone, Default, Debug, PartialEq)]
 pub struct Input {
@@ -24,40 +30,73 @@ pub struct Input {
     pub short_hop_pressed: bool,
     pub light_atk_pressed: bool,
     pub heavy_atk_pressed: bool,
+    pub special_pressed: bool,
     pub shield_pressed: bool,
     pub grab_pressed: bool,
 }
 
 impl GameInput for Input {}
 
-#[derive(Copy, Clone, PartialEq)]
+/// A box in character data units.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Aabb {
-    pub min: [f32; 2],
-    pub max: [f32; 2],
+    pub min: [i32; 2],
+    pub max: [i32; 2],
 }
 
-const INPUT_BUFFER_SIZE: usize = 30;
-const MIN_DASH_INPUT_BUFFER: usize = 3;
-const MAX_DASH_INPUT_BUFFER: usize = 10;
+impl Aabb {
+    /// Returns the box moved by the position.
+    pub fn offset(&self, position: [i32; 3]) -> Self {
+        Self {
+            min: [self.min[0] + position[0], self.min[1] + position[1]],
+            max: [self.max[0] + position[0], self.max[1] + position[1]],
+        }
+    }
+
+    /// Whether the boxes overlap. Boxes that only touch don't overlap.
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.min[0] < other.max[0]
+            && self.max[0] > other.min[0]
+            && self.min[1] < other.max[1]
+            && self.max[1] > other.min[1]
+    }
+}
+
+/// Long enough to hold a full charge and its release.
+const INPUT_BUFFER_SIZE: usize = 60;
+/// How long platforms are ignored for after dropping through one.
+const PLATFORM_DROP_FRAMES: u32 = 10;
+/// How long after letting go of a ledge before it can be grabbed again.
+const LEDGE_REGRAB_FRAMES: u32 = 30;
+
+/// The render-side scale, in character data units per world unit. Only `to_world` uses it, as the simulation never leaves character data units.
+const UNITS_PER_METER: f32 = 1000.0;
+
+/// Converts from character data units to world units. Only for rendering, as floats aren't deterministic across machines.
+pub fn to_world(units: [i32; 3]) -> [f32; 3] {
+    [
+        units[0] as f32 / UNITS_PER_METER,
+        units[1] as f32 / UNITS_PER_METER,
+        units[2] as f32 / UNITS_PER_METER,
+    ]
+}
+
+/// The character used until character selection exists.
+const DEFAULT_CHARACTER: &str = include_str!("../../res/characters/example.ron");
 
 #[derive(Clone, PartialEq)]
 pub struct Character {
     input: Input,
     prev_inputs: [Input; INPUT_BUFFER_SIZE],
-    pub prev_position: [f32; 3],
-    pub position: [f32; 3],
-    pub walk_speed: f32,
-    pub dash_speed: f32,
-    pub air_speed: f32,
-    pub gravity_speed: f32,
-
-    pub jumps: u8,
-    pub max_jumps: u8,
-    pub jump_frames: u8,
-    pub max_jump_frames: u8,
-    pub jump_speed: f32,
+    pub prev_position: [i32; 3],
+    pub position: [i32; 3],
 
-    pub state: CharacterState,
+    /// The frame data driven state machine.
+    pub fighter: game::Character,
+    /// The index of the stage ledge the character is hanging from.
+    pub ledge: Option<usize>,
+    ledge_cooldown: u32,
+    platform_drop: u32,
 
     pub push_boxes: Vec<Aabb>,
     pub hit_boxes: Vec<Aabb>,
@@ -67,72 +106,65 @@ pub struct Character {
 
 #[derive(Clone, PartialEq)]
 pub struct GameState {
+    pub character_data: Vec<Arc<CharacterStats>>,
     pub characters: Vec<Character>,
-    pub stage_aabbs: Vec<Aabb>,
-    pub stage_position: [f32; 3],
+    pub stage: Stage,
 }
 impl RollbackGameState<Input> for GameState {
     fn new() -> Self {
-        let mut state = Self {
-            stage_position: [0.0, -1.0, 0.0],
-            stage_aabbs: vec![Aabb {
-                min: [-2.0, -0.25],
-                max: [2.0, 0.25],
-            }],
-            characters: vec![
-                Character {
-                    state: CharacterState::Idle,
-                    prev_inputs: [Input::default(); INPUT_BUFFER_SIZE],
-                    input: Input::default(),
-                    position: [0.5, 3.0, 0.0],
-                    prev_position: [0.5, 3.0, 0.0],
-
-                    walk_speed: 0.05,
-                    dash_speed: 0.1,
-                    air_speed: 0.2,
-                    gravity_speed: 0.01,
-
-                    jumps: 0,
-                    max_jumps: 2,
-                    jump_frames: 0,
-                    max_jump_frames: 8,
-                    jump_speed: 0.1,
-
-                    push_boxes: vec![Aabb {
-                        min: [-0.5, -0.5],
-                        max: [0.5, 0.5],
-                    }],
-                    hit_boxes: vec![Aabb {
-                        min: [-0.3, -0.35],
-                        max: [0.3, 0.3],
-                    }],
-                    hurt_boxes: vec![],
-                    grab_boxes: vec![],
-                },
-                /*
-                Character {
-                    state: CharacterState::Idle,
-                    prev_inputs: [Input::default(); INPUT_BUFFER_SIZE],
-                    input: Input::default(),
-                    position: [-0.5, 0.0, 0.0],
-                    walk_speed: 0.05,
-                    dash_speed: 0.1,
-                    push_boxes: vec![Aabb {
-                        min: [-0.5, -0.5],
-                        max: [0.5, 0.5],
-                    }],
-                    hit_boxes: vec![],
-                    hurt_boxes: vec![],
-                    grab_boxes: vec![],
-                },*/
-            ],
+        let character_data = CharacterStats::parse(DEFAULT_CHARACTER)
+            .expect("The default character should always be valid!");
+
+        let rules = game::GameRules {
+            shield_regen: 1,
+            num_lives: 3,
+            input_buffer: 5,
+            motion_windows: motion::MotionWindows::default(),
+            respawn_invincibility: 120,
         };
 
-        state
+        let stage = Stage::default();
+
+        let spawn = |position: [i32; 3], facing: game::Direction| {
+            let mut fighter = game::Character::new(rules, 0, &character_data);
+            fighter.face(facing);
+            fighter.leave_ground(&character_data);
+
+            Character {
+                prev_inputs: [Input::default(); INPUT_BUFFER_SIZE],
+                input: Input::default(),
+                position,
+                prev_position: position,
+
+                fighter,
+                ledge: None,
+                ledge_cooldown: 0,
+                platform_drop: 0,
+
+                push_boxes: vec![],
+                hit_boxes: vec![],
+                hurt_boxes: vec![],
+                grab_boxes: vec![],
+            }
+        };
+
+        let characters = vec![
+            spawn(stage.spawn_points[0], game::Direction::Right),
+            spawn(stage.spawn_points[1], game::Direction::Left),
+        ];
+
+        Self {
+            character_data: vec![Arc::new(character_data)],
+            characters,
+            stage,
+        }
     }
 
     fn add_input(&mut self, player_id: PlayerId, input: Input) {
-        let mut character = &mut self.characters[player_id as usize];
+        let character = match self.characters.get_mut(player_id as usize) {
+            Some(character) => character,
+            None => return,
+        };
         character.input = input;
 
         // Handle input buffers
@@ -148,82 +180,36 @@ impl RollbackGameState<Input> for GameState {
     }
 
     fn tick(&mut self) {
-        for character in self.characters.iter_mut() {
-            character.prev_position = character.position;
+        for (index, character) in self.characters.iter_mut().enumerate() {
+            let data = &self.character_data[character.fighter.character_data_index()];
 
-            // Handle state transitions
-            let input = map_input_to_logical_input(character.input);
-            character.state = {
-                if input.is_moving() == false {
-                    CharacterState::Idle
-                } else {
-                    if is_jumpable(&character) {
-                        // If last input was pressed and second to last input was not pressed, jump if possible
-                        if character.input.jump_pressed
-                            && !character.prev_inputs[INPUT_BUFFER_SIZE - 2].jump_pressed
-                        {
-                            character.jumps += 1;
-                            character.jump_frames = character.max_jump_frames;
-                        }
-                    }
+            if character.fighter.is_eliminated() {
+                continue;
+            }
 
-                    match character.state {
-                        CharacterState::Idle => CharacterState::Walking,
-                        CharacterState::Walking => {
-                            let mut should_dash = false;
-                            let mut empty_frames = 0;
-
-                            for prev_input in character
-                                .prev_inputs
-                                .iter()
-                                .rev()
-                                .take(MAX_DASH_INPUT_BUFFER)
-                            {
-                                let logical_input = map_input_to_logical_input(*prev_input);
-                                if !logical_input.left_held && !logical_input.right_held {
-                                    empty_frames += 1;
-                                } else {
-                                    if empty_frames > MIN_DASH_INPUT_BUFFER {
-                                        should_dash = true;
-                                        break;
-                                    }
-                                }
-                            }
-
-                            match should_dash {
-                                true => CharacterState::Dashing,
-                                false => CharacterState::Walking,
-                            }
-                        }
-                        CharacterState::Dashing => CharacterState::Dashing,
-                        CharacterState::Falling => CharacterState::Falling,
-                    }
-                }
-            };
+            character.prev_position = character.position;
+            character.ledge_cooldown = character.ledge_cooldown.saturating_sub(1);
+            character.platform_drop = character.platform_drop.saturating_sub(1);
 
-            if character.state == CharacterState::Falling {
-                character.position[1] -= character.gravity_speed;
-            }
+            drop_through_platform(character, data, &self.stage);
+
+            // Handle state transitions
+            let frozen = character.fighter.in_hitstop();
+            let input = map_input_to_fighter_input(character);
+            character.fighter.register_input(input);
+            character.fighter.tick(data);
 
             // Do movement
-            match character.state {
-                CharacterState::Idle => {}
-                CharacterState::Walking => {
-                    move_character(character, character.walk_speed, input);
-                }
-                CharacterState::Dashing => {
-                    move_character(character, character.dash_speed, input);
-                }
-                CharacterState::Falling => {
-                    move_character(character, character.air_speed, input);
-                }
+            if !frozen {
+                let (x, y) = character.fighter.velocity();
+                character.position[0] += x;
+                character.position[1] += y;
             }
 
-            // Jump movement
-            if character.jump_frames > 0 {
-                character.jump_frames -= 1;
-                character.position[1] -= character.jump_speed;
-            }
+            follow_ledge(character, data, &self.stage);
+            land_on_stage(character, data, &self.stage);
+            check_blast_zone(character, index, data, &self.stage);
+            update_boxes(character, data);
         }
 
         // Do collision checks and the ilk
@@ -231,60 +217,736 @@ impl RollbackGameState<Input> for GameState {
     }
 }
 
-fn is_jumpable(character: &Character) -> bool {
-    let can_jump = character.jumps < character.max_jumps;
-    let valid_jump_state = character.state == CharacterState::Falling
-        || character.state == CharacterState::Idle
-        || character.state == CharacterState::Walking
-        || character.state == CharacterState::Dashing;
+impl GameState {
+    /// Replaces the character data at the index, such as when its file is hot reloaded. Characters keep their current state.
+    pub fn set_character_data(&mut self, index: usize, data: CharacterStats) {
+        if let Some(character_data) = self.character_data.get_mut(index) {
+            *character_data = Arc::new(data);
+        }
+    }
+}
+
+/// Converts the raw input into actions for the fighter. Buttons only trigger actions on the frame they're pressed.
+fn map_input_to_fighter_input(character: &Character) -> game::Input {
+    use character_stats::Input as Action;
+
+    let input = character.input;
+    let prev_input = character.prev_inputs[INPUT_BUFFER_SIZE - 2];
+
+    let x = input.move_x_axis.signum();
+    let y = input.move_y_axis.signum();
+    let direction = character.fighter.relative_direction(x, y);
+    let motions = motion::parse(
+        &character.prev_inputs,
+        character.fighter.facing(),
+        &character.fighter.rules().motion_windows,
+    );
+
+    let action = {
+        if input.jump_pressed && !prev_input.jump_pressed {
+            Some(Action::Jump)
+        } else if input.short_hop_pressed && !prev_input.short_hop_pressed {
+            Some(Action::ShortJump)
+        } else if input.special_pressed && !prev_input.special_pressed {
+            Some(Action::Special(direction))
+        } else if input.heavy_atk_pressed && !prev_input.heavy_atk_pressed {
+            Some(Action::Smash(direction))
+        } else if input.light_atk_pressed && !prev_input.light_atk_pressed {
+            Some(Action::Tilt(direction))
+        } else if input.grab_pressed && !prev_input.grab_pressed {
+            Some(Action::Grab)
+        } else if input.shield_pressed && !prev_input.shield_pressed {
+            Some(Action::Shield)
+        } else {
+            None
+        }
+    };
+
+    game::Input {
+        x,
+        y,
+        action,
+        dash: motions.contains(motion::Motion::DoubleTapForward)
+            || motions.contains(motion::Motion::DoubleTapBack),
+        shield_held: input.shield_pressed,
+        motions,
+    }
+}
 
-    can_jump && valid_jump_state
+/// Drops the character through the platform they're standing on when down is pressed.
+fn drop_through_platform(character: &mut Character, data: &CharacterStats, stage: &Stage) {
+    let pressed_down = character.input.move_y_axis < 0
+        && character.prev_inputs[INPUT_BUFFER_SIZE - 2].move_y_axis >= 0;
+
+    if pressed_down
+        && !character.fighter.in_air()
+        && character.fighter.is_actionable(data)
+        && stage.support(character.position) == Some(Support::Platform)
+    {
+        character.platform_drop = PLATFORM_DROP_FRAMES;
+        character.fighter.leave_ground(data);
+    }
 }
 
-fn move_character(character: &mut Character, speed: f32, input: LogicalInput) {
-    if input.up_held {
-        character.position[1] += speed;
-    } else if input.down_held {
-        character.position[1] -= speed;
+/// Keeps a hanging character on their ledge, and lets go of it once they climb up, jump or drop.
+fn follow_ledge(character: &mut Character, data: &CharacterStats, stage: &Stage) {
+    let ledge = match character.ledge {
+        Some(index) => stage.ledges[index],
+        None => return,
+    };
+
+    if character.fighter.is_hanging(data) {
+        character.position = ledge.hang_position();
+        return;
     }
 
-    if input.right_held {
-        character.position[0] += speed;
-    } else if input.left_held {
-        character.position[0] -= speed;
+    if !character.fighter.in_air() {
+        character.position = ledge.climb_position();
     }
+
+    character.ledge = None;
+    character.ledge_cooldown = LEDGE_REGRAB_FRAMES;
 }
 
-struct LogicalInput {
-    up_held: bool,
-    down_held: bool,
-    left_held: bool,
-    right_held: bool,
+/// Lands the character on ground or platforms they fell onto, or makes them fall if they've walked off.
+fn land_on_stage(character: &mut Character, data: &CharacterStats, stage: &Stage) {
+    if character.ledge.is_some() {
+        return;
+    }
+
+    if !character.fighter.in_air() {
+        if stage.support(character.position).is_none() {
+            character.fighter.leave_ground(data);
+        }
+
+        return;
+    }
+
+    if character.fighter.velocity().1 > 0 {
+        return;
+    }
+
+    let include_platforms = character.platform_drop == 0;
+    if let Some(height) = stage.landing(
+        character.prev_position,
+        character.position,
+        include_platforms,
+    ) {
+        character.position[1] = height;
+        character.fighter.land(data);
+    }
 }
 
-impl LogicalInput {
-    fn is_moving(&self) -> bool {
-        self.up_held || self.down_held || self.left_held || self.right_held
+/// Takes a life from a character that left the blast zone, respawning them if they have any left.
+fn check_blast_zone(character: &mut Character, index: usize, data: &CharacterStats, stage: &Stage) {
+    if !stage.is_out_of_bounds(character.position) {
+        return;
+    }
+
+    character.fighter.lose_life(data);
+    character.ledge = None;
+
+    if !character.fighter.is_eliminated() {
+        let spawn = stage.spawn_points[index % stage.spawn_points.len()];
+        character.position = spawn;
+        character.prev_position = spawn;
     }
 }
 
-fn map_input_to_logical_input(input: Input) -> LogicalInput {
-    let up_held = input.move_y_axis > 0;
-    let down_held = input.move_y_axis < 0;
+/// Sets the character's boxes from the current frame data.
+fn update_boxes(character: &mut Character, data: &CharacterStats) {
+    let frame = match character.fighter.is_eliminated() {
+        true => None,
+        false => character.fighter.frame(data),
+    };
+    let mirror = match character.fighter.facing() {
+        game::Direction::Left => -1,
+        game::Direction::Right => 1,
+    };
+
+    let to_aabb = |aabb: &character_stats::Aabb| {
+        let x = aabb.x * mirror;
+        let y = aabb.y;
+        let half_width = (aabb.width / 2) as i32;
+        let half_height = (aabb.height / 2) as i32;
+
+        Aabb {
+            min: [x - half_width, y - half_height],
+            max: [x + half_width, y + half_height],
+        }
+    };
 
-    let right_held = input.move_x_axis > 0;
-    let left_held = input.move_x_axis < 0;
+    let convert = |aabbs: &Vec<character_stats::Aabb>| aabbs.iter().map(to_aabb).collect();
 
-    LogicalInput {
-        up_held,
-        down_held,
-        right_held,
-        left_held,
+    match frame {
+        Some(frame) => {
+            character.push_boxes = frame.push_box.iter().map(to_aabb).collect();
+            character.hit_boxes = convert(&frame.hit_boxes);
+            character.hurt_boxes = convert(&frame.hurt_boxes);
+            character.grab_boxes = convert(&frame.grab_boxes);
+        }
+        None => {
+            character.push_boxes.clear();
+            character.hit_boxes.clear();
+            character.hurt_boxes.clear();
+            character.grab_boxes.clear();
+        }
     }
 }
 
 fn resolve_collisions(state: &mut GameState) {
+    separate_characters(state);
+    separate_from_stage(state);
+    grab_ledges(state);
+    resolve_hits(state);
+}
+
+/// Pushes apart characters whose push boxes overlap, splitting the distance between them.
+fn separate_characters(state: &mut GameState) {
+    let count = state.characters.len();
+
+    for i in 0..count {
+        for j in (i + 1)..count {
+            let (left, right) = state.characters.split_at_mut(j);
+            let a = &mut left[i];
+            let b = &mut right[0];
+
+            for a_box in a.push_boxes.iter() {
+                for b_box in b.push_boxes.iter() {
+                    let a_box = a_box.offset(a.position);
+                    let b_box = b_box.offset(b.position);
+
+                    if !a_box.overlaps(&b_box) {
+                        continue;
+                    }
+
+                    let overlap = a_box.max[0].min(b_box.max[0]) - a_box.min[0].max(b_box.min[0]);
+                    let push = overlap / 2;
+                    // Odd overlaps can't be split evenly, so the remainder goes to the second character.
+                    let other_push = overlap - push;
+
+                    // Ties go to the lower index being on the left, so the result doesn't depend on anything else.
+                    if a.position[0] <= b.position[0] {
+                        a.position[0] -= push;
+                        b.position[0] += other_push;
+                    } else {
+                        a.position[0] += push;
+                        b.position[0] -= other_push;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Pushes characters out of the walls and ceilings of the stage. Landing on top is handled separately.
+fn separate_from_stage(state: &mut GameState) {
     for character in state.characters.iter_mut() {
-        for push_box in &character.push_boxes {}
+        for push_box in character.push_boxes.iter() {
+            let push_box = push_box.offset(character.position);
+
+            if let Some([x, y]) = state.stage.push_out(&push_box) {
+                character.position[0] += x;
+                character.position[1] += y;
+
+                if x != 0 {
+                    character.fighter.hit_wall();
+                }
+                if y < 0 {
+                    character.fighter.hit_ceiling();
+                }
+            }
+        }
+    }
+}
+
+/// Catches falling characters on ledges in reach that no one else is hanging from.
+fn grab_ledges(state: &mut GameState) {
+    for index in 0..state.characters.len() {
+        let character = &state.characters[index];
+        let data = state.character_data[character.fighter.character_data_index()].clone();
+
+        if character.ledge.is_some()
+            || character.ledge_cooldown > 0
+            || !character.fighter.can_grab_ledge(&data)
+        {
+            continue;
+        }
+
+        let ledge = match state.stage.ledge_in_reach(character.position) {
+            Some(ledge) => ledge,
+            None => continue,
+        };
+
+        if state.characters.iter().any(|c| c.ledge == Some(ledge)) {
+            continue;
+        }
+
+        let hang = state.stage.ledges[ledge];
+        let character = &mut state.characters[index];
+        character.fighter.grab_ledge(&data, hang.facing);
+        character.ledge = Some(ledge);
+        character.position = hang.hang_position();
+        update_boxes(character, &data);
+    }
+}
+
+fn any_overlap(a: &[Aabb], a_position: [i32; 3], b: &[Aabb], b_position: [i32; 3]) -> bool {
+    a.iter().any(|a| {
+        let a = a.offset(a_position);
+        b.iter().any(|b| a.overlaps(&b.offset(b_position)))
+    })
+}
+
+/// Removes hits that lose a clash. When two characters hit each other, only the higher priority hit lands, while equal priorities trade.
+fn filter_clashes<F>(hits: &[(usize, usize)], priority: F) -> Vec<(usize, usize)>
+where
+    F: Fn(usize) -> u32,
+{
+    hits.iter()
+        .filter(|(attacker, target)| {
+            !hits.contains(&(*target, *attacker)) || priority(*attacker) >= priority(*target)
+        })
+        .copied()
+        .collect()
+}
+
+/// Checks hit boxes and grab boxes against hurt boxes. Each move hits a character at most once, and hits beat grabs.
+fn resolve_hits(state: &mut GameState) {
+    let count = state.characters.len();
+    let mut hits = vec![];
+    let mut grabs = vec![];
+
+    for (attacker_index, attacker) in state.characters.iter().enumerate() {
+        for (target_index, target) in state.characters.iter().enumerate() {
+            if attacker_index == target_index
+                || attacker.fighter.has_hit(target_index)
+                || target.fighter.is_invincible()
+            {
+                continue;
+            }
+
+            let target_data = &state.character_data[target.fighter.character_data_index()];
+
+            if any_overlap(
+                &attacker.hit_boxes,
+                attacker.position,
+                &target.hurt_boxes,
+                target.position,
+            ) {
+                hits.push((attacker_index, target_index));
+            } else if !target.fighter.in_air()
+                && !target.fighter.is_grabbed(target_data)
+                && any_overlap(
+                    &attacker.grab_boxes,
+                    attacker.position,
+                    &target.hurt_boxes,
+                    target.position,
+                )
+            {
+                grabs.push((attacker_index, target_index));
+            }
+        }
+    }
+
+    let frame = |state: &GameState, index: usize| {
+        let fighter = &state.characters[index].fighter;
+        fighter
+            .frame(&state.character_data[fighter.character_data_index()])
+            .cloned()
+            .unwrap_or_default()
+    };
+
+    let priorities: Vec<u32> = (0..count).map(|i| frame(state, i).priority).collect();
+    let hits = filter_clashes(&hits, |i| priorities[i]);
+    let frames: Vec<character_stats::Frame> = (0..count).map(|i| frame(state, i)).collect();
+
+    for (attacker, target) in hits.iter().copied() {
+        let frame = &frames[attacker];
+        let from = state.characters[attacker].fighter.facing();
+        let target_data =
+            state.character_data[state.characters[target].fighter.character_data_index()].clone();
+
+        state.characters[target]
+            .fighter
+            .take_hit(&target_data, frame, from);
+        state.characters[attacker].fighter.mark_hit(target);
+
+        let hitstop = game::hitstop(frame.damage);
+        state.characters[attacker].fighter.apply_hitstop(hitstop);
+        state.characters[target].fighter.apply_hitstop(hitstop);
+    }
+
+    for (grabber, target) in grabs.iter().copied() {
+        let interrupted = hits
+            .iter()
+            .any(|(_, hit)| *hit == grabber || *hit == target);
+        let target_data =
+            state.character_data[state.characters[target].fighter.character_data_index()].clone();
+
+        if interrupted || state.characters[target].fighter.is_grabbed(&target_data) {
+            continue;
+        }
+
+        state.characters[target]
+            .fighter
+            .take_grab(&target_data, &frames[grabber]);
+        state.characters[grabber].fighter.mark_hit(target);
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Whether the fighter is in the state, going by the frame data it's using.
+    fn in_state(
+        fighter: &game::Character,
+        data: &CharacterStats,
+        state_id: character_stats::StateId,
+    ) -> bool {
+        let state = data.state(state_id).unwrap();
+        fighter.frame(data).map_or(false, |frame| {
+            state.frames.iter().any(|f| std::ptr::eq(f, frame))
+        })
+    }
+
+    fn run(state: &mut GameState, frames: usize, input: Input) {
+        for _ in 0..frames {
+            state.add_input(0, input);
+            state.tick();
+        }
+    }
+
+    #[test]
+    fn game_state_character_lands_and_jumps() {
+        let mut state = GameState::new();
+        let required = state.character_data[0].required_states.clone();
+
+        run(&mut state, 120, Input::default());
+
+        let character = &state.characters[0];
+        assert_eq!(false, character.fighter.in_air());
+        let data = &state.character_data[0];
+        let idle = required.ground.idle;
+        assert_eq!(true, in_state(&character.fighter, data, idle));
+        assert_eq!(-750, character.position[1]);
+        assert_eq!(false, character.hurt_boxes.is_empty());
+
+        let jump = Input {
+            jump_pressed: true,
+            ..Input::default()
+        };
+        run(&mut state, 10, jump);
+
+        let character = &state.characters[0];
+        assert_eq!(true, character.fighter.in_air());
+        assert_eq!(true, character.position[1] > -750);
+    }
+
+    /// Lets the characters land, then places them on the stage at the given x positions.
+    pub(crate) fn settled(x0: i32, x1: i32) -> GameState {
+        let mut state = GameState::new();
+        run(&mut state, 120, Input::default());
+
+        for (character, x) in state.characters.iter_mut().zip([x0, x1].iter()) {
+            character.position[0] = *x;
+            character.prev_position = character.position;
+        }
+
+        state
+    }
+
+    fn run_both(state: &mut GameState, frames: usize, inputs: [Input; 2]) {
+        for _ in 0..frames {
+            state.add_input(0, inputs[0]);
+            state.add_input(1, inputs[1]);
+            state.tick();
+        }
+    }
+
+    fn forward_tilt() -> Input {
+        Input {
+            move_x_axis: 1,
+            light_atk_pressed: true,
+            ..Input::default()
+        }
+    }
+
+    #[test]
+    fn game_state_hit_applies_damage_knockback_and_hitstop() {
+        let mut state = settled(0, 600);
+        let data = state.character_data[0].clone();
+
+        run_both(&mut state, 7, [forward_tilt(), Input::default()]);
+
+        let target = &state.characters[1].fighter;
+        assert_eq!(9, target.damage());
+        let stunned = data.required_states.ground.stunned;
+        assert_eq!(true, in_state(target, &data, stunned));
+        assert_eq!(true, target.in_hitstop());
+        assert_eq!(true, state.characters[0].fighter.in_hitstop());
+        assert_eq!(true, state.characters[0].fighter.has_hit(1));
+
+        // Frozen during hitstop
+        let position = state.characters[1].position;
+        run_both(&mut state, 3, [Input::default(), Input::default()]);
+        assert_eq!(position, state.characters[1].position);
+
+        // Only hits once, and knocks away from the attacker
+        run_both(&mut state, 60, [Input::default(), Input::default()]);
+        assert_eq!(9, state.characters[1].fighter.damage());
+        assert_eq!(true, state.characters[1].position[0] > 600);
+    }
+
+    #[test]
+    fn game_state_knockback_grows_with_damage_and_shrinks_with_weight() {
+        let state = settled(0, 600);
+        let mut data = (*state.character_data[0]).clone();
+        let frame = data
+            .state(data.required_states.ground.tilts.forward)
+            .unwrap()
+            .frames[1]
+            .clone();
+        let mut fighter = state.characters[1].fighter.clone();
+
+        let light = fighter.knockback(&data, &frame);
+        fighter.take_hit(&data, &frame, game::Direction::Right);
+        fighter.take_hit(&data, &frame, game::Direction::Right);
+        let damaged = fighter.knockback(&data, &frame);
+        data.stats.weight = 200;
+        let heavy = fighter.knockback(&data, &frame);
+
+        assert_eq!(true, damaged.0 > light.0);
+        assert_eq!(damaged.0 / 2, heavy.0);
+    }
+
+    #[test]
+    fn game_state_equal_priority_hits_trade() {
+        let mut state = settled(-300, 300);
+        let jab = Input {
+            light_atk_pressed: true,
+            ..Input::default()
+        };
+
+        run_both(&mut state, 4, [jab, jab]);
+
+        assert_eq!(3, state.characters[0].fighter.damage());
+        assert_eq!(3, state.characters[1].fighter.damage());
+    }
+
+    #[test]
+    fn game_state_higher_priority_wins_clash() {
+        let hits = vec![(0, 1), (1, 0), (2, 0)];
+
+        assert_eq!(hits, filter_clashes(&hits, |_| 1));
+        assert_eq!(
+            vec![(0, 1), (2, 0)],
+            filter_clashes(&hits, |i| if i == 0 { 2 } else { 1 })
+        );
+    }
+
+    #[test]
+    fn game_state_shield_blocks_hits_but_not_grabs() {
+        let shield = Input {
+            shield_pressed: true,
+            ..Input::default()
+        };
+
+        let mut state = settled(0, 600);
+        run_both(&mut state, 7, [forward_tilt(), shield]);
+
+        let target = &state.characters[1].fighter;
+        let data = state.character_data[0].clone();
+        assert_eq!(0, target.damage());
+        assert_eq!(data.stats.shield_health - 9, target.remaining_shield());
+        assert_eq!(true, target.remaining_blockstun() > 0);
+        assert_eq!(true, target.is_shielding(&data));
+
+        let grab = Input {
+            grab_pressed: true,
+            ..Input::default()
+        };
+
+        let mut state = settled(0, 600);
+        run_both(&mut state, 9, [grab, shield]);
+
+        assert_eq!(true, state.characters[1].fighter.is_grabbed(&data));
+    }
+
+    #[test]
+    fn game_state_push_boxes_separate_characters() {
+        let mut state = settled(0, 100);
+        run_both(&mut state, 1, [Input::default(), Input::default()]);
+
+        let distance = state.characters[1].position[0] - state.characters[0].position[0];
+        assert_eq!(true, distance >= 500);
+    }
+
+    #[test]
+    fn game_state_fights_are_reproducible() {
+        let play = || {
+            let mut state = settled(-300, 400);
+            for frame in 0..300 {
+                let input = |player: usize| Input {
+                    move_x_axis: [1, 0, -1, 1][(frame / 7 + player) % 4],
+                    move_y_axis: [0, 0, -1][(frame / 11) % 3],
+                    jump_pressed: (frame + player * 5) % 37 == 1,
+                    light_atk_pressed: (frame + player) % 9 < 2,
+                    heavy_atk_pressed: (frame + player * 3) % 23 == 1,
+                    special_pressed: frame % 41 == 1,
+                    shield_pressed: (frame + player) % 31 < 4,
+                    grab_pressed: frame % 19 == player,
+                    ..Input::default()
+                };
+
+                run_both(&mut state, 1, [input(0), input(1)]);
+            }
+
+            state
+        };
+
+        let state = play();
+        assert_eq!(true, state == play());
+        assert_eq!(
+            true,
+            state.characters.iter().any(|c| c.fighter.damage() > 0)
+        );
+    }
+
+    #[test]
+    fn game_state_drops_through_platforms() {
+        let mut state = GameState::new();
+        state.characters[0].position = [-1000, 1000, 0];
+        state.characters[0].prev_position = state.characters[0].position;
+
+        run(&mut state, 60, Input::default());
+        assert_eq!(0, state.characters[0].position[1]);
+        assert_eq!(false, state.characters[0].fighter.in_air());
+
+        let down = Input {
+            move_y_axis: -1,
+            ..Input::default()
+        };
+        run(&mut state, 60, down);
+        assert_eq!(-750, state.characters[0].position[1]);
+        assert_eq!(false, state.characters[0].fighter.in_air());
+
+        // Jumping back up lands on the platform again.
+        let jump = Input {
+            jump_pressed: true,
+            ..Input::default()
+        };
+        run(&mut state, 5, jump);
+        run(&mut state, 80, Input::default());
+        assert_eq!(0, state.characters[0].position[1]);
+    }
+
+    #[test]
+    fn game_state_grabs_and_climbs_ledges() {
+        let mut state = GameState::new();
+        let data = state.character_data[0].clone();
+        let ledge = state.stage.ledges[0];
+        state.characters[0].position = [-2300, 0, 0];
+        state.characters[0].prev_position = state.characters[0].position;
+
+        run(&mut state, 30, Input::default());
+
+        let character = &state.characters[0];
+        assert_eq!(Some(0), character.ledge);
+        assert_eq!(true, character.fighter.is_hanging(&data));
+        assert_eq!(true, character.fighter.is_invincible());
+        assert_eq!(ledge.hang_position(), character.position);
+
+        // Only one character can hang from a ledge at a time.
+        state.characters[1].position = [-2300, 0, 0];
+        state.characters[1].prev_position = state.characters[1].position;
+        run_both(&mut state, 20, [Input::default(), Input::default()]);
+        assert_eq!(None, state.characters[1].ledge);
+
+        let forward = Input {
+            move_x_axis: 1,
+            ..Input::default()
+        };
+        run(&mut state, 1, forward);
+
+        let character = &state.characters[0];
+        assert_eq!(None, character.ledge);
+        assert_eq!(false, character.fighter.in_air());
+        assert_eq!(ledge.climb_position(), character.position);
+    }
+
+    #[test]
+    fn game_state_blast_zone_costs_a_life() {
+        let mut state = GameState::new();
+        let spawn = state.stage.spawn_points[0];
+
+        for lives in (0..3).rev() {
+            state.characters[0].position = [0, -3990, 0];
+            state.characters[0].fighter.take_hit(
+                &state.character_data[0].clone(),
+                &character_stats::Frame {
+                    damage: 10,
+                    ..character_stats::Frame::default()
+                },
+                game::Direction::Right,
+            );
+            run(&mut state, 30, Input::default());
+
+            let character = &state.characters[0];
+            assert_eq!(lives, character.fighter.remaining_lives());
+            assert_eq!(0, character.fighter.damage());
+            if lives > 0 {
+                assert_eq!(spawn[0], character.position[0]);
+            }
+        }
+
+        let character = &state.characters[0];
+        assert_eq!(true, character.fighter.is_eliminated());
+        assert_eq!(true, character.hurt_boxes.is_empty());
+    }
+
+    #[test]
+    fn game_state_quarter_circle_performs_command_special() {
+        let mut state = settled(0, 1000);
+        let fireball = state.character_data[0].command_specials[1].state;
+
+        for (x, y) in [(0, -1), (1, -1), (1, 0)].iter() {
+            let input = Input {
+                move_x_axis: *x,
+                move_y_axis: *y,
+                ..Input::default()
+            };
+            run(&mut state, 2, input);
+        }
+
+        let special = Input {
+            move_x_axis: 1,
+            special_pressed: true,
+            ..Input::default()
+        };
+        run(&mut state, 1, special);
+
+        let data = &state.character_data[0];
+        assert_eq!(true, in_state(&state.characters[0].fighter, data, fireball));
+    }
+
+    #[test]
+    fn game_state_attack_sets_hit_boxes() {
+        let mut state = GameState::new();
+        run(&mut state, 120, Input::default());
+
+        let attack = Input {
+            light_atk_pressed: true,
+            ..Input::default()
+        };
+        run(&mut state, 3, attack);
+
+        let character = &state.characters[0];
+        let data = &state.character_data[0];
+        let jab = data.required_states.ground.jab;
+        assert_eq!(true, in_state(&character.fighter, data, jab));
+        assert_eq!(1, character.hit_boxes.len());
+        assert_eq!(true, character.hit_boxes[0].min[0] > 0);
     }
 }
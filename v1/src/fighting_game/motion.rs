@@ -0,0 +1,281 @@
+use super::{game::Direction, Input};
+use serde::{Deserialize, Serialize};
+
+/// A directional motion input. Directions are relative to the direction the character is facing.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Motion {
+    /// Down, down-forward, forward.
+    QuarterCircleForward,
+    /// Down, down-back, back.
+    QuarterCircleBack,
+    /// Forward, down, down-forward.
+    DragonPunch,
+    /// Back, down, down-back.
+    ReverseDragonPunch,
+    /// Back held to charge, then forward.
+    ChargeBackForward,
+    /// Down held to charge, then up.
+    ChargeDownUp,
+    DoubleTapForward,
+    DoubleTapBack,
+}
+
+/// How lenient motion inputs are, in frames.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MotionWindows {
+    /// How long a motion can take, ending on the current frame.
+    pub motion: usize,
+    /// How long a direction must be held to charge.
+    pub charge: usize,
+    /// How long the direction can be released for between the taps of a double tap.
+    pub double_tap: usize,
+}
+
+impl Default for MotionWindows {
+    fn default() -> Self {
+        Self {
+            motion: 12,
+            charge: 30,
+            double_tap: 8,
+        }
+    }
+}
+
+/// A set of motions completed on a frame.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Motions(u16);
+
+impl Motions {
+    pub fn contains(&self, motion: Motion) -> bool {
+        self.0 & (1 << motion as u16) != 0
+    }
+
+    pub fn insert(&mut self, motion: Motion) {
+        self.0 |= 1 << motion as u16;
+    }
+}
+
+/// Converts the input to numpad notation relative to the facing direction. 6 is forward, 4 is back, 8 is up, 2 is down and 5 is neutral.
+pub fn numpad(input: &Input, facing: Direction) -> u8 {
+    let x = match facing {
+        Direction::Left => -input.move_x_axis.signum(),
+        Direction::Right => input.move_x_axis.signum(),
+    };
+    let y = input.move_y_axis.signum();
+
+    (5 + x + 3 * y) as u8
+}
+
+/// Finds the motions completed on the last frame of the history, which is ordered oldest to newest.
+pub fn parse(history: &[Input], facing: Direction, windows: &MotionWindows) -> Motions {
+    let directions: Vec<u8> = history.iter().map(|i| numpad(i, facing)).collect();
+    let mut motions = Motions::default();
+
+    let sequences = [
+        (Motion::QuarterCircleForward, [2, 3, 6]),
+        (Motion::QuarterCircleBack, [2, 1, 4]),
+        (Motion::DragonPunch, [6, 2, 3]),
+        (Motion::ReverseDragonPunch, [4, 2, 1]),
+    ];
+
+    for (motion, sequence) in sequences.iter() {
+        if sequence_in_window(&directions, sequence, windows.motion) {
+            motions.insert(*motion);
+        }
+    }
+
+    let back = [1, 4, 7];
+    let forward = [3, 6, 9];
+    let down = [1, 2, 3];
+    let up = [7, 8, 9];
+
+    if charge_released(&directions, &back, &forward, windows) {
+        motions.insert(Motion::ChargeBackForward);
+    }
+    if charge_released(&directions, &down, &up, windows) {
+        motions.insert(Motion::ChargeDownUp);
+    }
+
+    let horizontal: Vec<i8> = directions.iter().map(|d| ((d - 1) % 3) as i8 - 1).collect();
+    if double_tapped(&horizontal, 1, windows.double_tap) {
+        motions.insert(Motion::DoubleTapForward);
+    }
+    if double_tapped(&horizontal, -1, windows.double_tap) {
+        motions.insert(Motion::DoubleTapBack);
+    }
+
+    motions
+}
+
+/// Whether the sequence was input in order within the last frames. Other directions may come in between.
+fn sequence_in_window(directions: &[u8], sequence: &[u8], window: usize) -> bool {
+    let start = directions.len().saturating_sub(window);
+    let mut remaining = sequence.iter().peekable();
+
+    for direction in directions[start..].iter() {
+        if remaining.peek() == Some(&direction) {
+            remaining.next();
+        }
+    }
+
+    remaining.peek().is_none()
+}
+
+/// Whether a charge direction was held long enough, then released into the other direction within the motion window.
+fn charge_released(
+    directions: &[u8],
+    charge: &[u8],
+    release: &[u8],
+    windows: &MotionWindows,
+) -> bool {
+    let start = directions.len().saturating_sub(windows.motion);
+
+    let released = match directions[start..]
+        .iter()
+        .rposition(|d| release.contains(d))
+    {
+        Some(index) => start + index,
+        None => return false,
+    };
+
+    // Allow passing through other directions on the way to the release.
+    let charge_end = match directions[..released]
+        .iter()
+        .rposition(|d| charge.contains(d))
+    {
+        Some(index) if index >= start => index,
+        _ => return false,
+    };
+
+    let held = directions[..=charge_end]
+        .iter()
+        .rev()
+        .take_while(|d| charge.contains(d))
+        .count();
+
+    held >= windows.charge
+}
+
+/// Whether the horizontal direction was pressed this frame after being tapped and released within the window.
+fn double_tapped(horizontal: &[i8], direction: i8, window: usize) -> bool {
+    let len = horizontal.len();
+    if len < 3 || horizontal[len - 1] != direction || horizontal[len - 2] == direction {
+        return false;
+    }
+
+    let released = horizontal[..len - 1]
+        .iter()
+        .rev()
+        .take_while(|x| **x == 0)
+        .count();
+
+    released > 0
+        && released <= window
+        && len - 1 > released
+        && horizontal[len - 2 - released] == direction
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a history from numpad directions, facing right.
+    fn history(directions: &[u8]) -> Vec<Input> {
+        directions
+            .iter()
+            .map(|d| Input {
+                move_x_axis: ((d - 1) % 3) as i8 - 1,
+                move_y_axis: ((d - 1) / 3) as i8 - 1,
+                ..Input::default()
+            })
+            .collect()
+    }
+
+    fn parse_right(directions: &[u8]) -> Motions {
+        parse(
+            &history(directions),
+            Direction::Right,
+            &MotionWindows::default(),
+        )
+    }
+
+    #[test]
+    fn motion_numpad_mirrors_with_facing() {
+        let input = history(&[3])[0];
+
+        assert_eq!(3, numpad(&input, Direction::Right));
+        assert_eq!(1, numpad(&input, Direction::Left));
+        assert_eq!(5, numpad(&Input::default(), Direction::Left));
+    }
+
+    #[test]
+    fn motion_quarter_circles_and_dragon_punches() {
+        let qcf = parse_right(&[5, 5, 2, 2, 3, 6, 6]);
+        assert_eq!(true, qcf.contains(Motion::QuarterCircleForward));
+        assert_eq!(false, qcf.contains(Motion::QuarterCircleBack));
+        assert_eq!(false, qcf.contains(Motion::DragonPunch));
+
+        // Facing left, the same stick motion is a quarter circle back.
+        let mirrored = parse(
+            &history(&[5, 5, 2, 2, 3, 6, 6]),
+            Direction::Left,
+            &MotionWindows::default(),
+        );
+        assert_eq!(true, mirrored.contains(Motion::QuarterCircleBack));
+
+        // The down-forward shortcut for a dragon punch.
+        let dp = parse_right(&[6, 3, 2, 3, 3]);
+        assert_eq!(true, dp.contains(Motion::DragonPunch));
+
+        // Too slow for the window.
+        let mut slow = vec![2; 2];
+        slow.extend(vec![3; 12]);
+        slow.push(6);
+        assert_eq!(
+            false,
+            parse_right(&slow).contains(Motion::QuarterCircleForward)
+        );
+    }
+
+    #[test]
+    fn motion_charges_need_to_be_held() {
+        let mut charged = vec![4; 30];
+        charged.extend([5, 6].iter());
+        assert_eq!(
+            true,
+            parse_right(&charged).contains(Motion::ChargeBackForward)
+        );
+
+        let mut short = vec![4; 20];
+        short.extend([5, 6].iter());
+        assert_eq!(
+            false,
+            parse_right(&short).contains(Motion::ChargeBackForward)
+        );
+
+        let mut down_back = vec![1; 30];
+        down_back.push(9);
+        let motions = parse_right(&down_back);
+        assert_eq!(true, motions.contains(Motion::ChargeBackForward));
+        assert_eq!(true, motions.contains(Motion::ChargeDownUp));
+    }
+
+    #[test]
+    fn motion_double_taps() {
+        assert_eq!(
+            true,
+            parse_right(&[5, 6, 6, 5, 5, 6]).contains(Motion::DoubleTapForward)
+        );
+        assert_eq!(
+            true,
+            parse_right(&[5, 4, 5, 4]).contains(Motion::DoubleTapBack)
+        );
+
+        // Holding doesn't repeat, and taps too far apart don't count.
+        assert_eq!(Motions::default(), parse_right(&[5, 6, 5, 6, 6]));
+        assert_eq!(
+            Motions::default(),
+            parse_right(&[6, 5, 5, 5, 5, 5, 5, 5, 5, 5, 6])
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use super::{game::Direction, Aabb};
+
+/// How far from a ledge horizontally a character can grab it from.
+const LEDGE_GRAB_RANGE: i32 = 400;
+/// How far below a ledge a character's feet can be and still grab it.
+const LEDGE_GRAB_HEIGHT: i32 = 1200;
+/// How far from the ledge a hanging character is, horizontally and vertically.
+const LEDGE_HANG_OFFSET: [i32; 2] = [250, 900];
+/// How far onto the stage a character is placed after climbing up.
+const LEDGE_CLIMB_OFFSET: i32 = 300;
+/// How thick platforms are drawn.
+const PLATFORM_THICKNESS: i32 = 50;
+
+/// A platform that can be jumped through from below and dropped through with down.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Platform {
+    pub left: i32,
+    pub right: i32,
+    pub height: i32,
+}
+
+impl Platform {
+    /// A thin box for drawing the platform.
+    pub fn aabb(&self) -> Aabb {
+        Aabb {
+            min: [self.left, self.height - PLATFORM_THICKNESS],
+            max: [self.right, self.height],
+        }
+    }
+}
+
+/// A corner of the stage that characters can hang from.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Ledge {
+    pub position: [i32; 2],
+    /// The direction a character hanging from the ledge faces, which is towards the stage.
+    pub facing: Direction,
+}
+
+impl Ledge {
+    fn towards_stage(&self) -> i32 {
+        match self.facing {
+            Direction::Left => -1,
+            Direction::Right => 1,
+        }
+    }
+
+    /// Where a character hanging from the ledge is.
+    pub fn hang_position(&self) -> [i32; 3] {
+        [
+            self.position[0] - self.towards_stage() * LEDGE_HANG_OFFSET[0],
+            self.position[1] - LEDGE_HANG_OFFSET[1],
+            0,
+        ]
+    }
+
+    /// Where a character climbing up from the ledge ends up.
+    pub fn climb_position(&self) -> [i32; 3] {
+        [
+            self.position[0] + self.towards_stage() * LEDGE_CLIMB_OFFSET,
+            self.position[1],
+            0,
+        ]
+    }
+
+    /// Whether a character at the position can grab the ledge.
+    pub fn in_reach(&self, position: [i32; 3]) -> bool {
+        let [x, y, _] = position;
+
+        (x - self.position[0]).abs() <= LEDGE_GRAB_RANGE
+            && y <= self.position[1]
+            && y >= self.position[1] - LEDGE_GRAB_HEIGHT
+    }
+}
+
+/// What a character is standing on.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Support {
+    Solid,
+    Platform,
+}
+
+/// The stage geometry, in character data units. Positions are of the character's feet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stage {
+    /// Solid ground that characters can't pass through from any side.
+    pub solids: Vec<Aabb>,
+    pub platforms: Vec<Platform>,
+    pub ledges: Vec<Ledge>,
+    /// Leaving this area costs a stock.
+    pub blast_zone: Aabb,
+    /// Where characters start and respawn.
+    pub spawn_points: Vec<[i32; 3]>,
+}
+
+impl Default for Stage {
+    /// Creates a stage with a main floor and three platforms above it.
+    fn default() -> Self {
+        let floor = Aabb {
+            min: [-2000, -1250],
+            max: [2000, -750],
+        };
+
+        Self {
+            solids: vec![floor],
+            platforms: vec![
+                Platform {
+                    left: -1500,
+                    right: -600,
+                    height: 0,
+                },
+                Platform {
+                    left: 600,
+                    right: 1500,
+                    height: 0,
+                },
+                Platform {
+                    left: -450,
+                    right: 450,
+                    height: 750,
+                },
+            ],
+            ledges: vec![
+                Ledge {
+                    position: [floor.min[0], floor.max[1]],
+                    facing: Direction::Right,
+                },
+                Ledge {
+                    position: [floor.max[0], floor.max[1]],
+                    facing: Direction::Left,
+                },
+            ],
+            blast_zone: Aabb {
+                min: [-5000, -4000],
+                max: [5000, 5000],
+            },
+            spawn_points: vec![[-500, 3000, 0], [500, 3000, 0]],
+        }
+    }
+}
+
+impl Stage {
+    pub fn is_out_of_bounds(&self, position: [i32; 3]) -> bool {
+        let [x, y, _] = position;
+        let zone = &self.blast_zone;
+
+        x < zone.min[0] || x > zone.max[0] || y < zone.min[1] || y > zone.max[1]
+    }
+
+    /// Returns the height of the highest surface the feet passed through moving down from the previous position.
+    pub fn landing(
+        &self,
+        prev_position: [i32; 3],
+        position: [i32; 3],
+        include_platforms: bool,
+    ) -> Option<i32> {
+        let [x, y, _] = position;
+        let prev_y = prev_position[1];
+        let crossed = |left: i32, right: i32, height: i32| {
+            x >= left && x <= right && prev_y >= height && y <= height
+        };
+
+        let solids = self
+            .solids
+            .iter()
+            .filter(|solid| crossed(solid.min[0], solid.max[0], solid.max[1]))
+            .map(|solid| solid.max[1]);
+
+        let platforms = self
+            .platforms
+            .iter()
+            .filter(|_| include_platforms)
+            .filter(|p| crossed(p.left, p.right, p.height))
+            .map(|p| p.height);
+
+        solids
+            .chain(platforms)
+            .fold(None, |highest, height| match highest {
+                Some(highest) if highest >= height => Some(highest),
+                _ => Some(height),
+            })
+    }
+
+    /// Returns what the feet at the position are standing on, preferring solid ground.
+    pub fn support(&self, position: [i32; 3]) -> Option<Support> {
+        let [x, y, _] = position;
+        let on = |left: i32, right: i32, height: i32| x >= left && x <= right && y == height;
+
+        if self
+            .solids
+            .iter()
+            .any(|solid| on(solid.min[0], solid.max[0], solid.max[1]))
+        {
+            Some(Support::Solid)
+        } else if self.platforms.iter().any(|p| on(p.left, p.right, p.height)) {
+            Some(Support::Platform)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the first ledge in reach of the position.
+    pub fn ledge_in_reach(&self, position: [i32; 3]) -> Option<usize> {
+        self.ledges
+            .iter()
+            .position(|ledge| ledge.in_reach(position))
+    }
+
+    /// Returns how far the box must move to get out of the sides or bottom of solid ground, if it overlaps any.
+    /// Tops are left to landing, so characters aren't pushed up through the floor.
+    pub fn push_out(&self, push_box: &Aabb) -> Option<[i32; 2]> {
+        let mut correction = None;
+
+        for solid in self.solids.iter() {
+            if !push_box.overlaps(solid) {
+                continue;
+            }
+
+            let left = push_box.max[0] - solid.min[0];
+            let right = solid.max[0] - push_box.min[0];
+            let down = push_box.max[1] - solid.min[1];
+            let up = solid.max[1] - push_box.min[1];
+
+            let min = left.min(right).min(down);
+            if up <= min {
+                continue;
+            }
+
+            correction = if min == left {
+                Some([-left, 0])
+            } else if min == right {
+                Some([right, 0])
+            } else {
+                Some([0, -down])
+            };
+        }
+
+        correction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_landing_finds_highest_surface() {
+        let stage = Stage::default();
+
+        // Through the side platform and onto it.
+        assert_eq!(
+            Some(0),
+            stage.landing([-1000, 100, 0], [-1000, -100, 0], true)
+        );
+
+        // Platforms are skipped when dropping through, but the floor still catches.
+        assert_eq!(
+            None,
+            stage.landing([-1000, 100, 0], [-1000, -100, 0], false)
+        );
+        assert_eq!(
+            Some(-750),
+            stage.landing([-1000, 100, 0], [-1000, -800, 0], false)
+        );
+
+        // Moving up through a platform doesn't land.
+        assert_eq!(None, stage.landing([-1000, -100, 0], [-1000, 100, 0], true));
+
+        // Off the side of the stage.
+        assert_eq!(None, stage.landing([3000, 100, 0], [3000, -1000, 0], true));
+    }
+
+    #[test]
+    fn stage_support_prefers_solids() {
+        let stage = Stage::default();
+
+        assert_eq!(Some(Support::Solid), stage.support([0, -750, 0]));
+        assert_eq!(Some(Support::Platform), stage.support([1000, 0, 0]));
+        assert_eq!(None, stage.support([1000, 1, 0]));
+        assert_eq!(None, stage.support([2100, -750, 0]));
+    }
+
+    #[test]
+    fn stage_push_out_handles_walls_and_ceilings() {
+        let stage = Stage::default();
+        let aabb = |min: [i32; 2], max: [i32; 2]| Aabb { min, max };
+
+        // Wall on the left side.
+        assert_eq!(
+            Some([-250, 0]),
+            stage.push_out(&aabb([-2500, -1200], [-1750, -800]))
+        );
+
+        // Ceiling from below.
+        assert_eq!(
+            Some([0, -250]),
+            stage.push_out(&aabb([-250, -2000], [250, -1000]))
+        );
+
+        // Standing on top doesn't push.
+        assert_eq!(None, stage.push_out(&aabb([-250, -750], [250, 250])));
+        assert_eq!(None, stage.push_out(&aabb([-250, -800], [250, 200])));
+    }
+
+    #[test]
+    fn stage_ledges_in_reach() {
+        let stage = Stage::default();
+        let ledge = stage.ledges[0];
+
+        assert_eq!(Some(0), stage.ledge_in_reach([-2200, -1200, 0]));
+        assert_eq!(None, stage.ledge_in_reach([-2200, -500, 0]));
+        assert_eq!(Some(1), stage.ledge_in_reach([2200, -1200, 0]));
+        assert_eq!(None, stage.ledge_in_reach([0, -1200, 0]));
+
+        assert_eq!([-2250, -1650, 0], ledge.hang_position());
+        assert_eq!([-1700, -750, 0], ledge.climb_position());
+        assert_eq!(true, stage.is_out_of_bounds([0, -4500, 0]));
+        assert_eq!(false, stage.is_out_of_bounds([0, 0, 0]));
+    }
+}
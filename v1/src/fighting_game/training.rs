@@ -0,0 +1,624 @@
+use super::{character_stats::CharacterStats, to_world, Aabb, Character, GameState, Input};
+use networking::rollback::prelude::*;
+use portia::{
+    gui::{Color, RenderCommand, RenderQueue, ScreenPoint},
+    input::{Input as DeviceInput, Key, PressState},
+};
+
+/// The character controlled by the player.
+const PLAYER: usize = 0;
+/// The character controlled by the dummy.
+const DUMMY: usize = 1;
+/// How many updates each simulated frame takes, from full speed to the slowest slow motion.
+const SLOW_MOTION_RATES: [u32; 4] = [1, 2, 4, 8];
+/// How long to wait for both characters to recover before giving up on measuring frame advantage.
+const MAX_ADVANTAGE_FRAMES: u32 = 600;
+
+const HUD_FONT: &str = "Tuffy_Bold.ttf";
+const HUD_FONT_SIZE: f32 = 32.0;
+const HUD_POSITION: ScreenPoint = ScreenPoint { x: -0.95, y: 0.9 };
+const HUD_LINE_HEIGHT: f32 = 0.06;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum TrainingCommand {
+    SaveState,
+    LoadState,
+    TogglePause,
+    /// Advances a single frame while paused.
+    StepFrame,
+    /// Cycles through the slow motion rates.
+    SlowMotion,
+    /// Gives the player control of the dummy and records the inputs.
+    RecordDummy,
+    /// Loops the recorded inputs on the dummy.
+    PlayDummy,
+    StopDummy,
+    ToggleBoxes,
+}
+
+impl TrainingCommand {
+    /// The hotkey for each command.
+    pub fn from_key(key: Key) -> Option<Self> {
+        match key {
+            Key::F1 => Some(Self::SaveState),
+            Key::F2 => Some(Self::LoadState),
+            Key::F3 => Some(Self::TogglePause),
+            Key::F4 => Some(Self::StepFrame),
+            Key::F5 => Some(Self::SlowMotion),
+            Key::F6 => Some(Self::RecordDummy),
+            Key::F7 => Some(Self::PlayDummy),
+            Key::F8 => Some(Self::StopDummy),
+            Key::F9 => Some(Self::ToggleBoxes),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum DummyMode {
+    Idle,
+    Recording,
+    Playback,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Dummy {
+    mode: DummyMode,
+    recording: Vec<Input>,
+    playback_frame: usize,
+}
+
+impl Dummy {
+    /// Returns the inputs for the player and dummy characters.
+    fn inputs(&mut self, input: Input) -> (Input, Input) {
+        match self.mode {
+            DummyMode::Idle => (input, Input::default()),
+            DummyMode::Recording => {
+                self.recording.push(input);
+                (Input::default(), input)
+            }
+            DummyMode::Playback => {
+                let dummy_input = match self.recording.is_empty() {
+                    true => Input::default(),
+                    false => self.recording[self.playback_frame % self.recording.len()],
+                };
+                self.playback_frame += 1;
+
+                (input, dummy_input)
+            }
+        }
+    }
+}
+
+/// An interaction being measured for frame advantage.
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Measurement {
+    attacker: usize,
+    defender: usize,
+    started: u32,
+    attacker_ready: Option<u32>,
+    defender_ready: Option<u32>,
+}
+
+/// Measures how many frames sooner the attacker can act than the defender after a hit or block.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FrameAdvantage {
+    frame: u32,
+    prev_stun: Vec<u32>,
+    measuring: Option<Measurement>,
+    /// The last measured advantage, from the player's perspective.
+    last: Option<i32>,
+}
+
+impl FrameAdvantage {
+    pub fn last(&self) -> Option<i32> {
+        self.last
+    }
+
+    /// Updates the measurement after the state was ticked.
+    fn update(&mut self, state: &GameState) {
+        self.frame += 1;
+
+        let stun: Vec<u32> = state
+            .characters
+            .iter()
+            .map(|c| c.fighter.remaining_stun() + c.fighter.remaining_blockstun())
+            .collect();
+
+        // A new hit restarts the measurement, so the advantage is always for the last hit of a string.
+        if state.characters.len() == 2 {
+            let hit =
+                (0..stun.len()).find(|i| stun[*i] > self.prev_stun.get(*i).copied().unwrap_or(0));
+
+            if let Some(defender) = hit {
+                self.measuring = Some(Measurement {
+                    attacker: 1 - defender,
+                    defender,
+                    started: self.frame,
+                    attacker_ready: None,
+                    defender_ready: None,
+                });
+            }
+        }
+        self.prev_stun = stun;
+
+        let mut measurement = match self.measuring {
+            Some(measurement) => measurement,
+            None => return,
+        };
+
+        if measurement.attacker_ready.is_none() && is_ready(state, measurement.attacker) {
+            measurement.attacker_ready = Some(self.frame);
+        }
+        if measurement.defender_ready.is_none() && is_ready(state, measurement.defender) {
+            measurement.defender_ready = Some(self.frame);
+        }
+
+        self.measuring = match measurement {
+            Measurement {
+                attacker,
+                attacker_ready: Some(attacker_ready),
+                defender_ready: Some(defender_ready),
+                ..
+            } => {
+                let advantage = defender_ready as i32 - attacker_ready as i32;
+                self.last = match attacker == PLAYER {
+                    true => Some(advantage),
+                    false => Some(-advantage),
+                };
+
+                None
+            }
+            _ if self.frame - measurement.started > MAX_ADVANTAGE_FRAMES => None,
+            _ => Some(measurement),
+        };
+    }
+}
+
+/// Whether the character is free to act again. Shields count, as they can be dropped once blockstun ends.
+fn is_ready(state: &GameState, index: usize) -> bool {
+    let character = &state.characters[index];
+    let data = &state.character_data[character.fighter.character_data_index()];
+    let fighter = &character.fighter;
+
+    fighter.remaining_stun() == 0
+        && fighter.remaining_blockstun() == 0
+        && !fighter.in_hitstop()
+        && (fighter.is_actionable(data) || fighter.is_shielding(data))
+}
+
+/// A local mode for practicing against a dummy. Runs the game state directly, as there's nothing to roll back.
+pub struct TrainingMode {
+    state: GameState,
+    saved_state: Option<GameState>,
+    held_keys: Vec<Key>,
+
+    paused: bool,
+    step_requested: bool,
+    slow_motion: usize,
+    slow_motion_wait: u32,
+    show_boxes: bool,
+
+    dummy: Dummy,
+    advantage: FrameAdvantage,
+}
+
+impl TrainingMode {
+    pub fn new(state: GameState) -> Self {
+        Self {
+            state,
+            saved_state: None,
+            held_keys: vec![],
+
+            paused: false,
+            step_requested: false,
+            slow_motion: 0,
+            slow_motion_wait: 0,
+            show_boxes: true,
+
+            dummy: Dummy {
+                mode: DummyMode::Idle,
+                recording: vec![],
+                playback_frame: 0,
+            },
+            advantage: FrameAdvantage::default(),
+        }
+    }
+
+    pub fn state(&self) -> &GameState {
+        &self.state
+    }
+
+    /// Replaces the character data at the index, including in the saved state, so changes can be tried straight away.
+    pub fn set_character_data(&mut self, index: usize, data: CharacterStats) {
+        if let Some(saved_state) = &mut self.saved_state {
+            saved_state.set_character_data(index, data.clone());
+        }
+        self.state.set_character_data(index, data);
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// How many updates each simulated frame takes.
+    pub fn slow_motion_rate(&self) -> u32 {
+        SLOW_MOTION_RATES[self.slow_motion]
+    }
+
+    pub fn show_boxes(&self) -> bool {
+        self.show_boxes
+    }
+
+    pub fn dummy_mode(&self) -> DummyMode {
+        self.dummy.mode
+    }
+
+    /// The last measured frame advantage, from the player's perspective.
+    pub fn frame_advantage(&self) -> Option<i32> {
+        self.advantage.last()
+    }
+
+    /// Executes the commands for any hotkeys pressed. Holding a key only triggers it once.
+    pub fn handle_inputs(&mut self, inputs: &[DeviceInput]) {
+        for input in inputs {
+            if let DeviceInput::Key { key, state } = input {
+                let was_held = self.held_keys.contains(key);
+                self.held_keys.retain(|k| k != key);
+
+                if *state != PressState::Pressed {
+                    continue;
+                }
+                self.held_keys.push(*key);
+
+                if let (false, Some(command)) = (was_held, TrainingCommand::from_key(*key)) {
+                    self.execute(command);
+                }
+            }
+        }
+    }
+
+    pub fn execute(&mut self, command: TrainingCommand) {
+        match command {
+            TrainingCommand::SaveState => {
+                self.saved_state = Some(self.state.clone());
+            }
+            TrainingCommand::LoadState => {
+                if let Some(saved_state) = &self.saved_state {
+                    self.state = saved_state.clone();
+                    self.dummy.playback_frame = 0;
+                    self.advantage = FrameAdvantage {
+                        last: self.advantage.last,
+                        ..FrameAdvantage::default()
+                    };
+                }
+            }
+            TrainingCommand::TogglePause => {
+                self.paused = !self.paused;
+            }
+            TrainingCommand::StepFrame => {
+                self.paused = true;
+                self.step_requested = true;
+            }
+            TrainingCommand::SlowMotion => {
+                self.slow_motion = (self.slow_motion + 1) % SLOW_MOTION_RATES.len();
+                self.slow_motion_wait = 0;
+            }
+            TrainingCommand::RecordDummy => {
+                self.dummy.mode = DummyMode::Recording;
+                self.dummy.recording.clear();
+            }
+            TrainingCommand::PlayDummy => {
+                self.dummy.mode = DummyMode::Playback;
+                self.dummy.playback_frame = 0;
+            }
+            TrainingCommand::StopDummy => {
+                self.dummy.mode = DummyMode::Idle;
+            }
+            TrainingCommand::ToggleBoxes => {
+                self.show_boxes = !self.show_boxes;
+            }
+        }
+    }
+
+    /// Called once per simulation tick with the player's input. Returns whether the game advanced a frame.
+    pub fn update(&mut self, input: Input) -> bool {
+        if self.paused {
+            if !self.step_requested {
+                return false;
+            }
+            self.step_requested = false;
+        } else if self.slow_motion_wait > 0 {
+            self.slow_motion_wait -= 1;
+            return false;
+        } else {
+            self.slow_motion_wait = self.slow_motion_rate() - 1;
+        }
+
+        let (player_input, dummy_input) = self.dummy.inputs(input);
+        self.state.add_input(PLAYER as PlayerId, player_input);
+        self.state.add_input(DUMMY as PlayerId, dummy_input);
+        self.state.tick();
+        self.advantage.update(&self.state);
+
+        true
+    }
+
+    /// Draws the boxes of each character, and the training info over the game.
+    pub fn render(&self, queue: &mut RenderQueue) {
+        if self.show_boxes() {
+            for character in self.state.characters.iter() {
+                draw_boxes(character, queue);
+            }
+        }
+
+        let advantage = match self.frame_advantage() {
+            Some(advantage) => format!("Frame advantage: {:+}", advantage),
+            None => "Frame advantage: -".to_string(),
+        };
+        let speed = match (self.is_paused(), self.slow_motion_rate()) {
+            (true, _) => "Paused".to_string(),
+            (false, 1) => "Full speed".to_string(),
+            (false, rate) => format!("Slow motion 1/{}", rate),
+        };
+        let dummy = match self.dummy_mode() {
+            DummyMode::Idle => "Dummy: idle".to_string(),
+            DummyMode::Recording => format!("Dummy: recording {}", self.dummy.recording.len()),
+            DummyMode::Playback => format!("Dummy: playing {}", self.dummy.recording.len()),
+        };
+
+        for (line, text) in [advantage, speed, dummy].iter().enumerate() {
+            queue.push(RenderCommand::Text {
+                font_size: HUD_FONT_SIZE,
+                position: ScreenPoint {
+                    x: HUD_POSITION.x,
+                    y: HUD_POSITION.y - line as f32 * HUD_LINE_HEIGHT,
+                },
+                font: HUD_FONT,
+                text: text.clone(),
+                color: Color::white(),
+            });
+        }
+    }
+}
+
+/// Draws the push, hit, hurt and grab boxes of the character, each slightly in front of the last.
+pub fn draw_boxes(character: &Character, queue: &mut RenderQueue) {
+    let mut z = to_world(character.position)[2];
+    let z_inc = 0.01;
+
+    // pushbox
+    let color = [0.0, 1.0, 0.0, 0.8];
+    draw_aabbs(character.position, z, &character.push_boxes, color, queue);
+    z += z_inc;
+    // hitbox
+    let color = [1.0, 0.0, 0.0, 0.8];
+    draw_aabbs(character.position, z, &character.hit_boxes, color, queue);
+    z += z_inc;
+
+    // hurtbox
+    let color = [0.0, 0.0, 1.0, 0.8];
+    draw_aabbs(character.position, z, &character.hurt_boxes, color, queue);
+    z += z_inc;
+
+    // grabbox
+    let color = [0.5, 0.5, 0.5, 0.8];
+    draw_aabbs(character.position, z, &character.grab_boxes, color, queue);
+}
+
+/// Draws the boxes offset by the position, converting them to world units.
+pub fn draw_aabbs(
+    position: [i32; 3],
+    z: f32,
+    aabbs: &[Aabb],
+    color: [f32; 4],
+    queue: &mut RenderQueue,
+) {
+    let position = to_world(position);
+
+    for aabb in aabbs {
+        let min = {
+            let min = to_world([aabb.min[0], aabb.min[1], 0]);
+            let mut min = [min[0], min[1], 2.];
+            min[0] += position[0];
+            min[1] += position[1];
+            min[2] += position[2];
+            min
+        };
+
+        let max = {
+            let max = to_world([aabb.max[0], aabb.max[1], 0]);
+            let mut max = [max[0], max[1], 2.];
+            max[0] += position[0];
+            max[1] += position[1];
+            max[2] += position[2];
+            max
+        };
+
+        let debug_shape = RenderCommand::DebugRectangle { min, max, z, color };
+        queue.push(debug_shape);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fighting_game::tests::settled;
+
+    fn training() -> TrainingMode {
+        TrainingMode::new(settled(0, 400))
+    }
+
+    fn jab() -> Input {
+        Input {
+            light_atk_pressed: true,
+            ..Input::default()
+        }
+    }
+
+    fn key(key: Key, state: PressState) -> DeviceInput {
+        DeviceInput::Key { key, state }
+    }
+
+    #[test]
+    fn training_set_character_data_replaces_saved_state_data() {
+        let mut training = training();
+        training.execute(TrainingCommand::SaveState);
+
+        let mut data = (*training.state().character_data[0]).clone();
+        data.name = "Reloaded".to_string();
+        training.set_character_data(0, data);
+        assert_eq!("Reloaded", training.state().character_data[0].name);
+
+        training.execute(TrainingCommand::LoadState);
+        assert_eq!("Reloaded", training.state().character_data[0].name);
+    }
+
+    #[test]
+    fn training_save_and_load_state() {
+        let mut training = training();
+        training.handle_inputs(&[key(Key::F1, PressState::Pressed)]);
+        let saved = training.state().clone();
+
+        for _ in 0..10 {
+            training.update(jab());
+        }
+        assert_eq!(false, saved == *training.state());
+
+        training.handle_inputs(&[
+            key(Key::F1, PressState::Released),
+            key(Key::F2, PressState::Pressed),
+        ]);
+        assert_eq!(true, saved == *training.state());
+    }
+
+    #[test]
+    fn training_pause_step_and_slow_motion() {
+        let mut training = training();
+        let right = Input {
+            move_x_axis: 1,
+            ..Input::default()
+        };
+
+        // Holding the key doesn't toggle the pause again.
+        training.handle_inputs(&[key(Key::F3, PressState::Pressed)]);
+        training.handle_inputs(&[key(Key::F3, PressState::Pressed)]);
+        assert_eq!(true, training.is_paused());
+        assert_eq!(false, training.update(right));
+
+        training.execute(TrainingCommand::StepFrame);
+        assert_eq!(true, training.update(right));
+        assert_eq!(false, training.update(right));
+
+        training.execute(TrainingCommand::TogglePause);
+        training.execute(TrainingCommand::SlowMotion);
+        assert_eq!(2, training.slow_motion_rate());
+        let ticked: Vec<bool> = (0..4).map(|_| training.update(right)).collect();
+        assert_eq!(vec![true, false, true, false], ticked);
+    }
+
+    #[test]
+    fn training_dummy_records_and_plays_back() {
+        let mut training = training();
+        let start = training.state().clone();
+
+        training.execute(TrainingCommand::RecordDummy);
+        for _ in 0..3 {
+            training.update(jab());
+        }
+        for _ in 0..30 {
+            training.update(Input::default());
+        }
+        let recorded = training.state().characters[DUMMY].fighter.clone();
+        assert_eq!(3, training.state().characters[PLAYER].fighter.damage());
+
+        training.state = start;
+        training.execute(TrainingCommand::PlayDummy);
+        for _ in 0..33 {
+            training.update(Input::default());
+        }
+        assert_eq!(DummyMode::Playback, training.dummy_mode());
+        assert_eq!(true, recorded == training.state().characters[DUMMY].fighter);
+    }
+
+    #[test]
+    fn training_measures_frame_advantage() {
+        let measure = |attacker: usize| {
+            let mut training = training();
+            let mut state = training.state().clone();
+            if attacker == DUMMY {
+                training.execute(TrainingCommand::RecordDummy);
+            }
+
+            // Count the frames until each recovers from the hit, independently of the measurement.
+            let defender = 1 - attacker;
+            let mut hit = false;
+            let mut ready = [None, None];
+            for frame in 0..120 {
+                let input = match frame {
+                    0 => jab(),
+                    _ => Input::default(),
+                };
+                training.update(input);
+
+                let mut inputs = [Input::default(); 2];
+                inputs[attacker] = input;
+                state.add_input(0, inputs[0]);
+                state.add_input(1, inputs[1]);
+                state.tick();
+
+                hit |= state.characters[defender].fighter.remaining_stun() > 0;
+                for (index, ready) in ready.iter_mut().enumerate() {
+                    if hit && ready.is_none() && is_ready(&state, index) {
+                        *ready = Some(frame);
+                    }
+                }
+            }
+
+            let advantage = ready[defender].unwrap() - ready[attacker].unwrap();
+            (advantage, training.frame_advantage())
+        };
+
+        let (advantage, measured) = measure(PLAYER);
+        assert_eq!(Some(advantage), measured);
+
+        // Always from the player's perspective.
+        let (advantage, measured) = measure(DUMMY);
+        assert_eq!(Some(-advantage), measured);
+    }
+
+    #[test]
+    fn training_renders_boxes_through_debug_rectangles() {
+        let mut training = training();
+        let mut queue = RenderQueue::new(64);
+        training.render(&mut queue);
+
+        let character = &training.state().characters[PLAYER];
+        let num_boxes = |c: &Character| {
+            c.push_boxes.len() + c.hit_boxes.len() + c.hurt_boxes.len() + c.grab_boxes.len()
+        };
+        let rectangles = |queue: &RenderQueue| {
+            queue
+                .commands()
+                .iter()
+                .filter(|c| matches!(c, RenderCommand::DebugRectangle { .. }))
+                .count()
+        };
+
+        assert_eq!(true, num_boxes(character) > 0);
+        assert_eq!(
+            training
+                .state()
+                .characters
+                .iter()
+                .map(num_boxes)
+                .sum::<usize>(),
+            rectangles(&queue)
+        );
+
+        training.execute(TrainingCommand::ToggleBoxes);
+        let mut queue = RenderQueue::new(64);
+        training.render(&mut queue);
+        assert_eq!(0, rectangles(&queue));
+    }
+}
@@ -2,9 +2,13 @@ mod fighting_game;
 
 use portia::{
     gui::{AssetCommand, RenderCommand, RenderQueue},
-    input::{Input, Window},
+    input::{Input, Key, PressState, Window},
     GameImpl, GfxSettings, JobScheduler, SystemMessage,
 };
+use portia_client_server::{
+    session::{SessionBuilder, SessionEvent},
+    MultiplayerMode,
+};
 
 fn main() {
     portia::Game::<FinalDestination>::run("FinalDest");
@@ -16,9 +20,14 @@ pub struct FinalDestination {
     model_rot_degrees: f32,
     eye: [f32; 3],
     target: [f32; 3],
-    rollback_game: fighting_game::RollbackGame,
+    session: fighting_game::Session,
+    /// Replaces the session while practicing.
+    training: Option<fighting_game::training::TrainingMode>,
+    cpu: Option<fighting_game::cpu::Cpu>,
+    /// Kept while the CPU is off, so it comes back at the same level.
+    cpu_level: u32,
     input_poller: fighting_game::input_poller::InputPoller,
-    player: u8,
+    characters: fighting_game::character_loader::CharacterLoader,
 }
 
 //const TEST_GLTF: &'static str = "wizard_revised.glb";
@@ -30,6 +39,77 @@ const TEST_GLTF: &'static str = "models/monkey/monkey_2.glb";
 //const TEST_GLTF: &'static str = "models/sphere/sphere.gltf";
 //const TEST_GLTF: &'static str = "models/FlightHelmet/FlightHelmet.gltf";
 
+const MULTIPLAYER_MODE: MultiplayerMode = MultiplayerMode::DeterministicRollback;
+const TEST_CHARACTER: &'static str = "characters/example.ron";
+/// Every character uses the default character data for now, which the test character replaces.
+const TEST_CHARACTER_INDEX: usize = 0;
+const BINDINGS_FILE: &'static str = "config/bindings.ron";
+const TRAINING_MODE_KEY: Key = Key::F10;
+const CPU_KEY: Key = Key::F11;
+const CPU_HARDER_KEY: Key = Key::PageUp;
+const CPU_EASIER_KEY: Key = Key::PageDown;
+const BINDINGS_KEY: Key = Key::F12;
+const CPU_PLAYER: networking::rollback::PlayerId = 1;
+const CPU_SEED: u64 = 0;
+
+/// Loads the bindings profile, falling back to the defaults if it can't be read.
+fn load_bindings() -> fighting_game::bindings::Profile {
+    let bindings_path = portia::file_system::FileSystem::res_dir().join(BINDINGS_FILE);
+    match fighting_game::bindings::Profile::load_or_create(&bindings_path) {
+        Ok(profile) => profile,
+        Err(e) => {
+            println!("Error loading bindings {:?}: {}", bindings_path, e);
+            fighting_game::bindings::Profile::default()
+        }
+    }
+}
+
+/// Local players are assigned ids in order, so there need to be enough for the highest bound id and the CPU.
+fn num_players(profile: &fighting_game::bindings::Profile) -> usize {
+    profile
+        .player_ids()
+        .last()
+        .map_or(0, |id| *id as usize + 1)
+        .max(CPU_PLAYER as usize + 1)
+}
+
+impl FinalDestination {
+    /// Sets the CPU level, clamped to the valid range, and applies it to a running CPU.
+    fn set_cpu_level(&mut self, level: u32) {
+        use fighting_game::cpu::Difficulty;
+
+        self.cpu_level = level.clamp(Difficulty::MIN_LEVEL, Difficulty::MAX_LEVEL);
+        println!("CPU level {}.", self.cpu_level);
+
+        if let Some(cpu) = &mut self.cpu {
+            cpu.set_difficulty(Difficulty::level(self.cpu_level));
+        }
+    }
+
+    /// Reloads the bindings from disk. Players can't be added to a running session, so bindings for new players are rejected.
+    fn reload_bindings(&mut self) {
+        let profile = load_bindings();
+        if num_players(&profile) > num_players(self.input_poller.profile()) {
+            println!("Bindings for new players need a restart to take effect.");
+            return;
+        }
+
+        self.input_poller.set_profile(profile);
+    }
+
+    /// Swaps the latest version of the character into training. Matches keep the data they started with, as changing it mid match would desync the session.
+    fn reload_character(&mut self, file: &'static str) {
+        let data = match self.characters.character(file) {
+            Some(data) => data,
+            None => return,
+        };
+
+        if let Some(training) = &mut self.training {
+            training.set_character_data(TEST_CHARACTER_INDEX, data.clone());
+        }
+    }
+}
+
 impl GameImpl for FinalDestination {
     fn default(queue: &mut RenderQueue) -> Self {
         queue.push(RenderCommand::Asset(AssetCommand::LoadObj {
@@ -53,17 +133,32 @@ impl GameImpl for FinalDestination {
         let game_version = "0.0.1";
         let rollback_modes = vec![];
 
-        let mut rollback_game =
-            fighting_game::RollbackGame::new(game_version, frame_delay, rollback_modes);
-        let player = rollback_game.add_player();
+        let mut session: fighting_game::Session = SessionBuilder::new(game_version)
+            .mode(MULTIPLAYER_MODE)
+            .input_delay(frame_delay)
+            .rollback_modes(rollback_modes)
+            .build();
+
+        let profile = load_bindings();
+        for _ in 0..num_players(&profile) {
+            session.add_local_player();
+        }
+
+        // Hot reload character files in debug builds so frame data can be tuned while playing.
+        let mut characters =
+            fighting_game::character_loader::CharacterLoader::new(cfg!(debug_assertions));
+        characters.load(TEST_CHARACTER);
 
         Self {
-            input_poller: fighting_game::input_poller::InputPoller::new(),
+            characters,
+            input_poller: fighting_game::input_poller::InputPoller::new(profile),
             model_rot_degrees: 0.0,
             eye: [0.0, 0.0, 10.0],
             target: [0.0, 0.0, 0.0],
-            rollback_game,
-            player,
+            session,
+            training: None,
+            cpu: None,
+            cpu_level: fighting_game::cpu::Difficulty::DEFAULT_LEVEL,
         }
     }
 
@@ -93,6 +188,44 @@ impl GameImpl for FinalDestination {
                     },
                     _ => {}
                 },
+                Input::Key {
+                    key: TRAINING_MODE_KEY,
+                    state: PressState::Pressed,
+                } => {
+                    // Practice from wherever the current game is.
+                    self.training = match self.training.take() {
+                        Some(_) => None,
+                        None => Some(fighting_game::training::TrainingMode::new(
+                            self.session.state().game.clone(),
+                        )),
+                    };
+                    self.reload_character(TEST_CHARACTER);
+                }
+                Input::Key {
+                    key: CPU_KEY,
+                    state: PressState::Pressed,
+                } => {
+                    self.cpu = match self.cpu.take() {
+                        Some(_) => None,
+                        None => Some(fighting_game::cpu::Cpu::new(
+                            CPU_PLAYER as usize,
+                            fighting_game::cpu::Difficulty::level(self.cpu_level),
+                            CPU_SEED,
+                        )),
+                    };
+                }
+                Input::Key {
+                    key: CPU_HARDER_KEY,
+                    state: PressState::Pressed,
+                } => self.set_cpu_level(self.cpu_level + 1),
+                Input::Key {
+                    key: CPU_EASIER_KEY,
+                    state: PressState::Pressed,
+                } => self.set_cpu_level(self.cpu_level.saturating_sub(1)),
+                Input::Key {
+                    key: BINDINGS_KEY,
+                    state: PressState::Pressed,
+                } => self.reload_bindings(),
                 Input::AssetLoaded { file } => {
                     println!("Asset {:?} loaded!", file);
                 }
@@ -114,16 +247,61 @@ impl GameImpl for FinalDestination {
             }
         }
 
-        let player0_input = self.input_poller.poll(inputs);
+        for event in self.characters.update() {
+            match event {
+                fighting_game::character_loader::CharacterEvent::Loaded { file } => {
+                    println!("Character {:?} loaded!", file);
+                    self.reload_character(file);
+                }
+                fighting_game::character_loader::CharacterEvent::Failed { file, errors } => {
+                    for error in errors {
+                        println!("Error loading character {:?}: {}", file, error);
+                    }
+                }
+            }
+        }
+
+        let player_inputs = self.input_poller.poll(inputs);
+
+        if let Some(training) = &mut self.training {
+            training.handle_inputs(inputs);
+
+            let input = player_inputs
+                .iter()
+                .find(|(player, _)| *player == 0)
+                .map_or(fighting_game::Input::default(), |(_, input)| *input);
+            training.update(input);
+        } else {
+            for (player, input) in player_inputs {
+                // The CPU takes over its player from any local device.
+                if let Some(cpu) = &self.cpu {
+                    if cpu.player() == player as usize {
+                        continue;
+                    }
+                }
 
-        self.rollback_game
-            .register_local_input(self.player, player0_input);
+                self.session.register_local_input(player, input);
+            }
 
-        let events = self.rollback_game.tick();
-        for event in events {
-            match event {
-                networking::rollback::RollbackEvent::Disconnected => {
-                    unimplemented!("TODO: how to handle disconnects?");
+            if let Some(cpu) = &mut self.cpu {
+                let input = cpu.update(&self.session.state().game);
+                self.session
+                    .register_local_input(cpu.player() as networking::rollback::PlayerId, input);
+            }
+
+            for event in self.session.tick() {
+                match event {
+                    SessionEvent::Disconnected(player) => {
+                        // The match carries on, but their character no longer receives inputs.
+                        println!("Player {} disconnected.", player);
+                    }
+                    SessionEvent::Desync(frame) => {
+                        println!(
+                            "Sync test failed! Frame {} differed when simulated again.",
+                            frame
+                        );
+                    }
+                    SessionEvent::JoinedLobby(_) | SessionEvent::Connected(_) => {}
                 }
             }
         }
@@ -155,11 +333,16 @@ impl GameImpl for FinalDestination {
         });
         */
 
+        let state = match &self.training {
+            Some(training) => training.state(),
+            None => &self.session.state().game,
+        };
+
         // Players
-        for character in self.rollback_game.state().characters.iter() {
+        for character in state.characters.iter() {
             let scale = [0.5, 0.5, 0.5];
 
-            let position = character.position;
+            let position = fighting_game::to_world(character.position);
             let rotation = cgmath::Quaternion::from_axis_angle(
                 cgmath::Vector3::unit_z(),
                 cgmath::Deg(-self.model_rot_degrees * 0.5),
@@ -175,37 +358,26 @@ impl GameImpl for FinalDestination {
                 rotation,
             });
 
-            // Aabbs for debugging
-            {
-                let mut z = character.position[2];
-                let z_inc = 0.01;
-
-                // pushbox
-                let color = [0.0, 1.0, 0.0, 0.8];
-                draw_aabbs(character.position, z, &character.push_boxes, color, queue);
-                z += z_inc;
-                // hitbox
-                let color = [1.0, 0.0, 0.0, 0.8];
-                draw_aabbs(character.position, z, &character.hit_boxes, color, queue);
-                z += z_inc;
-
-                // hurtbox
-                let color = [0.0, 0.0, 1.0, 0.8];
-                draw_aabbs(character.position, z, &character.hurt_boxes, color, queue);
-                z += z_inc;
-
-                // grabbox
-                let color = [0.5, 0.5, 0.5, 0.8];
-                draw_aabbs(character.position, z, &character.grab_boxes, color, queue);
+            // Aabbs for debugging. Training mode draws them itself.
+            if self.training.is_none() {
+                fighting_game::training::draw_boxes(character, queue);
             }
         }
 
         // Draw stage
         {
-            let state = &self.rollback_game.state();
             let z = 0.0;
             let color = [0.9, 0.9, 0.9, 0.9];
-            draw_aabbs(state.stage_position, z, &state.stage_aabbs, color, queue);
+            fighting_game::training::draw_aabbs([0; 3], z, &state.stage.solids, color, queue);
+
+            let platforms: Vec<_> = state.stage.platforms.iter().map(|p| p.aabb()).collect();
+            let color = [0.7, 0.7, 0.7, 0.9];
+            fighting_game::training::draw_aabbs([0; 3], z, &platforms, color, queue);
+        }
+
+        match &self.training {
+            Some(training) => training.render(queue),
+            None => self.session.state().render(queue),
         }
 
         if DRAW_GLTF {
@@ -220,34 +392,3 @@ impl GameImpl for FinalDestination {
         });
     }
 }
-
-fn draw_aabbs(
-    position: [f32; 3],
-    z: f32,
-    aabbs: &Vec<fighting_game::Aabb>,
-    color: [f32; 4],
-    queue: &mut RenderQueue,
-) {
-    for aabb in aabbs {
-        let min = {
-            let min = aabb.min;
-            let mut min = [min[0], min[1], 2.];
-            min[0] += position[0];
-            min[1] += position[1];
-            min[2] += position[2];
-            min
-        };
-
-        let max = {
-            let max = aabb.max;
-            let mut max = [max[0], max[1], 2.];
-            max[0] += position[0];
-            max[1] += position[1];
-            max[2] += position[2];
-            max
-        };
-
-        let debug_shape = RenderCommand::DebugRectangle { min, max, z, color };
-        queue.push(debug_shape);
-    }
-}
@@ -0,0 +1,148 @@
+use crate::{numbers::*, particle::Particle, ParticleId};
+use std::collections::HashMap;
+
+type Cell = (i32, i32, i32);
+
+/// Spatial hash used to find particles that may be colliding.
+/// Pairs are always returned in the same order for the same particles, so it is safe to use for rollback.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SpatialHash {
+    cells: HashMap<Cell, Vec<ParticleId>>,
+    pairs: Vec<(ParticleId, ParticleId)>,
+}
+
+impl SpatialHash {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Finds all pairs of particles whose bounding spheres overlap. Each pair is ordered (lower id, higher id), and pairs are sorted.
+    pub fn find_pairs<N>(&mut self, particles: &[Particle<N>]) -> &Vec<(ParticleId, ParticleId)>
+    where
+        N: Number,
+    {
+        self.pairs.clear();
+        self.cells.clear();
+
+        let max_radius = particles
+            .iter()
+            .fold(N::i32(0), |max, particle| max.max(particle.radius));
+
+        if max_radius <= N::i32(0) {
+            return &self.pairs;
+        }
+
+        // With cells twice the largest radius, colliding particles are always in neighbouring cells.
+        let cell_size = max_radius * N::i32(2);
+
+        // Particles are inserted in id order, so every cell is sorted.
+        for (id, particle) in particles.iter().enumerate() {
+            let cell = cell_of(particle.position, cell_size);
+            self.cells.entry(cell).or_default().push(id);
+        }
+
+        for (id, particle) in particles.iter().enumerate() {
+            let start = self.pairs.len();
+            let (x, y, z) = cell_of(particle.position, cell_size);
+
+            for dx in -1..=1 {
+                for dy in -1..=1 {
+                    for dz in -1..=1 {
+                        let neighbour = (x + dx, y + dy, z + dz);
+                        if let Some(ids) = self.cells.get(&neighbour) {
+                            for &other in ids.iter().filter(|other| **other > id) {
+                                if overlaps(particle, &particles[other]) {
+                                    self.pairs.push((id, other));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Neighbours are visited in a fixed order, but sort to not depend on the cell layout.
+            self.pairs[start..].sort_unstable();
+        }
+
+        &self.pairs
+    }
+}
+
+fn cell_of<N>(position: Vec3<N>, cell_size: N) -> Cell
+where
+    N: Number,
+{
+    (
+        (position.x / cell_size).floor_to_int(),
+        (position.y / cell_size).floor_to_int(),
+        (position.z / cell_size).floor_to_int(),
+    )
+}
+
+fn overlaps<N>(a: &Particle<N>, b: &Particle<N>) -> bool
+where
+    N: Number,
+{
+    (b.position - a.position).len_squared() < (a.radius + b.radius).sqrd()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Particle, SpatialHash};
+    use game_math::f32::*;
+
+    fn particle(x: f32, y: f32, z: f32, radius: f32) -> Particle<f32> {
+        let mut p = Particle::new(Vec3::new(x, y, z), 1., Vec3::default(), 1.);
+        p.radius = radius;
+        p
+    }
+
+    #[test]
+    fn SpatialHash_find_pairs_returns_overlapping_in_order() {
+        let particles = vec![
+            particle(0., 0., 0., 1.),
+            particle(5., 0., 0., 1.),
+            particle(1.5, 0., 0., 1.),
+            particle(-1.5, 0., 0., 1.),
+            particle(5., 1.9, 0., 1.),
+            particle(-20., 0., 0., 1.),
+        ];
+
+        let mut hash = SpatialHash::new();
+        let pairs = hash.find_pairs(&particles).clone();
+
+        assert_eq!(vec![(0, 2), (0, 3), (1, 4)], pairs);
+
+        // Reusing the hash gives the same result.
+        assert_eq!(pairs, *hash.find_pairs(&particles));
+    }
+
+    #[test]
+    fn SpatialHash_find_pairs_no_radius_no_pairs() {
+        let particles = vec![particle(0., 0., 0., 0.), particle(0., 0., 0., 0.)];
+
+        let mut hash = SpatialHash::new();
+        assert_eq!(true, hash.find_pairs(&particles).is_empty());
+    }
+
+    #[test]
+    fn SpatialHash_find_pairs_fixed_point() {
+        use game_math::fix::Fix;
+
+        let particle = |x: i32| {
+            let mut p = Particle::new(
+                game_math::vectors::Vec3::new(Fix::from_int(x), Fix::ZERO, Fix::ZERO),
+                Fix::ONE,
+                game_math::vectors::Vec3::default(),
+                Fix::ONE,
+            );
+            p.radius = Fix::ONE;
+            p
+        };
+
+        let particles = vec![particle(-1), particle(0), particle(3)];
+        let mut hash = SpatialHash::new();
+
+        assert_eq!(vec![(0, 1)], *hash.find_pairs(&particles));
+    }
+}
@@ -0,0 +1,307 @@
+use crate::{numbers::*, Constraint, ParticleId, ParticleSystem};
+
+/// Helpers for building common bodies out of particles and constraints.
+/// A tear ratio breaks the generated constraints when they are stretched past their rest length times the ratio.
+/// Nothing is added if the body would go past the max number of particles.
+impl<N> ParticleSystem<N>
+where
+    N: Number,
+{
+    /// Adds a rope from start to end made of the given number of segments. Returns the particles from start to end.
+    pub fn add_rope(
+        &mut self,
+        start: Vec3<N>,
+        end: Vec3<N>,
+        segments: usize,
+        inv_mass: N,
+        tear_ratio: Option<N>,
+    ) -> Option<Vec<ParticleId>> {
+        let segments = segments.max(1);
+        if !self.has_room(segments + 1) {
+            return None;
+        }
+
+        let step = (end - start) / N::i32(segments as i32);
+
+        let ids = (0..=segments)
+            .map(|i| self.add_entity(start + step * N::i32(i as i32), inv_mass, None))
+            .collect::<Option<Vec<ParticleId>>>()?;
+
+        let length = step.len();
+        for pair in ids.windows(2) {
+            self.add_constraint(Constraint::Distance {
+                particle1: pair[0],
+                particle2: pair[1],
+                min: N::i32(0),
+                max: length,
+                break_length: tear_length(length, tear_ratio),
+            });
+        }
+
+        Some(ids)
+    }
+
+    /// Adds a grid of cloth with the given (columns, rows) starting at the origin, with each column offset by the column step
+    /// and each row by the row step. Structural, shear and bend constraints are generated. Returns the particles in row major order.
+    pub fn add_cloth(
+        &mut self,
+        origin: Vec3<N>,
+        column_step: Vec3<N>,
+        row_step: Vec3<N>,
+        size: (usize, usize),
+        inv_mass: N,
+        tear_ratio: Option<N>,
+    ) -> Option<Vec<ParticleId>> {
+        let (columns, rows) = size;
+        if !self.has_room(columns * rows) {
+            return None;
+        }
+
+        let mut ids = Vec::with_capacity(columns * rows);
+        for row in 0..rows {
+            for column in 0..columns {
+                let position =
+                    origin + column_step * N::i32(column as i32) + row_step * N::i32(row as i32);
+                ids.push(self.add_entity(position, inv_mass, None)?);
+            }
+        }
+
+        let id = |column: usize, row: usize| ids[row * columns + column];
+
+        for row in 0..rows {
+            for column in 0..columns {
+                // Structural
+                if column + 1 < columns {
+                    self.add_stick(id(column, row), id(column + 1, row), tear_ratio);
+                }
+                if row + 1 < rows {
+                    self.add_stick(id(column, row), id(column, row + 1), tear_ratio);
+                }
+
+                // Shear
+                if column + 1 < columns && row + 1 < rows {
+                    self.add_stick(id(column, row), id(column + 1, row + 1), tear_ratio);
+                    self.add_stick(id(column + 1, row), id(column, row + 1), tear_ratio);
+                }
+
+                // Bend. These don't tear, so cloth only rips along its structure.
+                if column + 2 < columns {
+                    self.add_stick(id(column, row), id(column + 2, row), None);
+                }
+                if row + 2 < rows {
+                    self.add_stick(id(column, row), id(column, row + 2), None);
+                }
+            }
+        }
+
+        Some(ids)
+    }
+
+    /// Adds a soft body from a mesh's vertices and edges. Each edge becomes a stick, and every vertex is attached to an
+    /// extra particle at the center to hold the volume. Returns the vertex particles followed by the center particle.
+    pub fn add_soft_body(
+        &mut self,
+        vertices: &[Vec3<N>],
+        edges: &[(usize, usize)],
+        inv_mass: N,
+        tear_ratio: Option<N>,
+    ) -> Option<Vec<ParticleId>> {
+        let center_count = if vertices.is_empty() { 0 } else { 1 };
+        if !self.has_room(vertices.len() + center_count) {
+            return None;
+        }
+
+        let mut ids = vertices
+            .iter()
+            .map(|vertex| self.add_entity(*vertex, inv_mass, None))
+            .collect::<Option<Vec<ParticleId>>>()?;
+
+        for (a, b) in edges.iter() {
+            if *a < ids.len() && *b < ids.len() && a != b {
+                self.add_stick(ids[*a], ids[*b], tear_ratio);
+            }
+        }
+
+        if !vertices.is_empty() {
+            let mut center = Vec3::default();
+            for vertex in vertices.iter() {
+                center += *vertex;
+            }
+            center /= N::i32(vertices.len() as i32);
+
+            let center = self.add_entity(center, inv_mass, None)?;
+            for vertex in ids.iter() {
+                self.add_stick(*vertex, center, None);
+            }
+            ids.push(center);
+        }
+
+        Some(ids)
+    }
+
+    /// Adds a stick between the two particles at their current distance.
+    fn add_stick(&mut self, particle1: ParticleId, particle2: ParticleId, tear_ratio: Option<N>) {
+        let rest_length =
+            (self.particles[particle2].position - self.particles[particle1].position).len();
+
+        self.add_constraint(Constraint::Stick {
+            particle1,
+            particle2,
+            rest_length,
+            break_length: tear_length(rest_length, tear_ratio),
+        });
+    }
+}
+
+fn tear_length<N>(rest_length: N, tear_ratio: Option<N>) -> Option<N>
+where
+    N: Number,
+{
+    tear_ratio.map(|ratio| rest_length * ratio)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Constraint, ParticleSystem};
+    use game_math::f32::*;
+
+    fn count(system: &ParticleSystem<f32>) -> (usize, usize) {
+        let sticks = system
+            .constraints()
+            .iter()
+            .filter(|c| matches!(c, Constraint::Stick { .. }))
+            .count();
+
+        (sticks, system.constraints().len() - sticks)
+    }
+
+    #[test]
+    fn ParticleSystem_add_rope_creates_segments() {
+        let mut system = ParticleSystem::new(5, Vec3::new(0., -1., 0.));
+
+        let ids = system
+            .add_rope(Vec3::new(0., 0., 0.), Vec3::new(4., 0., 0.), 4, 1., None)
+            .unwrap();
+
+        assert_eq!(vec![0, 1, 2, 3, 4], ids);
+        assert_eq!(Vec3::new(3., 0., 0.), system.particles[3].position);
+        assert_eq!((0, 4), count(&system));
+        assert_eq!(
+            Constraint::Distance {
+                particle1: 1,
+                particle2: 2,
+                min: 0.,
+                max: 1.,
+                break_length: None,
+            },
+            system.constraints()[1]
+        );
+    }
+
+    #[test]
+    fn ParticleSystem_add_cloth_creates_grid() {
+        let mut system = ParticleSystem::new(12, Vec3::new(0., -1., 0.));
+
+        let ids = system
+            .add_cloth(
+                Vec3::new(0., 0., 0.),
+                Vec3::new(1., 0., 0.),
+                Vec3::new(0., -1., 0.),
+                (4, 3),
+                1.,
+                None,
+            )
+            .unwrap();
+
+        assert_eq!(12, ids.len());
+        assert_eq!(Vec3::new(3., -2., 0.), system.particles[ids[11]].position);
+
+        // Structural: 3 * 3 + 4 * 2, shear: 3 * 2 * 2, bend: 2 * 3 + 4 * 1
+        assert_eq!((17 + 12 + 10, 0), count(&system));
+    }
+
+    #[test]
+    fn ParticleSystem_add_soft_body_adds_center() {
+        let mut system = ParticleSystem::new(5, Vec3::new(0., -1., 0.));
+
+        let vertices = [
+            Vec3::new(0., 0., 0.),
+            Vec3::new(2., 0., 0.),
+            Vec3::new(2., 2., 0.),
+            Vec3::new(0., 2., 0.),
+        ];
+        let edges = [(0, 1), (1, 2), (2, 3), (3, 0), (0, 2), (9, 0)];
+
+        let ids = system.add_soft_body(&vertices, &edges, 1., None).unwrap();
+
+        assert_eq!(5, ids.len());
+        assert_eq!(Vec3::new(1., 1., 0.), system.particles[ids[4]].position);
+        // Invalid edges are skipped.
+        assert_eq!((5 + 4, 0), count(&system));
+    }
+
+    #[test]
+    fn ParticleSystem_builders_add_nothing_past_max_particles() {
+        let mut system = ParticleSystem::new(4, Vec3::new(0., -1., 0.));
+
+        let rope = system.add_rope(Vec3::new(0., 0., 0.), Vec3::new(4., 0., 0.), 4, 1., None);
+        assert_eq!(None, rope);
+
+        let cloth = system.add_cloth(
+            Vec3::new(0., 0., 0.),
+            Vec3::new(1., 0., 0.),
+            Vec3::new(0., -1., 0.),
+            (2, 3),
+            1.,
+            None,
+        );
+        assert_eq!(None, cloth);
+
+        assert_eq!(0, system.particles().len());
+        assert_eq!(true, system.constraints().is_empty());
+    }
+
+    #[test]
+    fn ParticleSystem_cloth_tears_when_overstretched() {
+        let mut system = ParticleSystem::new(6, Vec3::new(0., 0., 0.));
+
+        let ids = system
+            .add_cloth(
+                Vec3::new(0., 0., 0.),
+                Vec3::new(1., 0., 0.),
+                Vec3::new(0., -1., 0.),
+                (3, 2),
+                1.,
+                Some(1.5),
+            )
+            .unwrap();
+        let constraints = system.constraints().len();
+
+        // Pull the corners apart hard enough that the solver can't keep up.
+        system.set_constraint_iterations(1);
+        system.add_constraint(Constraint::Pin {
+            particle: ids[0],
+            position: Vec3::new(-5., 0., 0.),
+        });
+        system.add_constraint(Constraint::Pin {
+            particle: ids[2],
+            position: Vec3::new(7., 0., 0.),
+        });
+
+        system.timestep();
+
+        assert_eq!(false, system.broken_constraints().is_empty());
+        assert_eq!(
+            constraints + 2,
+            system.constraints().len() + system.broken_constraints().len()
+        );
+        for constraint in system.broken_constraints() {
+            assert_eq!(true, constraint.is_broken(system.particles()));
+        }
+
+        // Nothing else breaks once settled.
+        system.clear_constraints();
+        system.timestep();
+        assert_eq!(true, system.broken_constraints().is_empty());
+    }
+}
@@ -0,0 +1,379 @@
+use crate::{numbers::*, particle::Particle, ParticleId};
+
+/// A constraint that is solved every iteration of the particle system.
+/// Particle constraints only affect the given particles, while containers and colliders affect every particle in the system.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Constraint<N>
+where
+    N: Number,
+{
+    /// Keeps two particles at the rest length. Breaks when the particles are further apart than the break length.
+    Stick {
+        particle1: ParticleId,
+        particle2: ParticleId,
+        rest_length: N,
+        break_length: Option<N>,
+    },
+    /// Keeps two particles between the min and max distance. A min of 0 acts as a rope. Breaks when the particles are further apart than the break length.
+    Distance {
+        particle1: ParticleId,
+        particle2: ParticleId,
+        min: N,
+        max: N,
+        break_length: Option<N>,
+    },
+    /// Anchors a particle to a point.
+    Pin {
+        particle: ParticleId,
+        position: Vec3<N>,
+    },
+    /// Keeps the angle between the two particles at the joint above the min angle, in radians. Used for bending resistance.
+    Angle {
+        particle1: ParticleId,
+        joint: ParticleId,
+        particle2: ParticleId,
+        min_angle: N,
+    },
+    /// Keeps all particles on the front side of the plane. Containers and colliders take the particle radius into account.
+    Plane { plane: Plane<N> },
+    /// Keeps all particles inside the box.
+    Box { aabb: Aabb<N> },
+    /// Pushes all particles out of the sphere.
+    Sphere { sphere: Sphere<N> },
+    /// Pushes all particles out of the capsule.
+    Capsule { capsule: Capsule<N> },
+}
+
+impl<N> Constraint<N>
+where
+    N: Number,
+{
+    /// Applies the constraint to the particles.
+    pub fn apply(&self, particles: &mut [Particle<N>]) {
+        match *self {
+            Constraint::Stick {
+                particle1,
+                particle2,
+                rest_length,
+                ..
+            } => {
+                distance_constraint(particles, particle1, particle2, rest_length, rest_length);
+            }
+            Constraint::Distance {
+                particle1,
+                particle2,
+                min,
+                max,
+                ..
+            } => {
+                distance_constraint(particles, particle1, particle2, min, max);
+            }
+            Constraint::Pin { particle, position } => {
+                particles[particle].position = position;
+            }
+            Constraint::Angle {
+                particle1,
+                joint,
+                particle2,
+                min_angle,
+            } => {
+                let joint = particles[joint].position;
+                let a = particles[particle1].position - joint;
+                let b = particles[particle2].position - joint;
+
+                let a_len = a.len();
+                let b_len = b.len();
+
+                // Law of cosines to get the distance between the two particles at the min angle.
+                let min_distance_sqrd =
+                    a_len.sqrd() + b_len.sqrd() - N::i32(2) * a_len * b_len * min_angle.cos();
+
+                if min_distance_sqrd > N::i32(0) {
+                    let min = min_distance_sqrd.sqrt();
+                    // Only push the ends apart, as the joint is shared with other constraints.
+                    let max = a_len + b_len;
+                    distance_constraint(particles, particle1, particle2, min, max);
+                }
+            }
+            Constraint::Plane { plane } => {
+                for_each_movable(particles, |particle| {
+                    let distance = plane.signed_distance(particle.position) - particle.radius;
+                    if distance < N::i32(0) {
+                        particle.position - plane.normal * distance
+                    } else {
+                        particle.position
+                    }
+                });
+            }
+            Constraint::Box { aabb } => {
+                for_each_movable(particles, |particle| {
+                    let radius = Vec3::new(particle.radius, particle.radius, particle.radius);
+                    let inner = Aabb::new(aabb.min + radius, aabb.max - radius);
+                    inner.closest_point(particle.position)
+                });
+            }
+            Constraint::Sphere { sphere } => {
+                for_each_movable(particles, |particle| {
+                    let radius = sphere.radius + particle.radius;
+                    push_out_of_sphere(particle.position, sphere.center, radius)
+                });
+            }
+            Constraint::Capsule { capsule } => {
+                for_each_movable(particles, |particle| {
+                    let nearest = capsule.segment.closest_point(particle.position);
+                    let radius = capsule.radius + particle.radius;
+                    push_out_of_sphere(particle.position, nearest, radius)
+                });
+            }
+        }
+    }
+
+    /// Whether the constraint has been stretched past its break length.
+    pub fn is_broken(&self, particles: &[Particle<N>]) -> bool {
+        match *self {
+            Constraint::Stick {
+                particle1,
+                particle2,
+                break_length: Some(break_length),
+                ..
+            }
+            | Constraint::Distance {
+                particle1,
+                particle2,
+                break_length: Some(break_length),
+                ..
+            } => {
+                let delta = particles[particle2].position - particles[particle1].position;
+                delta.len_squared() > break_length.sqrd()
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Moves two particles so they are between the min and max distance, weighted by their inverse mass.
+fn distance_constraint<N>(
+    particles: &mut [Particle<N>],
+    particle1: ParticleId,
+    particle2: ParticleId,
+    min: N,
+    max: N,
+) where
+    N: Number,
+{
+    let zero = N::i32(0);
+    let p1 = particles[particle1];
+    let p2 = particles[particle2];
+
+    let total_inv_mass = p1.inv_mass + p2.inv_mass;
+    if total_inv_mass == zero {
+        return;
+    }
+
+    let delta = p2.position - p1.position;
+    let delta_len = delta.len();
+    if delta_len == zero {
+        return;
+    }
+
+    let target = delta_len.max(min).min(max);
+    if target == delta_len {
+        return;
+    }
+
+    let correction = delta * ((delta_len - target) / delta_len / total_inv_mass);
+
+    particles[particle1].position += correction * p1.inv_mass;
+    particles[particle2].position -= correction * p2.inv_mass;
+}
+
+/// Sets the position of every particle that can move to the result of the function.
+fn for_each_movable<N, F>(particles: &mut [Particle<N>], f: F)
+where
+    N: Number,
+    F: Fn(&Particle<N>) -> Vec3<N>,
+{
+    for particle in particles.iter_mut() {
+        if particle.inv_mass != N::i32(0) {
+            particle.position = f(particle);
+        }
+    }
+}
+
+/// Moves the position to the surface of the sphere if it is inside.
+fn push_out_of_sphere<N>(position: Vec3<N>, center: Vec3<N>, radius: N) -> Vec3<N>
+where
+    N: Number,
+{
+    let delta = position - center;
+    if delta.len_squared() >= radius.sqrd() {
+        return position;
+    }
+
+    center + delta.normalize() * radius
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Constraint, Particle};
+    use game_math::f32::*;
+
+    fn particle(x: f32, y: f32, z: f32) -> Particle<f32> {
+        Particle::new(Vec3::new(x, y, z), 1., Vec3::default(), 1.)
+    }
+
+    #[test]
+    fn Constraint_stick_respects_inv_mass() {
+        let mut particles = vec![particle(0., 0., 0.), particle(4., 0., 0.)];
+        particles[0].inv_mass = 0.;
+
+        let stick = Constraint::Stick {
+            particle1: 0,
+            particle2: 1,
+            rest_length: 2.,
+            break_length: None,
+        };
+        stick.apply(&mut particles);
+
+        assert_eq!(Vec3::new(0., 0., 0.), particles[0].position);
+        assert_eq!(Vec3::new(2., 0., 0.), particles[1].position);
+
+        particles[0].inv_mass = 1.;
+        particles[1].position = Vec3::new(0., 4., 0.);
+        stick.apply(&mut particles);
+
+        assert_eq!(Vec3::new(0., 1., 0.), particles[0].position);
+        assert_eq!(Vec3::new(0., 3., 0.), particles[1].position);
+    }
+
+    #[test]
+    fn Constraint_distance_only_applies_outside_range() {
+        let mut particles = vec![particle(0., 0., 0.), particle(3., 0., 0.)];
+        let rope = Constraint::Distance {
+            particle1: 0,
+            particle2: 1,
+            min: 0.,
+            max: 4.,
+            break_length: None,
+        };
+
+        rope.apply(&mut particles);
+        assert_eq!(Vec3::new(3., 0., 0.), particles[1].position);
+
+        particles[1].position = Vec3::new(6., 0., 0.);
+        rope.apply(&mut particles);
+        assert_eq!(Vec3::new(1., 0., 0.), particles[0].position);
+        assert_eq!(Vec3::new(5., 0., 0.), particles[1].position);
+    }
+
+    #[test]
+    fn Constraint_pin_sets_position() {
+        let mut particles = vec![particle(1., 2., 3.)];
+        Constraint::Pin {
+            particle: 0,
+            position: Vec3::new(0., 5., 0.),
+        }
+        .apply(&mut particles);
+
+        assert_eq!(Vec3::new(0., 5., 0.), particles[0].position);
+    }
+
+    #[test]
+    fn Constraint_angle_pushes_ends_apart() {
+        let mut particles = vec![
+            particle(1., 0., 0.),
+            particle(0., 0., 0.),
+            particle(0., 1., 0.),
+        ];
+        particles[1].inv_mass = 0.;
+
+        let angle = Constraint::Angle {
+            particle1: 0,
+            joint: 1,
+            particle2: 2,
+            min_angle: std::f32::consts::PI,
+        };
+
+        // A right angle is below the min, so the ends are pushed apart.
+        let before = (particles[2].position - particles[0].position).len();
+        angle.apply(&mut particles);
+        let after = (particles[2].position - particles[0].position).len();
+        assert_eq!(true, after > before);
+        assert_eq!(Vec3::new(0., 0., 0.), particles[1].position);
+
+        // Straight is at the min, so nothing changes.
+        let mut particles = vec![
+            particle(-1., 0., 0.),
+            particle(0., 0., 0.),
+            particle(1., 0., 0.),
+        ];
+        angle.apply(&mut particles);
+        assert_eq!(Vec3::new(-1., 0., 0.), particles[0].position);
+        assert_eq!(Vec3::new(1., 0., 0.), particles[2].position);
+    }
+
+    #[test]
+    fn Constraint_containers_keep_particles_inside() {
+        let mut particles = vec![particle(0., -2., 0.), particle(12., 3., 0.)];
+        particles[1].inv_mass = 0.;
+
+        Constraint::Plane {
+            plane: Plane::new(Vec3::new(0., 1., 0.), 0.),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(0., 0., 0.), particles[0].position);
+
+        particles[0].position = Vec3::new(-10., 10., 0.);
+        Constraint::Box {
+            aabb: Aabb::new(Vec3::new(-8., -8., -8.), Vec3::new(8., 8., 8.)),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(-8., 8., 0.), particles[0].position);
+        // Immovable particles are left alone.
+        assert_eq!(Vec3::new(12., 3., 0.), particles[1].position);
+    }
+
+    #[test]
+    fn Constraint_colliders_push_particles_out() {
+        let mut particles = vec![particle(0., 1., 0.)];
+
+        Constraint::Sphere {
+            sphere: Sphere::new(Vec3::new(0., 0., 0.), 2.),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(0., 2., 0.), particles[0].position);
+
+        particles[0].position = Vec3::new(0.5, 3., 0.);
+        Constraint::Capsule {
+            capsule: Capsule::new(Vec3::new(0., 0., 0.), Vec3::new(0., 5., 0.), 1.),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(1., 3., 0.), particles[0].position);
+    }
+
+    #[test]
+    fn Constraint_colliders_use_particle_radius() {
+        let mut particles = vec![particle(0., 0.5, 0.)];
+        particles[0].radius = 1.;
+
+        Constraint::Plane {
+            plane: Plane::new(Vec3::new(0., 1., 0.), 0.),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(0., 1., 0.), particles[0].position);
+
+        particles[0].position = Vec3::new(10., 0., 0.);
+        Constraint::Box {
+            aabb: Aabb::new(Vec3::new(-8., -8., -8.), Vec3::new(8., 8., 8.)),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(7., 0., 0.), particles[0].position);
+
+        particles[0].position = Vec3::new(0., 2., 0.);
+        Constraint::Sphere {
+            sphere: Sphere::new(Vec3::new(0., 0., 0.), 2.),
+        }
+        .apply(&mut particles);
+        assert_eq!(Vec3::new(0., 3., 0.), particles[0].position);
+    }
+}
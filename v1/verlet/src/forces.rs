@@ -0,0 +1,67 @@
+use crate::numbers::*;
+
+/// A wind field that drags particles towards its velocity.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Wind<N>
+where
+    N: Number,
+{
+    /// The velocity of the air, in units per second.
+    pub velocity: Vec3<N>,
+    /// How strongly particles are dragged towards the wind velocity.
+    pub drag: N,
+    /// The area the wind blows in. None blows everywhere.
+    pub region: Option<Aabb<N>>,
+}
+
+impl<N> Wind<N>
+where
+    N: Number,
+{
+    /// Creates a new Wind that blows everywhere.
+    pub fn new(velocity: Vec3<N>, drag: N) -> Self {
+        Self {
+            velocity,
+            drag,
+            region: None,
+        }
+    }
+
+    /// The force applied to a particle at the position with the given velocity.
+    pub fn force(&self, position: Vec3<N>, velocity: Vec3<N>) -> Vec3<N> {
+        match self.region {
+            Some(region) if !region.contains_point(position) => Vec3::default(),
+            _ => (self.velocity - velocity) * self.drag,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Wind;
+    use game_math::f32::*;
+
+    #[test]
+    fn Wind_force_returns_expected() {
+        let mut wind = Wind::new(Vec3::new(4., 0., 0.), 0.5);
+
+        assert_eq!(
+            Vec3::new(2., 0., 0.),
+            wind.force(Vec3::default(), Vec3::default())
+        );
+        assert_eq!(
+            Vec3::new(1., -1., 0.),
+            wind.force(Vec3::default(), Vec3::new(2., 2., 0.))
+        );
+
+        wind.region = Some(Aabb::new(Vec3::new(0., 0., 0.), Vec3::new(1., 1., 1.)));
+        assert_eq!(
+            Vec3::new(2., 0., 0.),
+            wind.force(Vec3::new(0.5, 0.5, 0.5), Vec3::default())
+        );
+        assert_eq!(
+            Vec3::default(),
+            wind.force(Vec3::new(2., 0.5, 0.5), Vec3::default())
+        );
+    }
+}
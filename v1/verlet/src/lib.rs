@@ -1,3 +1,14 @@
+pub mod broadphase;
+use broadphase::SpatialHash;
+
+mod builders;
+
+pub mod constraint;
+pub use constraint::Constraint;
+
+pub mod forces;
+pub use forces::Wind;
+
 pub mod particle;
 use particle::*;
 
@@ -6,19 +17,6 @@ use numbers::*;
 
 pub type ParticleId = usize;
 
-#[derive(Clone, Copy, Debug, PartialEq)]
-pub enum Constraint<N>
-where
-    N: Number,
-{
-    Stick {
-        particle1: ParticleId,
-        particle2: ParticleId,
-        rest_length: N,
-        rest_len_sqrd: Option<N>,
-    },
-}
-
 /// Particle system for physics based on Verlet Integration scheme
 #[derive(Clone, Debug, PartialEq)]
 pub struct ParticleSystem<N>
@@ -27,9 +25,19 @@ where
 {
     pub particles: Vec<Particle<N>>,
     constraints: Vec<Constraint<N>>,
+    broken_constraints: Vec<Constraint<N>>,
     gravity: Vec3<N>,
     timestep: N,
+    previous_dt: Option<N>,
+    substeps: usize,
+    damping: N,
+    winds: Vec<Wind<N>>,
     max_particles: usize,
+    constraint_iterations: usize,
+    friction: N,
+    restitution: N,
+    broadphase: SpatialHash,
+    contacts: Vec<(ParticleId, ParticleId)>,
 }
 
 impl<N> ParticleSystem<N>
@@ -42,140 +50,381 @@ where
             gravity,
             particles: Vec::with_capacity(max_particles),
             constraints: Vec::with_capacity(max_particles),
+            broken_constraints: vec![],
             timestep: N::i32(1),
+            previous_dt: None,
+            substeps: 1,
+            damping: N::i32(0),
+            winds: vec![],
+            constraint_iterations: 20,
+            friction: N::i32(0),
+            restitution: N::i32(0),
+            broadphase: SpatialHash::new(),
+            contacts: vec![],
         };
 
         sim
     }
 
     pub fn add_constraint(&mut self, constraint: Constraint<N>) {
-        // TODO: if Line constraint, precalculate rest_len_sqrd
         self.constraints.push(constraint);
     }
 
+    pub fn constraints(&self) -> &Vec<Constraint<N>> {
+        &self.constraints
+    }
+
+    /// The constraints that broke during the last step.
+    pub fn broken_constraints(&self) -> &Vec<Constraint<N>> {
+        &self.broken_constraints
+    }
+
+    /// Removes all constraints from the system.
+    pub fn clear_constraints(&mut self) {
+        self.constraints.clear();
+    }
+
+    /// The number of times the constraints are solved each step.
+    pub fn constraint_iterations(&self) -> usize {
+        self.constraint_iterations
+    }
+
+    /// Sets the number of times the constraints are solved each step. More iterations are stiffer but slower.
+    pub fn set_constraint_iterations(&mut self, constraint_iterations: usize) {
+        self.constraint_iterations = constraint_iterations;
+    }
+
+    /// The time each call to `timestep` advances the simulation by.
+    pub fn dt(&self) -> N {
+        self.timestep
+    }
+
+    /// Sets the time each call to `timestep` advances the simulation by. Changes are corrected for, so velocities are kept.
+    pub fn set_dt(&mut self, dt: N) {
+        self.timestep = dt;
+    }
+
+    /// The number of physics steps each call to `timestep` is split into.
+    pub fn substeps(&self) -> usize {
+        self.substeps
+    }
+
+    /// Sets the number of physics steps each call to `timestep` is split into. More substeps are more stable but slower.
+    pub fn set_substeps(&mut self, substeps: usize) {
+        self.substeps = substeps.max(1);
+    }
+
+    /// The fraction of velocity all particles lose each call to `timestep`, from 0 to 1. Applied once after the substeps, so it doesn't depend on how many there are.
+    pub fn damping(&self) -> N {
+        self.damping
+    }
+
+    pub fn set_damping(&mut self, damping: N) {
+        self.damping = damping;
+    }
+
+    pub fn gravity(&self) -> Vec3<N> {
+        self.gravity
+    }
+
+    pub fn set_gravity(&mut self, gravity: Vec3<N>) {
+        self.gravity = gravity;
+    }
+
+    pub fn add_wind(&mut self, wind: Wind<N>) {
+        self.winds.push(wind);
+    }
+
+    pub fn winds(&self) -> &Vec<Wind<N>> {
+        &self.winds
+    }
+
+    pub fn clear_winds(&mut self) {
+        self.winds.clear();
+    }
+
+    /// Adds a force to the particle for the next tick. Forces are scaled by the particle's inverse mass.
+    pub fn add_force(&mut self, particle: ParticleId, force: Vec3<N>) {
+        if let Some(particle) = self.particles.get_mut(particle) {
+            particle.external_force += force;
+        }
+    }
+
+    /// Instantly changes the particle's velocity by the impulse scaled by its inverse mass.
+    pub fn apply_impulse(&mut self, particle: ParticleId, impulse: Vec3<N>) {
+        let dt = self.step_dt();
+
+        if let Some(particle) = self.particles.get_mut(particle) {
+            // Velocity is implicit, so move the old position instead.
+            particle.old_position -= impulse * particle.inv_mass * dt;
+        }
+    }
+
+    /// The velocity of the particle, in units per second.
+    pub fn velocity(&self, particle: ParticleId) -> Option<Vec3<N>> {
+        let dt = self.step_dt();
+
+        self.particles
+            .get(particle)
+            .map(|p| (p.position - p.old_position) / dt)
+    }
+
+    /// The friction between colliding particles, from 0 (none) to 1 (no sliding).
+    pub fn friction(&self) -> N {
+        self.friction
+    }
+
+    pub fn set_friction(&mut self, friction: N) {
+        self.friction = friction;
+    }
+
+    /// The bounciness of colliding particles, from 0 (no bounce) to 1 (perfectly elastic).
+    pub fn restitution(&self) -> N {
+        self.restitution
+    }
+
+    pub fn set_restitution(&mut self, restitution: N) {
+        self.restitution = restitution;
+    }
+
+    /// Adds a particle with no collision radius, moving at the given velocity in units per second.
+    /// Returns None if the system already has the max number of particles.
     pub fn add_entity(
         &mut self,
         position: Vec3<N>,
         inv_mass: N,
         velocity: Option<Vec3<N>>,
-    ) -> ParticleId {
+    ) -> Option<ParticleId> {
         let velocity = match velocity {
             Some(v) => v,
             None => Vec3::default(),
         };
 
-        let p = Particle::new(position, inv_mass, velocity);
+        let dt = self.step_dt();
+        self.add_particle(Particle::new(position, inv_mass, velocity, dt))
+    }
+
+    /// Adds a particle, such as one with a collision radius. Returns None if the system already has the max number of particles.
+    pub fn add_particle(&mut self, particle: Particle<N>) -> Option<ParticleId> {
+        if !self.has_room(1) {
+            return None;
+        }
+
         let id = self.particles.len();
-        self.particles.push(p);
+        self.particles.push(particle);
+
+        Some(id)
+    }
+
+    /// The most particles the system may hold.
+    pub fn max_particles(&self) -> usize {
+        self.max_particles
+    }
 
-        id
+    /// The time a single substep advances the simulation by. Velocities are stored as the distance moved over it.
+    fn step_dt(&self) -> N {
+        self.previous_dt
+            .unwrap_or(self.timestep / N::i32(self.substeps as i32))
+    }
+
+    /// Returns whether the given number of particles can be added without going past the max.
+    fn has_room(&self, count: usize) -> bool {
+        self.particles.len() + count <= self.max_particles
     }
 
     pub fn particles(&self) -> &Vec<Particle<N>> {
         &self.particles
     }
 
-    /// Step the physics simulation
+    /// Step the physics simulation by dt, split into the configured number of substeps.
     pub fn timestep(&mut self) {
-        self.accumulate_forces();
-        self.verlet();
-        self.satisfy_constraints(20);
+        self.broken_constraints.clear();
+
+        let dt = self.timestep / N::i32(self.substeps as i32);
+        for _ in 0..self.substeps {
+            self.accumulate_forces(dt);
+            self.verlet(dt);
+            self.satisfy_constraints();
+            self.break_constraints();
+
+            self.previous_dt = Some(dt);
+        }
+
+        self.apply_damping();
+
+        for particle in self.particles.iter_mut() {
+            particle.external_force = Vec3::default();
+        }
+    }
+
+    /// Removes the damped fraction of each particle's velocity. As velocity is implicit, this moves the old position.
+    fn apply_damping(&mut self) {
+        let one = N::i32(1);
+
+        for particle in self.particles.iter_mut() {
+            let damping = (one - self.damping) * (one - particle.damping);
+            let velocity = particle.position - particle.old_position;
+            particle.old_position = particle.position - velocity * damping;
+        }
+    }
+
+    /// Removes any constraints that have been stretched past their break length.
+    fn break_constraints(&mut self) {
+        let particles = &self.particles;
+        let broken = &mut self.broken_constraints;
+
+        self.constraints.retain(|constraint| {
+            if constraint.is_broken(particles) {
+                broken.push(*constraint);
+                false
+            } else {
+                true
+            }
+        });
     }
 
-    /// Integrate verlet
-    fn verlet(&mut self) {
+    /// Integrate time corrected verlet, which keeps velocities the same when dt changes.
+    fn verlet(&mut self, dt: N) {
+        let zero = N::i32(0);
+        let previous_dt = self.previous_dt.unwrap_or(dt);
+        let dt_ratio = dt / previous_dt;
+
         for particle in self.particles.iter_mut() {
+            // Infinite mass particles are only moved by constraints.
+            if particle.inv_mass == zero {
+                particle.old_position = particle.position;
+                continue;
+            }
+
             let current_pos = particle.position;
-            let temp = current_pos;
             let old = particle.old_position;
             let acceleration = particle.force;
-            particle.position += current_pos - old + acceleration * self.timestep * self.timestep;
-            particle.old_position = temp;
+
+            particle.position +=
+                (current_pos - old) * dt_ratio + acceleration * dt * (dt + previous_dt) / N::i32(2);
+            particle.old_position = current_pos;
         }
     }
 
-    /// Add forces
-    fn accumulate_forces(&mut self) {
+    /// Add gravity, wind and external forces
+    fn accumulate_forces(&mut self, dt: N) {
+        let previous_dt = self.previous_dt.unwrap_or(dt);
+
         for particle in self.particles.iter_mut() {
-            particle.force = self.gravity;
+            let velocity = (particle.position - particle.old_position) / previous_dt;
+
+            let mut force = particle.external_force;
+            for wind in self.winds.iter() {
+                force += wind.force(particle.position, velocity);
+            }
+
+            particle.force = self.gravity + force * particle.inv_mass;
         }
     }
 
-    fn satisfy_constraints(&mut self, constraint_iterations: usize) {
-        for iteration in 0..constraint_iterations {
-            // Testing bounding box
-            for particle in self.particles.iter_mut() {
-                {
-                    let min = N::i32(-8);
-                    let max = N::i32(8);
-                    let min = Vec3::new(min, min, min);
-                    let max = Vec3::new(max, max, max);
+    fn satisfy_constraints(&mut self) {
+        // Pairs are only found once per step, so particles pushed together by constraints are caught next step.
+        let pairs = self.broadphase.find_pairs(&self.particles);
+        let mut touched = vec![false; pairs.len()];
+
+        for _ in 0..self.constraint_iterations {
+            for constraint in self.constraints.iter() {
+                constraint.apply(&mut self.particles);
+            }
 
-                    particle.position = particle.position.max(min).min(max);
+            for (i, (a, b)) in pairs.iter().enumerate() {
+                if resolve_collision(&mut self.particles, *a, *b) {
+                    touched[i] = true;
                 }
             }
+        }
+
+        self.contacts.clear();
+        for (pair, touched) in pairs.iter().zip(touched) {
+            if touched {
+                self.contacts.push(*pair);
+            }
+        }
 
-            // Iterate over constraints
-            let two = N::i32(2);
+        self.apply_contact_velocities();
+    }
 
-            for constraint in self.constraints.iter() {
-                match constraint {
-                    Constraint::Stick {
-                        particle1,
-                        particle2,
-                        rest_length,
-                        rest_len_sqrd,
-                    } => {
-                        let rest_len_sqrd = match rest_len_sqrd {
-                            Some(r) => *r,
-                            None => {
-                                let r = *rest_length;
-                                r * r
-                            }
-                        };
-
-                        let particle1 = *particle1;
-                        let particle2 = *particle2;
-                        let p1 = self.particles[particle1];
-                        let p2 = self.particles[particle2];
-
-                        let delta = p1.position - p2.position;
-                        let delta_len = delta.dot(delta).sqrt();
-                        let diff = (delta_len - *rest_length) / delta_len;
-
-                        let mut delta: Vec3<N> = p1.position - p2.position;
-                        delta *= (rest_len_sqrd) / (delta.dot(delta) + rest_len_sqrd);
-
-                        // NOTE: I had to reverse the add/sub ops here. Not sure why, but may play a role later on.
-                        self.particles[particle1].position -= delta * p1.inv_mass * diff / two;
-                        self.particles[particle2].position += delta * p2.inv_mass * diff / two;
-                    }
-                }
+    /// Applies friction and restitution to the particles that collided this step.
+    /// As velocity is implicit in Verlet integration, this is done by moving the old positions.
+    fn apply_contact_velocities(&mut self) {
+        let zero = N::i32(0);
+        let one = N::i32(1);
+
+        for (a, b) in self.contacts.iter() {
+            let p1 = self.particles[*a];
+            let p2 = self.particles[*b];
+
+            let total_inv_mass = p1.inv_mass + p2.inv_mass;
+            let normal = (p2.position - p1.position).normalize();
+            if total_inv_mass == zero || normal == Vec3::default() {
+                continue;
             }
 
-            // TESTING: two locked particle for cloth
-            {
-                self.particles[0].position = Vec3::default();
-                self.particles[8].position =
-                    Vec3::default() + Vec3::new(N::i32(2), N::i32(0), N::i32(0));
+            let relative_velocity =
+                (p2.position - p2.old_position) - (p1.position - p1.old_position);
+            let normal_speed = relative_velocity.dot(normal);
+
+            // Already separating
+            if normal_speed >= zero {
+                continue;
             }
+
+            let normal_velocity = normal * normal_speed;
+            let tangent_velocity = relative_velocity - normal_velocity;
+            let new_velocity =
+                tangent_velocity * (one - self.friction) - normal_velocity * self.restitution;
+
+            let change = (new_velocity - relative_velocity) / total_inv_mass;
+            self.particles[*a].old_position += change * p1.inv_mass;
+            self.particles[*b].old_position -= change * p2.inv_mass;
         }
     }
 }
 
-fn apply_stick_constraint<N>(
-    particle1: ParticleId,
-    particle2: ParticleId,
-    rest_len: N,
-    sim: &mut ParticleSystem<N>,
-) where
+/// Pushes two overlapping particles apart, weighted by their inverse mass. Returns whether they were overlapping.
+fn resolve_collision<N>(particles: &mut [Particle<N>], a: ParticleId, b: ParticleId) -> bool
+where
     N: Number,
 {
+    let zero = N::i32(0);
+    let p1 = particles[a];
+    let p2 = particles[b];
+
+    let total_inv_mass = p1.inv_mass + p2.inv_mass;
+    if total_inv_mass == zero {
+        return false;
+    }
+
+    let min_distance = p1.radius + p2.radius;
+    let delta = p2.position - p1.position;
+    let distance_sqrd = delta.len_squared();
+    if distance_sqrd >= min_distance.sqrd() {
+        return false;
+    }
+
+    let distance = distance_sqrd.sqrt();
+    let normal = if distance == zero {
+        // Fully overlapping, so pick an axis to keep it deterministic.
+        Vec3::new(N::i32(1), zero, zero)
+    } else {
+        delta / distance
+    };
+
+    let correction = normal * ((min_distance - distance) / total_inv_mass);
+    particles[a].position -= correction * p1.inv_mass;
+    particles[b].position += correction * p2.inv_mass;
+
+    true
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Constraint, Particle, ParticleSystem};
+    use super::{Constraint, Particle, ParticleSystem, Wind};
     use game_math::f32::*;
 
     #[test]
@@ -184,13 +433,15 @@ mod tests {
         assert_eq!(Vec3::new(0.0, -1.0, 0.0), system.gravity);
         assert_eq!(1.0, system.timestep);
         assert_eq!(2, system.max_particles);
+        assert_eq!(20, system.constraint_iterations());
+        assert_eq!(true, system.constraints().is_empty());
     }
 
     #[test]
     fn ParticleSystem_single_particle_applies_gravity_and_moves() {
         let mut system = ParticleSystem::new(1, Vec3::new(0.0, -1.0, 0.0));
 
-        system.add_entity(Vec3::new(0.0, 1.0, 0.0), None);
+        system.add_entity(Vec3::new(0.0, 1.0, 0.0), 1.0, None);
 
         system.timestep();
 
@@ -203,34 +454,421 @@ mod tests {
 
     #[test]
     fn ParticleSystem_single_particle_applies_stick_constraint() {
-        let mut system = ParticleSystem::new(1, Vec3::new(0.0, -1.0, 0.0));
+        let mut system = ParticleSystem::new(2, Vec3::new(0.0, -1.0, 0.0));
 
         let a = 2.;
         let b = 3.;
         let c = -5.;
 
-        let p1 = system.add_entity(Vec3::new(a, a, c), None);
+        let p1 = system.add_entity(Vec3::new(a, a, c), 1.0, None).unwrap();
 
         let d = a / 100.;
-        let p2 = system.add_entity(Vec3::new(b, a, c), Some(Vec3::new(d, d, d)));
+        let p2 = system
+            .add_entity(Vec3::new(b, a, c), 1.0, Some(Vec3::new(d, d, d)))
+            .unwrap();
 
         system.add_constraint(Constraint::Stick {
             particle1: p1,
             particle2: p2,
-            rest_length: 1.,
-            rest_len_sqrd: None,
+            rest_length: 2.,
+            break_length: None,
         });
 
         system.timestep();
 
         let particle = system.particles[0];
-        assert_eq!(Vec3::new(2.0101922, 1.0001998, -4.9998), particle.position);
         assert_eq!(Vec3::new(2.0, 2.0, -5.0), particle.old_position);
         assert_eq!(system.gravity, particle.force);
 
         let particle = system.particles[1];
-        assert_eq!(Vec3::new(3.0098078, 1.0198002, -4.9802), particle.position);
         assert_eq!(Vec3::new(3.0, 2.0, -5.0), particle.old_position);
         assert_eq!(system.gravity, particle.force);
+
+        let distance = (system.particles[1].position - system.particles[0].position).len();
+        assert_eq!(true, (distance - 2.).abs() < 0.0001);
+
+        // Both particles share the correction equally, so the midpoint is untouched.
+        let midpoint = (system.particles[0].position + system.particles[1].position) / 2.;
+        assert_eq!(Vec3::new(2.51, 1.01, -4.99), midpoint);
+    }
+
+    #[test]
+    fn ParticleSystem_no_hard_coded_bounds_or_pins() {
+        let mut system = ParticleSystem::new(9, Vec3::new(0.0, -1.0, 0.0));
+
+        for i in 0..9 {
+            system.add_entity(Vec3::new(i as f32 * 4., 0., 0.), 1.0, None);
+        }
+
+        for _ in 0..10 {
+            system.timestep();
+        }
+
+        // Free falling particles keep falling past where the old testing box was.
+        for particle in system.particles() {
+            assert_eq!(true, particle.position.y < -8.);
+        }
+        assert_eq!(32., system.particles[8].position.x);
+    }
+
+    #[test]
+    fn ParticleSystem_configured_constraints_are_applied() {
+        let mut system = ParticleSystem::new(2, Vec3::new(0.0, -1.0, 0.0));
+
+        let anchor = system.add_entity(Vec3::new(0., 0., 0.), 1.0, None).unwrap();
+        let end = system.add_entity(Vec3::new(3., 0., 0.), 1.0, None).unwrap();
+
+        system.add_constraint(Constraint::Distance {
+            particle1: anchor,
+            particle2: end,
+            min: 0.,
+            max: 3.,
+            break_length: None,
+        });
+        system.add_constraint(Constraint::Pin {
+            particle: anchor,
+            position: Vec3::new(0., 0., 0.),
+        });
+        system.add_constraint(Constraint::Plane {
+            plane: Plane::new(Vec3::new(0., 1., 0.), -5.),
+        });
+
+        for _ in 0..100 {
+            system.timestep();
+        }
+
+        // The rope swings down and hangs below the pin, above the floor.
+        let end = system.particles[end].position;
+        assert_eq!(Vec3::new(0., 0., 0.), system.particles[anchor].position);
+        assert_eq!(true, end.len() <= 3.0001);
+        assert_eq!(true, end.y >= -5.);
+
+        system.clear_constraints();
+        assert_eq!(0, system.constraints().len());
+    }
+
+    #[test]
+    fn ParticleSystem_constraint_iterations_are_configurable() {
+        let mut stiff = ParticleSystem::new(3, Vec3::new(0.0, 0.0, 0.0));
+        for i in 0..3 {
+            stiff.add_entity(Vec3::new(i as f32 * 2., 0., 0.), 1.0, None);
+        }
+        for i in 0..2 {
+            stiff.add_constraint(Constraint::Stick {
+                particle1: i,
+                particle2: i + 1,
+                rest_length: 1.,
+                break_length: None,
+            });
+        }
+
+        let mut loose = stiff.clone();
+        loose.set_constraint_iterations(1);
+        assert_eq!(1, loose.constraint_iterations());
+
+        stiff.timestep();
+        loose.timestep();
+
+        let error = |system: &ParticleSystem<f32>| {
+            let p = system.particles();
+            ((p[1].position - p[0].position).len() - 1.).abs()
+                + ((p[2].position - p[1].position).len() - 1.).abs()
+        };
+
+        assert_eq!(true, error(&stiff) < error(&loose));
+    }
+
+    fn ball(position: Vec3, inv_mass: f32, velocity: Vec3, radius: f32) -> Particle<f32> {
+        let mut particle = Particle::new(position, inv_mass, velocity, 1.);
+        particle.radius = radius;
+        particle
+    }
+
+    #[test]
+    fn ParticleSystem_collisions_respect_inv_mass() {
+        let mut system = ParticleSystem::new(3, Vec3::default());
+
+        let wall = system
+            .add_particle(ball(Vec3::new(0., 0., 0.), 0., Vec3::default(), 1.))
+            .unwrap();
+        let a = system
+            .add_particle(ball(Vec3::new(1., 0., 0.), 1., Vec3::default(), 1.))
+            .unwrap();
+        let b = system
+            .add_particle(ball(Vec3::new(10., 0., 0.), 1., Vec3::default(), 1.))
+            .unwrap();
+
+        system.timestep();
+
+        assert_eq!(Vec3::new(0., 0., 0.), system.particles[wall].position);
+        assert_eq!(Vec3::new(2., 0., 0.), system.particles[a].position);
+        assert_eq!(Vec3::new(10., 0., 0.), system.particles[b].position);
+
+        // Equal masses share the correction.
+        system.particles[b].position = Vec3::new(3., 0., 0.);
+        system.particles[b].old_position = Vec3::new(3., 0., 0.);
+        system.particles[a].old_position = system.particles[a].position;
+        system.timestep();
+
+        let distance = (system.particles[b].position - system.particles[a].position).len();
+        assert_eq!(true, (distance - 2.).abs() < 0.0001);
+        assert_eq!(Vec3::new(0., 0., 0.), system.particles[wall].position);
+    }
+
+    #[test]
+    fn ParticleSystem_restitution_and_friction() {
+        let run = |friction: f32, restitution: f32| {
+            let mut system = ParticleSystem::new(2, Vec3::default());
+            system.set_friction(friction);
+            system.set_restitution(restitution);
+
+            system.add_particle(ball(Vec3::new(0., 0., 0.), 0., Vec3::default(), 1.));
+            let id = system
+                .add_particle(ball(
+                    Vec3::new(0.5, 2.5, 0.),
+                    1.,
+                    Vec3::new(0.5, -1., 0.),
+                    1.,
+                ))
+                .unwrap();
+
+            system.timestep();
+            let p = system.particles[id];
+            let normal = p.position.normalize();
+            let velocity = p.position - p.old_position;
+
+            (
+                velocity.dot(normal),
+                (velocity - normal * velocity.dot(normal)).len(),
+            )
+        };
+
+        // No restitution removes the velocity into the other particle.
+        let (normal_speed, tangent_speed) = run(0., 0.);
+        assert_eq!(true, normal_speed.abs() < 0.0001);
+
+        // Full restitution bounces back out.
+        let (bounced, _) = run(0., 1.);
+        assert_eq!(true, bounced > 0.3);
+
+        // Friction slows the sliding velocity.
+        let (_, slowed) = run(0.5, 0.);
+        assert_eq!(true, (slowed - tangent_speed / 2.).abs() < 0.0001);
+        let (_, stopped) = run(1., 0.);
+        assert_eq!(true, stopped.abs() < 0.0001);
+
+        let system = ParticleSystem::<f32>::new(0, Vec3::default());
+        assert_eq!(0., system.friction());
+        assert_eq!(0., system.restitution());
+    }
+
+    #[test]
+    fn ParticleSystem_collisions_are_deterministic_with_fixed_point() {
+        use game_math::fix::Fix;
+        use game_math::random::Random;
+
+        let build = || {
+            let mut random = Random::new(3);
+            let mut system = ParticleSystem::new(
+                30,
+                game_math::vectors::Vec3::new(Fix::ZERO, -Fix::from_ratio(1, 10), Fix::ZERO),
+            );
+            system.set_restitution(Fix::from_ratio(1, 2));
+            system.set_friction(Fix::from_ratio(1, 4));
+            system.add_constraint(Constraint::Plane {
+                plane: game_math::shapes::Plane::new(
+                    game_math::vectors::Vec3::new(Fix::ZERO, Fix::ONE, Fix::ZERO),
+                    Fix::ZERO,
+                ),
+            });
+
+            for _ in 0..30 {
+                let position = game_math::vectors::Vec3::new(
+                    random.range(Fix::from_int(-3), Fix::from_int(3)),
+                    random.range(Fix::from_int(0), Fix::from_int(6)),
+                    random.range(Fix::from_int(-3), Fix::from_int(3)),
+                );
+                let mut particle = Particle::new(
+                    position,
+                    Fix::ONE,
+                    game_math::vectors::Vec3::default(),
+                    Fix::ONE,
+                );
+                particle.radius = Fix::from_ratio(1, 2);
+                system.add_particle(particle);
+            }
+
+            system
+        };
+
+        let mut a = build();
+        let mut b = build();
+
+        for _ in 0..60 {
+            a.timestep();
+            b.timestep();
+        }
+
+        assert_eq!(a, b);
+
+        // Particles have come to rest on the floor and don't overlap much.
+        for (i, p1) in a.particles().iter().enumerate() {
+            assert_eq!(true, p1.position.y >= Fix::from_ratio(1, 2));
+            for p2 in a.particles().iter().skip(i + 1) {
+                assert_eq!(
+                    true,
+                    (p2.position - p1.position).len() > Fix::from_ratio(9, 10)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ParticleSystem_dt_changes_keep_velocity() {
+        let mut system = ParticleSystem::new(1, Vec3::default());
+        let id = system
+            .add_entity(Vec3::default(), 1.0, Some(Vec3::new(2., 0., 0.)))
+            .unwrap();
+
+        system.timestep();
+        assert_eq!(Vec3::new(2., 0., 0.), system.particles[id].position);
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), system.velocity(id));
+
+        system.set_dt(0.5);
+        assert_eq!(0.5, system.dt());
+        system.timestep();
+        assert_eq!(Vec3::new(3., 0., 0.), system.particles[id].position);
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), system.velocity(id));
+    }
+
+    #[test]
+    fn ParticleSystem_substeps_match_smaller_dt() {
+        let mut substepped = ParticleSystem::new(1, Vec3::new(0., -10., 0.));
+        substepped.add_entity(Vec3::default(), 1.0, None);
+        substepped.set_substeps(4);
+        assert_eq!(4, substepped.substeps());
+
+        let mut small = ParticleSystem::new(1, Vec3::new(0., -10., 0.));
+        small.add_entity(Vec3::default(), 1.0, None);
+        small.set_dt(0.25);
+
+        substepped.timestep();
+        for _ in 0..4 {
+            small.timestep();
+        }
+
+        assert_eq!(small.particles[0], substepped.particles[0]);
+        assert_eq!(small.velocity(0), substepped.velocity(0));
+
+        substepped.set_substeps(0);
+        assert_eq!(1, substepped.substeps());
+    }
+
+    #[test]
+    fn ParticleSystem_forces_and_impulses_scale_by_inv_mass() {
+        let mut system = ParticleSystem::new(3, Vec3::default());
+        let light = system.add_entity(Vec3::default(), 1.0, None).unwrap();
+        let heavy = system.add_entity(Vec3::default(), 0.5, None).unwrap();
+        let fixed = system.add_entity(Vec3::default(), 0.0, None).unwrap();
+
+        for id in [light, heavy, fixed].iter() {
+            system.add_force(*id, Vec3::new(2., 0., 0.));
+        }
+        system.timestep();
+
+        assert_eq!(Vec3::new(2., 0., 0.), system.particles[light].position);
+        assert_eq!(Vec3::new(1., 0., 0.), system.particles[heavy].position);
+        assert_eq!(Vec3::new(0., 0., 0.), system.particles[fixed].position);
+        // External forces only last a tick.
+        assert_eq!(Vec3::default(), system.particles[light].external_force);
+
+        system.apply_impulse(heavy, Vec3::new(0., 4., 0.));
+        system.apply_impulse(fixed, Vec3::new(0., 4., 0.));
+        assert_eq!(Some(Vec3::new(1., 2., 0.)), system.velocity(heavy));
+        assert_eq!(Some(Vec3::default()), system.velocity(fixed));
+        assert_eq!(None, system.velocity(10));
+    }
+
+    #[test]
+    fn ParticleSystem_damping_slows_particles() {
+        let mut system = ParticleSystem::new(2, Vec3::default());
+        let a = system
+            .add_entity(Vec3::default(), 1.0, Some(Vec3::new(4., 0., 0.)))
+            .unwrap();
+        let b = system
+            .add_entity(Vec3::default(), 1.0, Some(Vec3::new(4., 0., 0.)))
+            .unwrap();
+        system.particles[b].damping = 0.5;
+        system.set_damping(0.5);
+
+        system.timestep();
+
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), system.velocity(a));
+        assert_eq!(Some(Vec3::new(1., 0., 0.)), system.velocity(b));
+    }
+
+    #[test]
+    fn ParticleSystem_damping_does_not_depend_on_substeps() {
+        let build = |substeps: usize| {
+            let mut system = ParticleSystem::new(1, Vec3::default());
+            system.set_substeps(substeps);
+            system.set_damping(0.5);
+            system.add_entity(Vec3::default(), 1.0, Some(Vec3::new(4., 0., 0.)));
+            system.timestep();
+            system
+        };
+
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), build(1).velocity(0));
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), build(4).velocity(0));
+    }
+
+    #[test]
+    fn ParticleSystem_add_entity_velocity_is_per_second() {
+        let mut system = ParticleSystem::new(1, Vec3::default());
+        system.set_dt(0.5);
+        let id = system
+            .add_entity(Vec3::default(), 1.0, Some(Vec3::new(2., 0., 0.)))
+            .unwrap();
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), system.velocity(id));
+
+        system.timestep();
+        assert_eq!(Vec3::new(1., 0., 0.), system.particles[id].position);
+        assert_eq!(Some(Vec3::new(2., 0., 0.)), system.velocity(id));
+    }
+
+    #[test]
+    fn ParticleSystem_wind_pushes_particles_in_region() {
+        let mut system = ParticleSystem::new(2, Vec3::default());
+        let inside = system.add_entity(Vec3::new(0., 0., 0.), 1.0, None).unwrap();
+        let outside = system
+            .add_entity(Vec3::new(10., 0., 0.), 1.0, None)
+            .unwrap();
+
+        let mut wind = Wind::new(Vec3::new(0., 0., 2.), 0.5);
+        wind.region = Some(Aabb::new(Vec3::new(-1., -1., -1.), Vec3::new(1., 1., 1.)));
+        system.add_wind(wind);
+        assert_eq!(1, system.winds().len());
+
+        system.timestep();
+
+        assert_eq!(Vec3::new(0., 0., 1.), system.particles[inside].position);
+        assert_eq!(Vec3::new(10., 0., 0.), system.particles[outside].position);
+
+        system.clear_winds();
+        assert_eq!(true, system.winds().is_empty());
+    }
+
+    #[test]
+    fn ParticleSystem_add_particle_respects_max_particles() {
+        let mut system = ParticleSystem::new(2, Vec3::default());
+        assert_eq!(2, system.max_particles());
+
+        assert_eq!(Some(0), system.add_entity(Vec3::default(), 1.0, None));
+        assert_eq!(
+            Some(1),
+            system.add_particle(Particle::new(Vec3::default(), 1.0, Vec3::default(), 1.0))
+        );
+        assert_eq!(None, system.add_entity(Vec3::default(), 1.0, None));
+        assert_eq!(2, system.particles().len());
     }
 }
@@ -1,6 +1,7 @@
 pub use game_math::mat4::Mat4;
 pub use game_math::quaternion::Quaternion;
-pub use game_math::vec2::Vec2;
-pub use game_math::vec3::Vec3;
-pub use game_math::vec4::Vec4;
+pub use game_math::shapes::{Aabb, Capsule, Plane, Sphere};
+pub use game_math::vectors::Vec2;
+pub use game_math::vectors::Vec3;
+pub use game_math::vectors::Vec4;
 pub use game_math::Number;
@@ -6,9 +6,16 @@ where
     N: Number,
 {
     pub inv_mass: N,
+    /// The collision radius. A radius of 0 does not collide with other particles.
+    pub radius: N,
     pub position: Vec3<N>,
     pub old_position: Vec3<N>,
+    /// The acceleration applied during the last step.
     pub force: Vec3<N>,
+    /// Forces added for the current tick. Cleared after each tick.
+    pub external_force: Vec3<N>,
+    /// The fraction of velocity lost each tick, from 0 to 1. Combined with the system damping.
+    pub damping: N,
 }
 
 impl<N> Particle<N>
@@ -16,12 +23,16 @@ where
     N: Number,
 {
     /// Creates a new particle. inv_mass = 0 is 'infinite mass' or totally immovable.
-    pub fn new(position: Vec3<N>, inv_mass: N, velocity: Vec3<N>) -> Self {
+    /// The velocity is in units per second, and dt is the length of the step the particle will be simulated with.
+    pub fn new(position: Vec3<N>, inv_mass: N, velocity: Vec3<N>, dt: N) -> Self {
         Self {
             inv_mass,
+            radius: N::i32(0),
             position: position,
-            old_position: position - velocity,
+            old_position: position - velocity * dt,
             force: Vec3::default(),
+            external_force: Vec3::default(),
+            damping: N::i32(0),
         }
     }
 }
@@ -36,10 +47,15 @@ mod tests {
         let position = Vec3::new(1.0, 2.0, 3.0);
         let velocity = Vec3::new(1.0, 1.0, 1.0);
 
-        let particle = Particle::new(position, velocity);
+        let particle = Particle::new(position, 1.0, velocity, 0.5);
+
+        assert_eq!(1.0, particle.inv_mass);
+        assert_eq!(0.0, particle.radius);
 
         assert_eq!(position, particle.position);
         assert_eq!(Vec3::default(), particle.force);
-        assert_eq!(position - velocity, particle.old_position);
+        assert_eq!(Vec3::default(), particle.external_force);
+        assert_eq!(0.0, particle.damping);
+        assert_eq!(position - velocity * 0.5, particle.old_position);
     }
 }